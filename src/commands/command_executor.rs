@@ -1,139 +1,1045 @@
-use std::{env, path::Path, process::Command};
+use std::fs::{File, OpenOptions};
+use std::io::{self, PipeReader, PipeWriter};
+use std::os::fd::AsFd;
+use std::{
+    env,
+    path::Path,
+    process::{Child, Command, Stdio},
+};
 
 /// Execute given input string as a command.
+/// Command lines are parsed like the shell does: words are split on whitespace and may be quoted
+/// with `'` or `"` or escaped with `\`, `$NAME`, `${NAME}` and a leading `~` are expanded,
+/// commands are piped with `|`, redirected with `<`, `>`, `>>`, `2>` and `2>&1`,
+/// and chained with `&&`, `||` and `;`. `exit` stops the line, the commands after it are not run.
+/// Lines using other shell syntax, such as globs, brace expansion, subshells or command substitution,
+/// are run by `sh` instead.
 ///
 /// # Arguments
 ///
 /// * `input` - The input command.
-pub fn execute_command(input: &str) {
-    let mut parts = input.trim().split_whitespace();
-    let command = parts.next().unwrap();
-    let args = parts;
-
-    match command {
-        "cd" => {
-            let new_dir = args.peekable().peek().map_or("/", |x| *x);
-            let root = Path::new(new_dir);
-            if let Err(e) = env::set_current_dir(&root) {
-                eprintln!("{}", e);
-            }
-            println!("{}", env::current_dir().unwrap().to_str().unwrap());
-        }
-        "exit" => return,
-        command => {
-            let child = Command::new(command).args(args).spawn();
-
-            // gracefully handle malformed user input
-            match child {
-                Ok(mut child) => {
-                    let _ = child.wait();
+///
+/// # Returns
+///
+/// The exit code of the command, `None` if it could not be started or was killed by a signal.
+/// For a pipeline, the exit code of its last command, and for a command list, the one of
+/// the last pipeline run. `exit` returns the code given to it, or the one of the previous pipeline.
+pub fn execute_command(input: &str) -> Option<i32> {
+    let command_list = match tokenize(input).and_then(parse_command_list) {
+        Ok(command_list) => command_list,
+        Err(LineError::Unsupported) => return run_in_shell(input),
+        Err(LineError::Syntax(message)) => {
+            eprintln!("Syntax error: {message}");
+            return None;
+        }
+    };
+
+    let mut exit_status = Some(0);
+    for (connector, pipeline) in &command_list {
+        let runs = match connector {
+            Connector::Always => true,
+            Connector::And => exit_status == Some(0),
+            Connector::Or => exit_status != Some(0),
+        };
+        if !runs {
+            continue;
+        }
+        if let [command] = pipeline.as_slice() {
+            if command.words[0] == "exit" {
+                return match command.words.get(1) {
+                    Some(code) => code.parse().ok(),
+                    None => exit_status,
+                };
+            }
+        }
+        exit_status = run_pipeline(pipeline);
+    }
+    exit_status
+}
+
+/// Runs a shell command without showing it to the user, to get what it prints.
+///
+/// # Arguments
+///
+/// * `input` - The shell command, e.g. `git branch`.
+///
+/// # Returns
+///
+/// What the command printed on stdout, `None` if it failed.
+pub fn command_output(input: &str) -> Option<String> {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(input)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Why a command line can not be run by `execute_command` itself.
+#[derive(Debug, PartialEq)]
+enum LineError {
+    /// The line uses shell syntax that is not parsed, such as globs or subshells
+    Unsupported,
+    /// The line is invalid, e.g. `make &&`
+    Syntax(String),
+}
+
+/// A word or an operator of a command line.
+#[derive(Debug, PartialEq)]
+enum Token {
+    /// A word, with its quotes removed and its variables expanded
+    Word(String),
+    /// `<`, `>`, `>>` or `>&`, along with the file descriptor they apply to
+    Redirect(i32, RedirectKind),
+    /// `|`
+    Pipe,
+    /// `&&`, `||` or `;`
+    Connector(Connector),
+}
+
+/// The kinds of redirections, the file or file descriptor they redirect to follows them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum RedirectKind {
+    /// `< file`
+    Input,
+    /// `> file`
+    Output,
+    /// `>> file`
+    Append,
+    /// `>&2`, `2>&1`
+    Duplicate(i32),
+}
+
+/// How a pipeline of a command list follows the previous one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Connector {
+    /// `;`, or the first pipeline
+    Always,
+    /// `&&`, runs if the previous pipeline succeeded
+    And,
+    /// `||`, runs if the previous pipeline failed
+    Or,
+}
+
+/// A redirection of a simple command.
+#[derive(Debug, PartialEq)]
+enum Redirection {
+    /// Reads stdin from a file
+    Input(String),
+    /// Writes stdout (1) or stderr (2) to a file, appending to it or truncating it
+    Output { fd: i32, path: String, append: bool },
+    /// Writes `fd` where `target` is written, e.g. `2>&1`
+    Duplicate { fd: i32, target: i32 },
+}
+
+/// A program along with its arguments and redirections, e.g. `grep foo > out.txt`.
+#[derive(Debug, Default, PartialEq)]
+struct SimpleCommand {
+    words: Vec<String>,
+    redirections: Vec<Redirection>,
+}
+
+/// Commands joined by `|`, the output of each one is the input of the next one.
+type Pipeline = Vec<SimpleCommand>;
+
+/// Splits a command line into words and operators.
+///
+/// # Arguments
+///
+/// * `input` - The command line.
+///
+/// # Returns
+///
+/// The tokens of the line, `LineError::Unsupported` if it uses syntax that is not parsed.
+fn tokenize(input: &str) -> Result<Vec<Token>, LineError> {
+    let mut tokens = Vec::new();
+    // the word being read, `None` between words, `Some("")` after `''`
+    let mut word: Option<String> = None;
+    let mut word_quoted = false;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                tokens.extend(word.take().map(Token::Word));
+                word_quoted = false;
+            }
+            '#' if word.is_none() => break,
+            '\'' => {
+                let text = word.get_or_insert_with(String::new);
+                word_quoted = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => text.push(c),
+                        None => return Err(LineError::Syntax("the quote is never closed".into())),
+                    }
+                }
+            }
+            '"' => {
+                let text = word.get_or_insert_with(String::new);
+                word_quoted = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c) if matches!(c, '"' | '\\' | '$' | '`') => text.push(c),
+                            Some(c) => {
+                                text.push('\\');
+                                text.push(c);
+                            }
+                            None => {
+                                return Err(LineError::Syntax("the quote is never closed".into()))
+                            }
+                        },
+                        Some('$') => text.push_str(&expand_variable(&mut chars)?),
+                        Some('`') => return Err(LineError::Unsupported),
+                        Some(c) => text.push(c),
+                        None => return Err(LineError::Syntax("the quote is never closed".into())),
+                    }
+                }
+            }
+            '\\' => {
+                let text = word.get_or_insert_with(String::new);
+                word_quoted = true;
+                text.extend(chars.next());
+            }
+            '$' => {
+                let value = expand_variable(&mut chars)?;
+                word.get_or_insert_with(String::new).push_str(&value);
+            }
+            '~' if word.is_none()
+                && chars.peek().is_none_or(|&c| c == '/' || c.is_whitespace()) =>
+            {
+                let home = env::var("HOME").unwrap_or_else(|_| "~".to_owned());
+                word = Some(home);
+            }
+            '|' => {
+                tokens.extend(word.take().map(Token::Word));
+                match chars.next_if(|&c| c == '|' || c == '&') {
+                    Some('|') => tokens.push(Token::Connector(Connector::Or)),
+                    Some(_) => return Err(LineError::Unsupported),
+                    None => tokens.push(Token::Pipe),
                 }
-                Err(e) => eprintln!("Error: {}", e),
-            };
-        }
-    }
-}
-
-// use std::{
-//     env,
-//     path::Path,
-//     process::{exit, Child, Command, Stdio},
-// };
-
-// pub struct CommandExecutor {}
-
-// impl CommandExecutor {
-//     pub fn new() -> Self {
-//         CommandExecutor {}
-//     }
-
-//     pub fn execute_command(&self, command: &str) {
-//         print!(">>");
-//         let mut parts = command.split_whitespace();
-//         if let Some(command_name) = parts.next() {
-//             let mut process = Command::new(command_name);
-//             process.args(parts);
-
-//             let output = match process.output() {
-//                 Ok(output) => output,
-//                 Err(error) => {
-//                     eprintln!("Failed to execute command '{}': {}", command, error);
-//                     exit(1);
-//                 }
-//             };
-
-//             if !output.stdout.is_empty() {
-//                 let stdout = String::from_utf8(output.stdout).unwrap();
-//                 println!("{}", stdout);
-//             }
-
-//             if !output.stderr.is_empty() {
-//                 let stderr = String::from_utf8_lossy(&output.stderr);
-//                 eprintln!("{}", stderr);
-//             }
-//         } else {
-//             println!("Invalid command");
-//         }
-//     }
-// }
-// incl piping
-// fn execute_command(input: &str) {
-//     // must be peekable so we know when we are on the last command
-//     let mut commands = input.trim().split(" | ").peekable();
-//     let mut previous_command = None;
-
-//     while let Some(command) = commands.next() {
-//         let mut parts = command.trim().split_whitespace();
-//         let command = parts.next().unwrap();
-//         let args = parts;
-
-//         match command {
-//             "cd" => {
-//                 let new_dir = args.peekable().peek().map_or("/", |x| *x);
-//                 let root = Path::new(new_dir);
-//                 if let Err(e) = env::set_current_dir(&root) {
-//                     eprintln!("{}", e);
-//                 }
-
-//                 previous_command = None;
-//             }
-//             "exit" => return,
-//             command => {
-//                 let stdin = previous_command.map_or(Stdio::inherit(), |output: Child| {
-//                     Stdio::from(output.stdout.unwrap())
-//                 });
-
-//                 let stdout = if commands.peek().is_some() {
-//                     // there is another command piped behind this one
-//                     // prepare to send output to the next command
-//                     Stdio::piped()
-//                 } else {
-//                     // there are no more commands piped behind this one
-//                     // send output to shell stdout
-//                     Stdio::inherit()
-//                 };
-
-//                 let output = Command::new(command)
-//                     .args(args)
-//                     .stdin(stdin)
-//                     .stdout(stdout)
-//                     .spawn();
-
-//                 match output {
-//                     Ok(output) => {
-//                         previous_command = Some(output);
-//                     }
-//                     Err(e) => {
-//                         previous_command = None;
-//                         eprintln!("{}", e);
-//                     }
-//                 };
-//             }
-//         }
-//     }
-
-//     if let Some(mut final_command) = previous_command {
-//         // block until the final command has finished
-//         final_command.wait();
-//     }
-// }
+            }
+            '&' => {
+                tokens.extend(word.take().map(Token::Word));
+                if chars.next_if_eq(&'&').is_none() {
+                    // background jobs and `&>`
+                    return Err(LineError::Unsupported);
+                }
+                tokens.push(Token::Connector(Connector::And));
+            }
+            ';' => {
+                tokens.extend(word.take().map(Token::Word));
+                tokens.push(Token::Connector(Connector::Always));
+            }
+            '<' | '>' => {
+                // `2>` redirects the file descriptor written right before the operator
+                let fd = match &word {
+                    Some(digits)
+                        if !word_quoted
+                            && !digits.is_empty()
+                            && digits.chars().all(|c| c.is_ascii_digit()) =>
+                    {
+                        let fd = digits.parse().map_err(|_| LineError::Unsupported)?;
+                        word = None;
+                        fd
+                    }
+                    _ => {
+                        tokens.extend(word.take().map(Token::Word));
+                        if c == '<' {
+                            0
+                        } else {
+                            1
+                        }
+                    }
+                };
+                word_quoted = false;
+                let kind = match (c, chars.peek()) {
+                    ('<', Some('<' | '&' | '>')) => return Err(LineError::Unsupported),
+                    ('<', _) => RedirectKind::Input,
+                    ('>', Some('>')) => {
+                        chars.next();
+                        RedirectKind::Append
+                    }
+                    ('>', Some('&')) => {
+                        chars.next();
+                        let mut target = String::new();
+                        while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                            target.push(digit);
+                        }
+                        RedirectKind::Duplicate(target.parse().map_err(|_| LineError::Unsupported)?)
+                    }
+                    _ => {
+                        chars.next_if_eq(&'|');
+                        RedirectKind::Output
+                    }
+                };
+                tokens.push(Token::Redirect(fd, kind));
+            }
+            // globs, brace expansion, subshells, groups and command substitution
+            '*' | '?' | '[' | ']' | '{' | '}' | '(' | ')' | '`' => {
+                return Err(LineError::Unsupported)
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    tokens.extend(word.map(Token::Word));
+    Ok(tokens)
+}
+
+/// Expands the variable following a `$`: `$NAME` or `${NAME}`. Unset variables are empty.
+///
+/// # Arguments
+///
+/// * `chars` - The characters of the line, right after the `$`.
+///
+/// # Returns
+///
+/// The value of the variable, `$` alone if no name follows,
+/// or `LineError::Unsupported` for special parameters and `$(...)`.
+fn expand_variable(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<String, LineError> {
+    let is_name_char = |c: &char| c.is_ascii_alphanumeric() || *c == '_';
+    let name: String = if chars.next_if_eq(&'{').is_some() {
+        let name: String = std::iter::from_fn(|| chars.next_if(is_name_char)).collect();
+        if chars.next_if_eq(&'}').is_none() {
+            return Err(LineError::Unsupported);
+        }
+        name
+    } else {
+        std::iter::from_fn(|| chars.next_if(is_name_char)).collect()
+    };
+
+    if name.is_empty() {
+        return match chars.peek() {
+            Some('(' | '?' | '$' | '!' | '#' | '@' | '*' | '-') => Err(LineError::Unsupported),
+            _ => Ok("$".to_owned()),
+        };
+    }
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        return Err(LineError::Unsupported);
+    }
+    Ok(env::var(&name).unwrap_or_default())
+}
+
+/// Groups the tokens of a line into pipelines of simple commands, chained by `&&`, `||` and `;`.
+///
+/// # Arguments
+///
+/// * `tokens` - The tokens of the line, see `tokenize`.
+///
+/// # Returns
+///
+/// The pipelines, along with how each one follows the previous one.
+fn parse_command_list(tokens: Vec<Token>) -> Result<Vec<(Connector, Pipeline)>, LineError> {
+    let mut command_list = Vec::new();
+    let mut connector = Connector::Always;
+    let mut pipeline: Pipeline = Vec::new();
+    let mut command = SimpleCommand::default();
+    let mut tokens = tokens.into_iter().peekable();
+
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Word(word) => {
+                // `NAME=value command` sets an environment variable
+                let is_assignment = command.words.is_empty()
+                    && word.split_once('=').is_some_and(|(name, _)| {
+                        !name.is_empty()
+                            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                    });
+                if is_assignment {
+                    return Err(LineError::Unsupported);
+                }
+                command.words.push(word);
+            }
+            Token::Redirect(fd, kind) => {
+                let redirection = match (fd, kind) {
+                    (1 | 2, RedirectKind::Duplicate(target @ (1 | 2))) => {
+                        Redirection::Duplicate { fd, target }
+                    }
+                    (0, RedirectKind::Input)
+                    | (1 | 2, RedirectKind::Output | RedirectKind::Append) => {
+                        let Some(Token::Word(path)) = tokens.next() else {
+                            return Err(LineError::Syntax(
+                                "a redirection is missing its file".into(),
+                            ));
+                        };
+                        match kind {
+                            RedirectKind::Input => Redirection::Input(path),
+                            _ => Redirection::Output {
+                                fd,
+                                path,
+                                append: kind == RedirectKind::Append,
+                            },
+                        }
+                    }
+                    // other file descriptors
+                    _ => return Err(LineError::Unsupported),
+                };
+                command.redirections.push(redirection);
+            }
+            Token::Pipe => {
+                check_redirected_command(&command)?;
+                if command.words.is_empty() {
+                    return Err(LineError::Syntax(
+                        "`|` is missing a command before it".into(),
+                    ));
+                }
+                if tokens
+                    .peek()
+                    .is_none_or(|token| matches!(token, Token::Pipe | Token::Connector(_)))
+                {
+                    return Err(LineError::Syntax(
+                        "`|` is missing a command after it".into(),
+                    ));
+                }
+                pipeline.push(std::mem::take(&mut command));
+            }
+            Token::Connector(next_connector) => {
+                check_redirected_command(&command)?;
+                if command.words.is_empty() {
+                    return Err(LineError::Syntax(format!(
+                        "{} is missing a command before it",
+                        connector_name(next_connector)
+                    )));
+                }
+                pipeline.push(std::mem::take(&mut command));
+                command_list.push((connector, std::mem::take(&mut pipeline)));
+                connector = next_connector;
+            }
+        }
+    }
+
+    check_redirected_command(&command)?;
+    if command.words.is_empty() {
+        // a trailing `;` ends the line like a newline
+        if connector != Connector::Always {
+            return Err(LineError::Syntax(format!(
+                "{} is missing a command after it",
+                connector_name(connector)
+            )));
+        }
+    } else {
+        pipeline.push(command);
+        command_list.push((connector, pipeline));
+    }
+    Ok(command_list)
+}
+
+/// Checks that a command with redirections has a program to run, e.g. not `> out.txt`.
+///
+/// # Arguments
+///
+/// * `command` - The command being read.
+///
+/// # Returns
+///
+/// A syntax error if the command only has redirections.
+fn check_redirected_command(command: &SimpleCommand) -> Result<(), LineError> {
+    if command.words.is_empty() && !command.redirections.is_empty() {
+        return Err(LineError::Syntax(
+            "a redirection is missing its command".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Gets how a connector is written, for error messages.
+fn connector_name(connector: Connector) -> &'static str {
+    match connector {
+        Connector::Always => "`;`",
+        Connector::And => "`&&`",
+        Connector::Or => "`||`",
+    }
+}
+
+/// Runs a pipeline: its commands are started together, each one reading what the previous one writes.
+/// `cd` is run by cmd-cat itself when it is alone.
+///
+/// # Arguments
+///
+/// * `pipeline` - The commands of the pipeline.
+///
+/// # Returns
+///
+/// The exit code of the last command, `None` if it could not be started or was killed by a signal.
+fn run_pipeline(pipeline: &[SimpleCommand]) -> Option<i32> {
+    if let [command] = pipeline {
+        if command.words[0] == "cd" {
+            return Some(change_directory(command.words.get(1)));
+        }
+    }
+
+    let mut children: Vec<Child> = Vec::new();
+    let mut previous_output: Option<PipeReader> = None;
+    let mut last_started = false;
+    for (index, command) in pipeline.iter().enumerate() {
+        let stdin = previous_output
+            .take()
+            .map_or_else(Stdio::inherit, Stdio::from);
+        let stdout = if index + 1 < pipeline.len() {
+            // there is another command piped behind this one
+            match io::pipe() {
+                Ok((reader, writer)) => {
+                    previous_output = Some(reader);
+                    Target::Pipe(writer)
+                }
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    break;
+                }
+            }
+        } else {
+            Target::Terminal(1)
+        };
+
+        // gracefully handle malformed user input
+        match spawn_command(command, stdin, stdout) {
+            Ok(child) => {
+                children.push(child);
+                last_started = index + 1 == pipeline.len();
+            }
+            Err(e) => eprintln!("Error: {}", e),
+        }
+    }
+
+    // block until all of the commands have finished
+    let exit_codes: Vec<Option<i32>> = children
+        .iter_mut()
+        .map(|child| child.wait().ok().and_then(|status| status.code()))
+        .collect();
+    if !last_started {
+        return None;
+    }
+    exit_codes.last().copied().flatten()
+}
+
+/// Changes the working directory of cmd-cat and prints the new one.
+///
+/// # Arguments
+///
+/// * `directory` - The directory, `/` if not given.
+///
+/// # Returns
+///
+/// The exit code, 1 if the directory could not be changed.
+fn change_directory(directory: Option<&String>) -> i32 {
+    let root = Path::new(directory.map_or("/", String::as_str));
+    let exit_status = match env::set_current_dir(root) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    };
+    println!("{}", env::current_dir().unwrap().to_str().unwrap());
+    exit_status
+}
+
+/// Where stdout or stderr of a command is written.
+enum Target {
+    /// The stdout (1) or stderr (2) of cmd-cat
+    Terminal(i32),
+    /// The next command of the pipeline
+    Pipe(PipeWriter),
+    File(File),
+}
+
+impl Target {
+    /// Duplicates the target, for `2>&1` and `>&2`.
+    fn try_clone(&self) -> io::Result<Target> {
+        Ok(match self {
+            Target::Terminal(fd) => Target::Terminal(*fd),
+            Target::Pipe(writer) => Target::Pipe(writer.try_clone()?),
+            Target::File(file) => Target::File(file.try_clone()?),
+        })
+    }
+
+    /// Converts the target to the stdio of the file descriptor `fd` of a command.
+    fn into_stdio(self, fd: i32) -> io::Result<Stdio> {
+        Ok(match self {
+            Target::Terminal(terminal_fd) if terminal_fd == fd => Stdio::inherit(),
+            Target::Terminal(1) => Stdio::from(io::stdout().as_fd().try_clone_to_owned()?),
+            Target::Terminal(_) => Stdio::from(io::stderr().as_fd().try_clone_to_owned()?),
+            Target::Pipe(writer) => Stdio::from(writer),
+            Target::File(file) => Stdio::from(file),
+        })
+    }
+}
+
+/// Starts a command of a pipeline, with its redirections applied from left to right.
+///
+/// # Arguments
+///
+/// * `command` - The command.
+/// * `stdin` - Where the command reads from, unless redirected.
+/// * `stdout` - Where the command writes to, unless redirected.
+///
+/// # Returns
+///
+/// The started command, or the error of the command or of one of its files.
+fn spawn_command(command: &SimpleCommand, stdin: Stdio, stdout: Target) -> io::Result<Child> {
+    let with_path =
+        |path: &str, err: io::Error| io::Error::new(err.kind(), format!("{path}: {err}"));
+    let mut stdin = stdin;
+    let mut stdout = stdout;
+    let mut stderr = Target::Terminal(2);
+    for redirection in &command.redirections {
+        let (fd, target) = match redirection {
+            Redirection::Input(path) => {
+                stdin = Stdio::from(File::open(path).map_err(|err| with_path(path, err))?);
+                continue;
+            }
+            Redirection::Output { fd, path, append } => {
+                let file = OpenOptions::new()
+                    .write(true)
+                    .create(true)
+                    .append(*append)
+                    .truncate(!append)
+                    .open(path)
+                    .map_err(|err| with_path(path, err))?;
+                (*fd, Target::File(file))
+            }
+            Redirection::Duplicate { fd, target } => {
+                let target = match target {
+                    1 => stdout.try_clone()?,
+                    _ => stderr.try_clone()?,
+                };
+                (*fd, target)
+            }
+        };
+        match fd {
+            1 => stdout = target,
+            _ => stderr = target,
+        }
+    }
+
+    Command::new(&command.words[0])
+        .args(&command.words[1..])
+        .stdin(stdin)
+        .stdout(stdout.into_stdio(1)?)
+        .stderr(stderr.into_stdio(2)?)
+        .spawn()
+        .map_err(|err| with_path(&command.words[0], err))
+}
+
+/// Runs a command line with `sh`, for the shell syntax `execute_command` does not parse.
+///
+/// # Arguments
+///
+/// * `input` - The command line.
+///
+/// # Returns
+///
+/// The exit code of the command line, `None` if `sh` could not be started or was killed by a signal.
+fn run_in_shell(input: &str) -> Option<i32> {
+    match Command::new("sh").arg("-c").arg(input).status() {
+        Ok(status) => status.code(),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(text: &str) -> Token {
+        Token::Word(text.to_owned())
+    }
+
+    fn simple_command(words: &[&str], redirections: Vec<Redirection>) -> SimpleCommand {
+        SimpleCommand {
+            words: words.iter().map(|word| word.to_string()).collect(),
+            redirections,
+        }
+    }
+
+    fn parse(input: &str) -> Result<Vec<(Connector, Pipeline)>, LineError> {
+        tokenize(input).and_then(parse_command_list)
+    }
+
+    #[test]
+    fn tokenize_removes_quotes_and_escapes() {
+        assert_eq!(
+            tokenize(r#"echo 'a  b' "c \"d\" \n" e\ f '' g#h # comment"#),
+            Ok(vec![
+                word("echo"),
+                word("a  b"),
+                word(r#"c "d" \n"#),
+                word("e f"),
+                word(""),
+                word("g#h"),
+            ])
+        );
+    }
+
+    #[test]
+    fn tokenize_expands_variables_and_home() {
+        env::set_var("CMD_CAT_TEST_NAME", "cat");
+        let home = env::var("HOME").unwrap_or_else(|_| "~".to_owned());
+        assert_eq!(
+            tokenize("echo $CMD_CAT_TEST_NAME ${CMD_CAT_TEST_NAME}s \"$CMD_CAT_TEST_NAME\" '$CMD_CAT_TEST_NAME' $ ~/x a~"),
+            Ok(vec![
+                word("echo"),
+                word("cat"),
+                word("cats"),
+                word("cat"),
+                word("$CMD_CAT_TEST_NAME"),
+                word("$"),
+                word(&format!("{home}/x")),
+                word("a~"),
+            ])
+        );
+    }
+
+    #[test]
+    fn tokenize_reads_operators() {
+        assert_eq!(
+            tokenize("a|b||c&&d;e<f>g>>h 2>i 2>&1 >&2 '2'>j"),
+            Ok(vec![
+                word("a"),
+                Token::Pipe,
+                word("b"),
+                Token::Connector(Connector::Or),
+                word("c"),
+                Token::Connector(Connector::And),
+                word("d"),
+                Token::Connector(Connector::Always),
+                word("e"),
+                Token::Redirect(0, RedirectKind::Input),
+                word("f"),
+                Token::Redirect(1, RedirectKind::Output),
+                word("g"),
+                Token::Redirect(1, RedirectKind::Append),
+                word("h"),
+                Token::Redirect(2, RedirectKind::Output),
+                word("i"),
+                Token::Redirect(2, RedirectKind::Duplicate(1)),
+                Token::Redirect(1, RedirectKind::Duplicate(2)),
+                word("2"),
+                Token::Redirect(1, RedirectKind::Output),
+                word("j"),
+            ])
+        );
+    }
+
+    #[test]
+    fn tokenize_rejects_unclosed_quotes() {
+        let error = Err(LineError::Syntax("the quote is never closed".into()));
+        assert_eq!(tokenize("echo 'abc"), error);
+        assert_eq!(tokenize("echo \"abc"), error);
+        assert_eq!(tokenize("echo \"abc\\"), error);
+    }
+
+    #[test]
+    fn tokenize_leaves_other_syntax_to_the_shell() {
+        for input in [
+            "ls *.rs",
+            "echo $(date)",
+            "echo `date`",
+            "echo \"`date`\"",
+            "sleep 1 &",
+            "(cd /tmp)",
+            "echo $?",
+            "echo $1",
+            "ls &> out",
+            "cat <<EOF",
+            "a |& b",
+            "echo ${HOME:-x}",
+            "echo {a,b}",
+            "ls file[12].txt",
+            "find . -exec rm {} ;",
+        ] {
+            assert_eq!(tokenize(input), Err(LineError::Unsupported), "{input}");
+        }
+    }
+
+    #[test]
+    fn parse_builds_pipelines_and_command_lists() {
+        assert_eq!(
+            parse("ps aux | grep foo > out.txt 2>&1 && echo done || echo failed; ls"),
+            Ok(vec![
+                (
+                    Connector::Always,
+                    vec![
+                        simple_command(&["ps", "aux"], Vec::new()),
+                        simple_command(
+                            &["grep", "foo"],
+                            vec![
+                                Redirection::Output {
+                                    fd: 1,
+                                    path: "out.txt".to_owned(),
+                                    append: false,
+                                },
+                                Redirection::Duplicate { fd: 2, target: 1 },
+                            ]
+                        ),
+                    ]
+                ),
+                (
+                    Connector::And,
+                    vec![simple_command(&["echo", "done"], Vec::new())]
+                ),
+                (
+                    Connector::Or,
+                    vec![simple_command(&["echo", "failed"], Vec::new())]
+                ),
+                (Connector::Always, vec![simple_command(&["ls"], Vec::new())]),
+            ])
+        );
+        assert_eq!(
+            parse("sort < in.txt >> out.txt;"),
+            Ok(vec![(
+                Connector::Always,
+                vec![simple_command(
+                    &["sort"],
+                    vec![
+                        Redirection::Input("in.txt".to_owned()),
+                        Redirection::Output {
+                            fd: 1,
+                            path: "out.txt".to_owned(),
+                            append: true,
+                        },
+                    ]
+                )]
+            )])
+        );
+        assert_eq!(parse("  # only a comment"), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn parse_rejects_missing_commands() {
+        let syntax_error = |message: &str| Err(LineError::Syntax(message.to_owned()));
+        assert_eq!(
+            parse("make &&"),
+            syntax_error("`&&` is missing a command after it")
+        );
+        assert_eq!(
+            parse("make ||"),
+            syntax_error("`||` is missing a command after it")
+        );
+        assert_eq!(
+            parse("&& make"),
+            syntax_error("`&&` is missing a command before it")
+        );
+        assert_eq!(
+            parse("; make"),
+            syntax_error("`;` is missing a command before it")
+        );
+        assert_eq!(
+            parse("ls |"),
+            syntax_error("`|` is missing a command after it")
+        );
+        assert_eq!(
+            parse("ls | && b"),
+            syntax_error("`|` is missing a command after it")
+        );
+        assert_eq!(
+            parse("| grep a"),
+            syntax_error("`|` is missing a command before it")
+        );
+    }
+
+    #[test]
+    fn parse_rejects_redirections_without_target() {
+        let error = Err(LineError::Syntax(
+            "a redirection is missing its file".into(),
+        ));
+        assert_eq!(parse("echo a >"), error);
+        assert_eq!(parse("sort <"), error);
+        assert_eq!(parse("echo a >> | cat"), error);
+        assert_eq!(parse("echo a 2> && ls"), error);
+    }
+
+    #[test]
+    fn parse_rejects_redirections_without_command() {
+        let error = Err(LineError::Syntax(
+            "a redirection is missing its command".into(),
+        ));
+        assert_eq!(parse("> out.txt"), error);
+        assert_eq!(parse("> out.txt; ls"), error);
+        assert_eq!(parse("ls && < in.txt"), error);
+        assert_eq!(parse("2>&1 | cat"), error);
+        assert_eq!(
+            parse("> out.txt echo a"),
+            Ok(vec![(
+                Connector::Always,
+                vec![simple_command(
+                    &["echo", "a"],
+                    vec![Redirection::Output {
+                        fd: 1,
+                        path: "out.txt".to_owned(),
+                        append: false,
+                    }]
+                )]
+            )])
+        );
+    }
+
+    #[test]
+    fn parse_leaves_other_syntax_to_the_shell() {
+        assert_eq!(parse("FOO=bar env"), Err(LineError::Unsupported));
+        assert_eq!(parse("cat 3< file"), Err(LineError::Unsupported));
+        assert_eq!(parse("echo a >&3"), Err(LineError::Unsupported));
+    }
+
+    /// A temporary folder for the files written by a test, removed when dropped.
+    struct TempDir(std::path::PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir =
+                env::temp_dir().join(format!("cmd-cat-executor-{name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn path(&self, name: &str) -> String {
+            self.0.join(name).to_string_lossy().into_owned()
+        }
+
+        fn read(&self, name: &str) -> Option<String> {
+            std::fs::read_to_string(self.0.join(name)).ok()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn execute_returns_the_exit_code() {
+        assert_eq!(execute_command("true"), Some(0));
+        assert_eq!(execute_command("false"), Some(1));
+        assert_eq!(execute_command("sh -c 'exit 3'"), Some(3));
+        assert_eq!(execute_command("true | false"), Some(1));
+        assert_eq!(execute_command("false | true"), Some(0));
+        assert_eq!(execute_command("cmd-cat-no-such-program"), None);
+        assert_eq!(execute_command("echo a &&"), None);
+    }
+
+    #[test]
+    fn execute_pipes_and_redirects() {
+        let dir = TempDir::new("redirect");
+        let out = dir.path("out.txt");
+
+        assert_eq!(
+            execute_command(&format!("echo a | tr a b > {out}")),
+            Some(0)
+        );
+        assert_eq!(dir.read("out.txt").as_deref(), Some("b\n"));
+        assert_eq!(execute_command(&format!("echo c >> {out}")), Some(0));
+        assert_eq!(dir.read("out.txt").as_deref(), Some("b\nc\n"));
+        assert_eq!(execute_command(&format!("echo d > {out}")), Some(0));
+        assert_eq!(dir.read("out.txt").as_deref(), Some("d\n"));
+
+        assert_eq!(
+            execute_command(&format!("tr d e < {out} > {}", dir.path("in.txt"))),
+            Some(0)
+        );
+        assert_eq!(dir.read("in.txt").as_deref(), Some("e\n"));
+
+        let both = dir.path("both.txt");
+        assert_eq!(
+            execute_command(&format!("sh -c 'echo out; echo err >&2' > {both} 2>&1")),
+            Some(0)
+        );
+        assert_eq!(dir.read("both.txt").as_deref(), Some("out\nerr\n"));
+        assert_eq!(
+            execute_command(&format!(
+                "sh -c 'echo out; echo err >&2' 2> {both} > /dev/null"
+            )),
+            Some(0)
+        );
+        assert_eq!(dir.read("both.txt").as_deref(), Some("err\n"));
+        assert_eq!(
+            execute_command(&format!("sh -c 'echo err >&2' 2>&1 | tr e E > {out}")),
+            Some(0)
+        );
+        assert_eq!(dir.read("out.txt").as_deref(), Some("Err\n"));
+    }
+
+    #[test]
+    fn execute_chains_command_lists() {
+        let dir = TempDir::new("chain");
+        let out = dir.path("out.txt");
+
+        assert_eq!(
+            execute_command(&format!("false && echo and >> {out}")),
+            Some(1)
+        );
+        assert_eq!(dir.read("out.txt"), None);
+        assert_eq!(
+            execute_command(&format!("false || echo or >> {out}")),
+            Some(0)
+        );
+        assert_eq!(
+            execute_command(&format!("true && echo and >> {out}")),
+            Some(0)
+        );
+        assert_eq!(
+            execute_command(&format!("true || echo or >> {out}")),
+            Some(0)
+        );
+        assert_eq!(
+            execute_command(&format!("false; echo always >> {out}; false")),
+            Some(1)
+        );
+        assert_eq!(dir.read("out.txt").as_deref(), Some("or\nand\nalways\n"));
+    }
+
+    #[test]
+    fn execute_stops_at_exit() {
+        let dir = TempDir::new("exit");
+        let out = dir.path("out.txt");
+
+        assert_eq!(execute_command("exit"), Some(0));
+        assert_eq!(
+            execute_command(&format!("false; exit; echo after > {out}")),
+            Some(1)
+        );
+        assert_eq!(
+            execute_command(&format!("true && exit 4 || echo after > {out}")),
+            Some(4)
+        );
+        assert_eq!(
+            execute_command(&format!("false && exit 4 || echo after > {out}")),
+            Some(0)
+        );
+        assert_eq!(dir.read("out.txt").as_deref(), Some("after\n"));
+    }
+
+    #[test]
+    fn execute_runs_globs_with_the_shell() {
+        let dir = TempDir::new("glob");
+        std::fs::write(dir.path("a.txt"), "").unwrap();
+        std::fs::write(dir.path("b.txt"), "").unwrap();
+        let out = dir.path("out");
+
+        assert_eq!(
+            execute_command(&format!("echo {}/*.txt > {out}", dir.0.display())),
+            Some(0)
+        );
+        assert_eq!(
+            dir.read("out"),
+            Some(format!("{} {}\n", dir.path("a.txt"), dir.path("b.txt")))
+        );
+        assert_eq!(
+            execute_command(&format!("ls {}/[a].txt > {out}", dir.0.display())),
+            Some(0)
+        );
+        assert_eq!(dir.read("out"), Some(format!("{}\n", dir.path("a.txt"))));
+    }
+}
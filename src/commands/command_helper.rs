@@ -1,15 +1,92 @@
 use crate::commands::command_executor::execute_command;
+use crate::commands::tool_helper::print_matching_options;
+use crate::commands::{native_platform, Command};
+use crate::custom_styling::color_style;
 use crate::database::SqliteDatabase;
+use crate::utils::config::Config;
 use crate::utils::menu::{self, get_custom_confirmation};
+use crate::utils::paths;
 use crate::utils::{check_chosen_command, execute_current_command};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
 
 /// Get a new instance of SqliteDatabase, basically separates the error handling side of things.
+/// The database is opened at the path given by `--db` or `CMD_CAT_DB`, or else in the data directory,
+/// which is seeded with an existing database on first run (see `seed_database`).
+/// tldr pages are searched in the language of the user, see `Config::languages`,
+/// and commands in the `common` platform and the one of the system.
+/// The shell history is searched unless `search-history` is turned off in the config.
+/// Exits with a message if the database can not be opened, e.g. when a newer cmd-cat wrote it.
+///
+/// # Arguments
+///
+/// * `cli_path` - The value of the `--db` argument, if given.
+/// * `config` - The settings of the config file.
 ///
 /// # Returns
 ///
 /// The initialized `SqliteDatabase` instance.
-pub fn setup_database() -> SqliteDatabase {
-    SqliteDatabase::new().expect("Failed to create database")
+pub fn setup_database(cli_path: Option<&str>, config: &Config) -> SqliteDatabase {
+    let db_path = match paths::database_path_override(cli_path) {
+        Some(db_path) => db_path,
+        None => {
+            let db_path = paths::default_database_path();
+            if !db_path.exists() {
+                seed_database(&db_path, &paths::seed_database_candidates());
+            }
+            db_path
+        }
+    };
+    let db = match SqliteDatabase::open(&db_path) {
+        Ok(db) => db,
+        Err(err) => {
+            eprintln!(
+                "{}",
+                color_style::color_light_red(&format!(
+                    "Failed to open the database at {}: {err}",
+                    db_path.display()
+                ))
+            );
+            process::exit(1);
+        }
+    };
+    db.with_languages(config.languages())
+        .with_platforms(vec!["common".to_owned(), native_platform().to_owned()])
+        .with_history(config.search_history())
+}
+
+/// First run of cmd-cat: creates the data directory and copies the first database found
+/// in the current directory or next to the executable into it.
+/// If there is none, the user is told how to build the database.
+///
+/// # Arguments
+///
+/// * `db_path` - Where the database should be created.
+/// * `candidates` - The databases to copy, see `paths::seed_database_candidates`.
+fn seed_database(db_path: &Path, candidates: &[PathBuf]) {
+    if let Some(data_dir) = db_path.parent() {
+        fs::create_dir_all(data_dir).expect("Failed to create data directory");
+    }
+
+    let seed = candidates.iter().find(|candidate| candidate.is_file());
+    match seed {
+        Some(seed) => {
+            fs::copy(seed, db_path).expect("Failed to copy database");
+            println!(
+                "Copied the database from {} to {}",
+                seed.display(),
+                db_path.display()
+            );
+        }
+        None => println!(
+            "{}",
+            color_style::color_light_red(&format!(
+                "No command database found, creating an empty one at {}. Run `cmd_cat --update` to download the commands.",
+                db_path.display()
+            ))
+        ),
+    }
 }
 
 /// A helper function to handle error while getting the matching commands based on the given prefix.
@@ -21,25 +98,56 @@ pub fn setup_database() -> SqliteDatabase {
 ///
 /// # Returns
 ///
-/// A vector of matching command names and descriptions.
-fn get_matching_commands(db: &SqliteDatabase, prefix: &str) -> Vec<(String, String)> {
+/// A vector of matching commands.
+fn get_matching_commands(db: &SqliteDatabase, prefix: &str) -> Vec<Command> {
     db.find_matching_commands(prefix)
         .expect("Failed to find matching commands")
 }
 
 /// Handle the input command by deligating it to specific funtions.
+/// An imported shell alias starting the input is expanded first.
 ///
 /// # Arguments
 ///
 /// * `input` - The input command.
 /// * `db` - The database instance.
 pub fn handle_command(input: &str, db: &SqliteDatabase) {
+    let input = expand_alias(input, db);
     let command_parts: Vec<&str> = input.split_whitespace().collect();
 
     if command_parts.len() == 1 {
-        handle_single_word_command(&command_parts[0], db);
+        handle_single_word_command(command_parts[0], db);
     } else if command_parts.len() > 1 {
-        handle_multi_word_commands(&command_parts, db, input);
+        handle_multi_word_commands(&command_parts, db, &input);
+    }
+}
+
+/// Expands the imported shell alias starting the input, as the shell would: the expansion is
+/// expanded again if it starts with another alias, but an alias is never expanded twice.
+///
+/// # Arguments
+///
+/// * `input` - The input command, e.g. `gs --short`.
+/// * `db` - The database instance.
+///
+/// # Returns
+///
+/// The expanded input, e.g. `git status --short`, or the input if it does not start with an alias.
+fn expand_alias(input: &str, db: &SqliteDatabase) -> String {
+    let mut expanded = input.trim().to_owned();
+    let mut expanded_names: Vec<String> = Vec::new();
+    loop {
+        let (first_word, rest) = expanded
+            .split_once(char::is_whitespace)
+            .unwrap_or((&expanded, ""));
+        if expanded_names.iter().any(|name| name == first_word) {
+            return expanded;
+        }
+        let Some(alias) = db.get_alias(first_word).expect("Failed to get alias") else {
+            return expanded;
+        };
+        expanded_names.push(first_word.to_owned());
+        expanded = format!("{alias} {rest}").trim_end().to_owned();
     }
 }
 
@@ -56,7 +164,7 @@ pub fn handle_single_word_command(prefix: &str, db: &SqliteDatabase) {
     if matching_commands.is_empty() {
         search_in_database(prefix, db);
     } else {
-        handle_multiple_returned_command(&matching_commands, prefix);
+        handle_multiple_returned_command(&matching_commands, prefix, db);
     }
 }
 
@@ -73,18 +181,15 @@ pub fn handle_single_word_command(prefix: &str, db: &SqliteDatabase) {
 pub fn handle_multi_word_commands(command_parts: &[&str], db: &SqliteDatabase, input: &str) {
     let command = command_parts.join(" ");
 
-    if let Some(description) = db
-        .get_command_description(&command)
-        .expect("Failed to get command description")
-    {
-        execute_current_command(&command, &description);
+    if let Some(stored_command) = db.get_command(&command).expect("Failed to get command") {
+        execute_current_command(&command, &stored_command, db);
     } else {
         let matching_commands = get_matching_commands(db, &command);
 
         if matching_commands.is_empty() {
             handle_progressive_search(command_parts, db, input)
         } else {
-            handle_multiple_returned_command(&matching_commands, input);
+            handle_multiple_returned_command(&matching_commands, input, db);
         }
     }
 }
@@ -94,16 +199,17 @@ pub fn handle_multi_word_commands(command_parts: &[&str], db: &SqliteDatabase, i
 ///
 /// # Arguments
 ///
-/// * `matching_commands` - The matching command names and descriptions.
+/// * `matching_commands` - The matching commands.
 /// * `input` - The input command.
-pub fn handle_multiple_returned_command(matching_commands: &Vec<(String, String)>, input: &str) {
+/// * `db` - The database instance.
+pub fn handle_multiple_returned_command(
+    matching_commands: &[Command],
+    input: &str,
+    db: &SqliteDatabase,
+) {
     let choice = menu::handle_multiple_returned_command(matching_commands, input);
-    match choice {
-        Ok(choice) => {
-            let (command, description) = &matching_commands[choice as usize];
-            check_chosen_command(command, description);
-        }
-        Err(_) => return,
+    if let Ok(choice) = choice {
+        check_chosen_command(&matching_commands[choice], db);
     }
 }
 
@@ -118,7 +224,7 @@ pub fn handle_multiple_returned_command(matching_commands: &Vec<(String, String)
 /// * `db` - The database instance.
 /// * `input` - The input command
 pub fn handle_progressive_search(command_parts: &[&str], db: &SqliteDatabase, input: &str) {
-    let matching_commands = get_matching_commands(db, &command_parts[0]);
+    let matching_commands = get_matching_commands(db, command_parts[0]);
 
     let mut command_words = string_to_words_arr(&matching_commands);
 
@@ -143,10 +249,10 @@ pub fn handle_progressive_search(command_parts: &[&str], db: &SqliteDatabase, in
         let matching_commands = filter_matching_commands(command_words, matching_commands);
 
         if matching_commands.len() == 1 {
-            let (command, description) = &matching_commands[0];
-            execute_current_command(&command, &description);
+            let command = &matching_commands[0];
+            execute_current_command(&command.name, command, db);
         } else {
-            handle_multiple_returned_command(&matching_commands, input);
+            handle_multiple_returned_command(&matching_commands, input, db);
         }
     }
 }
@@ -160,41 +266,64 @@ pub fn handle_progressive_search(command_parts: &[&str], db: &SqliteDatabase, in
 /// * `input` - The input command.
 /// * `db` - The database instance.
 pub fn search_in_database(input: &str, db: &SqliteDatabase) {
-    let matching_commands = match db.search_commands(input) {
-        Ok(data) => data,
-        Err(_) => Vec::new(),
-    };
+    let matching_commands = db.search_commands(input).unwrap_or_default();
+    show_search_results(input, &matching_commands, db);
+}
+
+/// Shows the results of a search as a menu, see `search_in_database`.
+///
+/// # Arguments
+///
+/// * `input` - The search input, executed as is if the user wants to when nothing matched.
+/// * `matching_commands` - The commands matching the input.
+/// * `db` - The database instance.
+fn show_search_results(input: &str, matching_commands: &[Command], db: &SqliteDatabase) {
     if matching_commands.is_empty() {
         println!("No matches found.");
         let response = get_custom_confirmation(
             "Do you still want to proceed to execution of the command? It might cause Fatal error.",
         );
         if response {
-            execute_command(input)
+            execute_command(input);
         }
     } else {
-        handle_multiple_returned_command(&matching_commands, "");
+        handle_multiple_returned_command(matching_commands, "", db);
+    }
+}
+
+/// Search for a command in the database like `search_in_database`, and if no command matches,
+/// list the documented options of the tools matching the input instead.
+///
+/// # Arguments
+///
+/// * `input` - The search input.
+/// * `db` - The database instance.
+pub fn search_commands_and_options(input: &str, db: &SqliteDatabase) {
+    let matching_commands = db.search_commands(input).unwrap_or_default();
+    if matching_commands.is_empty() && print_matching_options(input, db) {
+        return;
     }
+    show_search_results(input, &matching_commands, db);
 }
 
 /// Helper function for handle_progressive_search Convert matching vector of commands(tuple) to a vector of words array.
 ///
 /// # Arguments
 ///
-/// * `matching_commands` - The matching command names and descriptions.
+/// * `matching_commands` - The matching commands.
 ///
 /// # Returns
 ///
 /// A vector of word arrays of commands.
-fn string_to_words_arr(matching_commands: &Vec<(String, String)>) -> Vec<(usize, Vec<String>)> {
+fn string_to_words_arr(matching_commands: &[Command]) -> Vec<(usize, Vec<String>)> {
     matching_commands
-        .clone()
         .iter()
         .enumerate()
-        .map(|(index, (command, _))| {
+        .map(|(index, command)| {
             (
                 index,
                 command
+                    .name
                     .split_whitespace()
                     .map(String::from)
                     .collect::<Vec<String>>(),
@@ -216,21 +345,16 @@ fn string_to_words_arr(matching_commands: &Vec<(String, String)>) -> Vec<(usize,
 ///
 /// A vector of matching command words.
 fn filter_by_exact_match(
-    command_words: &Vec<(usize, Vec<String>)>,
+    command_words: &[(usize, Vec<String>)],
     word: &str,
     word_index: usize,
 ) -> Vec<(usize, Vec<String>)> {
     command_words
-        .clone()
-        .into_iter()
+        .iter()
         .filter(|(_, command_word)| {
-            if !(command_word.len() < word_index + 1) {
-                if command_word[word_index] == word {
-                    return true;
-                }
-            }
-            return false;
+            command_word.len() > word_index && command_word[word_index] == word
         })
+        .cloned()
         .collect::<Vec<_>>()
 }
 
@@ -246,18 +370,17 @@ fn filter_by_exact_match(
 /// # Returns
 ///
 /// A vector of matching command words with updated placeholders.
-
 fn filter_by_placeholder_match(
-    command_words: &Vec<(usize, Vec<String>)>,
+    command_words: &[(usize, Vec<String>)],
     word: &str,
     word_index: usize,
 ) -> Vec<(usize, Vec<String>)> {
     command_words
-        .clone()
-        .into_iter()
+        .iter()
         .filter(|(_, command_word)| {
-            !(command_word.len() < word_index + 1) && command_word[word_index].starts_with("{{")
+            command_word.len() > word_index && command_word[word_index].starts_with("{{")
         })
+        .cloned()
         .map(|(command, mut command_word)| {
             command_word[word_index] = format!("{}[{}]", command_word[word_index], word);
             (command, command_word)
@@ -265,24 +388,94 @@ fn filter_by_placeholder_match(
         .collect::<Vec<_>>()
 }
 
-/// Filter matching commands based on surviving command words (Basically it extract the descrption)
+/// Filter matching commands based on surviving command words (Basically it extract the descrption and page)
 ///
 /// # Arguments
 ///
 /// * `command_words` - The command words surviving elemination
-/// * `matching_commands` - The matching command names and descriptions.
+/// * `matching_commands` - The matching commands.
 ///
 /// # Returns
 ///
-/// A vector of matching command names and descriptions.
+/// A vector of matching commands, with the surviving words as command.
 pub fn filter_matching_commands(
     command_words: Vec<(usize, Vec<String>)>,
-    matching_commands: Vec<(String, String)>,
-) -> Vec<(String, String)> {
+    matching_commands: Vec<Command>,
+) -> Vec<Command> {
     let mut new_matching_commands = Vec::new();
     for (i, command) in command_words {
-        let (_, description) = &matching_commands[i];
-        new_matching_commands.push((command.join(" ").to_owned(), description.to_owned()));
+        let mut new_command = matching_commands[i].clone();
+        new_command.name = command.join(" ");
+        new_matching_commands.push(new_command);
     }
     new_matching_commands
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{ALIAS_SOURCE, ALIAS_TAG};
+
+    /// Opens an in-memory database holding the given aliases.
+    fn database_with_aliases(aliases: &[(&str, &str)]) -> SqliteDatabase {
+        let db = SqliteDatabase::open(":memory:").unwrap();
+        let commands: Vec<Command> = aliases
+            .iter()
+            .map(|(name, value)| {
+                let mut command = Command::new(value, "").with_source(ALIAS_SOURCE);
+                command.page = name.to_string();
+                command.tags = vec![ALIAS_TAG.to_owned()];
+                command
+            })
+            .collect();
+        db.replace_source(ALIAS_SOURCE, &commands).unwrap();
+        db
+    }
+
+    #[test]
+    fn expands_aliases_starting_the_input() {
+        let db = database_with_aliases(&[("gs", "git status"), ("g", "gs --short")]);
+        assert_eq!(expand_alias("gs", &db), "git status");
+        assert_eq!(expand_alias("  g -b ", &db), "git status --short -b");
+        assert_eq!(expand_alias("echo gs", &db), "echo gs");
+    }
+
+    #[test]
+    fn expands_recursive_aliases_once() {
+        let db = database_with_aliases(&[("ls", "ls --color"), ("a", "b x"), ("b", "a y")]);
+        assert_eq!(expand_alias("ls -l", &db), "ls --color -l");
+        assert_eq!(expand_alias("a", &db), "a y x");
+        assert_eq!(expand_alias("b", &db), "b x y");
+    }
+
+    #[test]
+    fn missing_database_is_seeded_with_the_first_candidate() {
+        let dir = std::env::temp_dir().join(format!("cmd-cat-seed-{}", std::process::id()));
+        let db_path = dir.join("data").join(paths::DATABASE_FILE_NAME);
+        let seed_path = dir.join("seed.db");
+        fs::create_dir_all(&dir).unwrap();
+        let seed = SqliteDatabase::open(&seed_path).unwrap();
+        seed.insert(&Command::new("ls -la", "List all files:"))
+            .unwrap();
+        drop(seed);
+
+        seed_database(&db_path, &[dir.join("missing.db"), seed_path]);
+        let db = SqliteDatabase::open(&db_path).unwrap();
+        let copied = db.find_matching_commands("ls").unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(copied.len(), 1);
+    }
+
+    #[test]
+    fn missing_database_without_candidates_is_created_empty() {
+        let dir = std::env::temp_dir().join(format!("cmd-cat-seed-empty-{}", std::process::id()));
+        let db_path = dir.join("data").join(paths::DATABASE_FILE_NAME);
+
+        seed_database(&db_path, &[dir.join("missing.db")]);
+        let data_dir_created = db_path.parent().unwrap().is_dir();
+        let copied = db_path.exists();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(data_dir_created);
+        assert!(!copied);
+    }
+}
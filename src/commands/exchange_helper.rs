@@ -0,0 +1,249 @@
+use crate::custom_styling::color_style;
+use crate::database::exchange::{self, ExchangeFormat, ExportFilter};
+use crate::database::file_parse::{
+    import_alias_files, import_cheatsheets, import_history_files, import_navi_files,
+    sync_sheet_folder, UpdateError,
+};
+use crate::database::search::normalize_tag;
+use crate::database::SqliteDatabase;
+use crate::utils::config::Config;
+use crate::utils::paths::{cheatsheets_dir, expand_home, shell_history_paths, shell_startup_paths};
+use crate::utils::take_option_value;
+use std::path::{Path, PathBuf};
+
+/// Exports commands to a JSON or TOML file, or a directory of tldr-style Markdown pages.
+///
+/// # Arguments
+///
+/// * `input` - `<path> [--format json|toml|md] [--source <source>] [--tag <tag>] [--page <page>]`,
+///   the format defaults to the extension of the path.
+/// * `db` - The database instance.
+pub fn export_database(input: &str, db: &SqliteDatabase) {
+    let mut args: Vec<String> = input.split_whitespace().map(String::from).collect();
+    let format_name = take_option_value(&mut args, "--format");
+    let filter = ExportFilter {
+        source: take_option_value(&mut args, "--source"),
+        tag: take_option_value(&mut args, "--tag").map(|tag| normalize_tag(&tag)),
+        page: take_option_value(&mut args, "--page"),
+    };
+    let Some((path, format)) = get_path_and_format(&args, format_name.as_deref()) else {
+        println!("Usage: --export <path> [--format json|toml|md] [--source <source>] [--tag <tag>] [--page <page>]");
+        return;
+    };
+
+    let commands = db
+        .export_commands(&filter)
+        .expect("Failed to read commands");
+    if format == ExchangeFormat::Markdown && commands.iter().any(|c| !c.variables.is_empty()) {
+        println!(
+            "{}",
+            color_style::color_light_red(
+                "Markdown pages can not hold the placeholder settings of commands, use --format json or toml to keep them."
+            )
+        );
+    }
+    match exchange::write_commands(&commands, format, path) {
+        Ok(()) => println!("Exported {} commands to {}", commands.len(), path.display()),
+        Err(err) => eprintln!("Failed to export to {}: {err}", path.display()),
+    }
+}
+
+/// Imports commands from a file or directory written by `export_database`,
+/// merging them into the commands already stored.
+///
+/// # Arguments
+///
+/// * `input` - `<path> [--format json|toml|md]`, the format defaults to the extension of the path.
+/// * `db` - The database instance.
+pub fn import_database(input: &str, db: &SqliteDatabase) {
+    let mut args: Vec<String> = input.split_whitespace().map(String::from).collect();
+    let format_name = take_option_value(&mut args, "--format");
+    let Some((path, format)) = get_path_and_format(&args, format_name.as_deref()) else {
+        println!("Usage: --import <path> [--format json|toml|md]");
+        return;
+    };
+
+    let commands = match exchange::read_commands(path, format) {
+        Ok(commands) => commands,
+        Err(err) => {
+            eprintln!("Failed to import {}: {err}", path.display());
+            return;
+        }
+    };
+    let summary = db
+        .import_commands(&commands)
+        .expect("Failed to import commands");
+    println!(
+        "Imported {} commands: {} added, {} already present",
+        commands.len(),
+        summary.added,
+        summary.merged
+    );
+}
+
+/// Imports the commands of navi cheatsheets, replacing the ones imported before.
+///
+/// # Arguments
+///
+/// * `input` - `<path>`, a `.cheat` file or a folder of them.
+/// * `db` - The database instance.
+pub fn import_navi(input: &str, db: &SqliteDatabase) {
+    let path = input.trim();
+    if path.is_empty() {
+        println!("Usage: --import-navi <path>");
+        return;
+    }
+    print_import_result(import_navi_files(db, path), |count| {
+        format!("Imported {count} commands from navi cheatsheets.")
+    });
+}
+
+/// Imports the commands of `cheat` cheatsheets, replacing the ones imported before.
+///
+/// # Arguments
+///
+/// * `input` - `[path]`, a cheatsheet or a folder of them, `~/.config/cheat/cheatsheets` if not given.
+/// * `db` - The database instance.
+pub fn import_cheat(input: &str, db: &SqliteDatabase) {
+    let path = match input.trim() {
+        "" => match cheatsheets_dir() {
+            Some(dir) => dir.to_string_lossy().into_owned(),
+            None => {
+                println!("Usage: --import-cheat [path]");
+                return;
+            }
+        },
+        path => path.to_owned(),
+    };
+    print_import_result(import_cheatsheets(db, &path), |count| {
+        format!("Imported {count} commands from the cheatsheets in {path}.")
+    });
+}
+
+/// Imports the aliases and one-line functions of shell startup files, replacing the ones imported before.
+///
+/// # Arguments
+///
+/// * `input` - `[path ...]`, startup files or fish folders, `~/.bashrc`, `~/.zshrc` and such if not given
+///   (see `paths::shell_startup_paths`).
+/// * `db` - The database instance.
+pub fn import_aliases(input: &str, db: &SqliteDatabase) {
+    let paths = match input.trim() {
+        "" => shell_startup_paths(),
+        paths => paths.split_whitespace().map(expand_home).collect(),
+    };
+    if paths.is_empty() {
+        println!("No shell startup file found. Usage: --import-aliases [path ...]");
+        return;
+    }
+    print_import_result(import_alias_files(db, &paths), |count| {
+        format!(
+            "Imported {count} aliases and functions from {}.",
+            join_paths(&paths)
+        )
+    });
+}
+
+/// Imports the commands of the shell history files, replacing the ones imported before.
+///
+/// # Arguments
+///
+/// * `input` - `[path ...]`, history files, `~/.bash_history`, `~/.zsh_history` and fish's if not given
+///   (see `paths::shell_history_paths`).
+/// * `db` - The database instance.
+pub fn import_history(input: &str, db: &SqliteDatabase) {
+    let paths = match input.trim() {
+        "" => shell_history_paths(),
+        paths => paths.split_whitespace().map(expand_home).collect(),
+    };
+    if paths.is_empty() {
+        println!("No shell history file found. Usage: --import-history [path ...]");
+        return;
+    }
+    print_import_result(import_history_files(db, &paths), |count| {
+        format!("Imported {count} commands from {}.", join_paths(&paths))
+    });
+}
+
+/// Prints the outcome of an import, which leaves the database unchanged when it fails.
+///
+/// # Arguments
+///
+/// * `result` - The number of commands imported, or the error that stopped the import.
+/// * `message` - Builds the message printed on success from the number of commands.
+fn print_import_result(result: Result<usize, UpdateError>, message: impl FnOnce(usize) -> String) {
+    match result {
+        Ok(count) => println!("{}", message(count)),
+        Err(err) => println!(
+            "{}",
+            color_style::color_light_red(&format!(
+                "Import failed, the database was left unchanged: {err}"
+            ))
+        ),
+    }
+}
+
+/// Joins paths for messages, e.g. `/home/me/.bashrc, /home/me/.zshrc`.
+///
+/// # Arguments
+///
+/// * `paths` - The paths.
+///
+/// # Returns
+///
+/// The paths separated by commas.
+fn join_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Loads the TOML and YAML cheatsheet folder if it changed since it was last loaded.
+///
+/// # Arguments
+///
+/// * `db` - The database instance.
+/// * `config` - The settings of the config file, which tell where the folder is.
+/// * `report_unchanged` - Whether to say so when nothing changed, it is quiet on start-up.
+pub fn sync_cheatsheets(db: &SqliteDatabase, config: &Config, report_unchanged: bool) {
+    let folder = config.cheatsheet_folder();
+    let folder_path = folder.to_string_lossy();
+    match sync_sheet_folder(db, &folder_path) {
+        Ok(Some(0)) if !report_unchanged && !folder.is_dir() => {}
+        Ok(Some(count)) => println!("Loaded {count} commands from {folder_path}."),
+        Ok(None) if report_unchanged => {
+            println!("The cheatsheets in {folder_path} are up to date.")
+        }
+        Ok(None) => {}
+        Err(err) => println!(
+            "{}",
+            color_style::color_light_red(&format!(
+                "Failed to load the cheatsheets, the commands loaded before are kept: {err}"
+            ))
+        ),
+    }
+}
+
+/// Gets the path argument and the format, given explicitly or guessed from the path.
+///
+/// # Arguments
+///
+/// * `args` - The remaining arguments, the path being the only one.
+/// * `format_name` - The value of `--format`, if given.
+///
+/// # Returns
+///
+/// The path and the format, `None` if the path is missing or the format unknown.
+fn get_path_and_format<'a>(
+    args: &'a [String],
+    format_name: Option<&str>,
+) -> Option<(&'a Path, ExchangeFormat)> {
+    let path = Path::new(args.first()?);
+    let format = match format_name {
+        Some(name) => ExchangeFormat::from_name(name),
+        None => ExchangeFormat::from_path(path),
+    }?;
+    Some((path, format))
+}
@@ -0,0 +1,94 @@
+use crate::commands::Command;
+use crate::database::search::normalize_tag;
+use crate::database::SqliteDatabase;
+use crate::utils::menu;
+
+/// Stars or unstars a command picked from the search results of `query`.
+/// Starred commands are pinned at the top of every menu.
+/// Without query, unstarring lists the starred commands.
+///
+/// # Arguments
+///
+/// * `query` - The search query, same syntax as `meow`.
+/// * `favorite` - `true` to star the command, `false` to unstar it.
+/// * `db` - The database instance.
+pub fn star_command(query: &str, favorite: bool, db: &SqliteDatabase) {
+    let query = if !favorite && query.trim().is_empty() {
+        "is:favorite"
+    } else {
+        query
+    };
+    let Some(command) = choose_command(query, db) else {
+        return;
+    };
+
+    db.set_favorite(command.id, favorite)
+        .expect("Failed to update favorites");
+    let action = if favorite { "Starred" } else { "Unstarred" };
+    println!("{action}: {}", command.name);
+}
+
+/// Attaches a tag to a command picked from the search results.
+///
+/// # Arguments
+///
+/// * `input` - The tag followed by the search query, e.g. `deploy kubectl apply`.
+/// * `db` - The database instance.
+pub fn tag_command(input: &str, db: &SqliteDatabase) {
+    let (tag, query) = input.trim().split_once(' ').unwrap_or((input.trim(), ""));
+    if normalize_tag(tag).is_empty() {
+        println!("Usage: --tag <tag> <search-term>");
+        return;
+    }
+    let Some(command) = choose_command(query, db) else {
+        return;
+    };
+
+    db.add_tag(command.id, tag).expect("Failed to add tag");
+    println!("Tagged #{}: {}", normalize_tag(tag), command.name);
+}
+
+/// Removes a tag from a command picked among the commands with that tag.
+///
+/// # Arguments
+///
+/// * `input` - The tag, optionally followed by a search query to narrow the commands down.
+/// * `db` - The database instance.
+pub fn untag_command(input: &str, db: &SqliteDatabase) {
+    let (tag, query) = input.trim().split_once(' ').unwrap_or((input.trim(), ""));
+    let tag = normalize_tag(tag);
+    if tag.is_empty() {
+        println!("Usage: --untag <tag> [search-term]");
+        return;
+    }
+    let Some(command) = choose_command(&format!("tag:{tag} {query}"), db) else {
+        return;
+    };
+
+    db.remove_tag(command.id, &tag)
+        .expect("Failed to remove tag");
+    println!("Removed #{tag}: {}", command.name);
+}
+
+/// Shows a menu with the search results of `query`.
+/// Only stored commands are listed, the commands of the shell history can not be starred or tagged.
+///
+/// # Arguments
+///
+/// * `query` - The search query.
+/// * `db` - The database instance.
+///
+/// # Returns
+///
+/// The chosen command, `None` if nothing matched or the menu was cancelled.
+fn choose_command(query: &str, db: &SqliteDatabase) -> Option<Command> {
+    let mut commands = db.search_commands(query).unwrap_or_default();
+    commands.retain(|command| command.id != 0);
+    if commands.is_empty() {
+        println!("No matches found.");
+        return None;
+    }
+
+    let choice = menu::handle_multiple_returned_command(&commands, "").ok()?;
+    Some(commands[choice].clone())
+}
@@ -1,10 +1,70 @@
 pub mod command_executor;
 pub mod command_helper;
+pub mod exchange_helper;
+pub mod favorites;
+pub mod personal_commands;
+pub mod tool_helper;
 
-///
+/// Source of the examples imported from tldr pages, these are replaced on every update.
+pub const TLDR_SOURCE: &str = "tldr";
+
+/// Source of the commands added by the user, never touched by updates.
+pub const USER_SOURCE: &str = "user";
+
+/// Source of the commands imported from navi cheatsheets, replaced on every navi import.
+pub const NAVI_SOURCE: &str = "navi";
+
+/// Source of the commands loaded from the TOML and YAML cheatsheet folder, kept in sync with it.
+pub const SHEET_SOURCE: &str = "sheet";
+
+/// Source of the commands imported from `cheat` cheatsheets, replaced on every cheatsheet import.
+pub const CHEAT_SOURCE: &str = "cheat";
+
+/// Source of the aliases and functions read from the shell startup files, replaced on every alias import.
+pub const ALIAS_SOURCE: &str = "alias";
+
+/// Source of the commands found in the shell history, they are not stored as commands.
+pub const HISTORY_SOURCE: &str = "history";
+
+/// Source of the tools read from the local man pages, replaced on every man page import.
+pub const MAN_SOURCE: &str = "man";
+
+/// Source of the tools read from their `--help` output, replaced one tool at a time.
+pub const HELP_SOURCE: &str = "help";
+
+/// Tag of the aliases of `ALIAS_SOURCE`, which are expanded when they start the input.
+pub const ALIAS_TAG: &str = "alias";
+
+/// Tag of the shell functions of `ALIAS_SOURCE`.
+pub const FUNCTION_TAG: &str = "function";
+
+/// A command example along with a short description of what it does,
+/// and the page it was taken from.
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct Command {
     pub name: String,
     pub description: String,
+    /// Name of the page the example belongs to, e.g. `tar`
+    pub page: String,
+    /// Platform directory of the page (common, linux, osx, ...)
+    pub platform: String,
+    /// Language of the page, `en` for the default `pages` directory
+    pub language: String,
+    /// The `>` summary lines of the page
+    pub summary: String,
+    /// The "More information" link of the page
+    pub url: String,
+    /// Where the command comes from: `TLDR_SOURCE`, `USER_SOURCE`, `NAVI_SOURCE`, `SHEET_SOURCE`,
+    /// `CHEAT_SOURCE`, `ALIAS_SOURCE` or `HISTORY_SOURCE`
+    pub source: String,
+    /// Row id of the command in the database, 0 if it is not stored yet
+    pub id: i64,
+    /// Whether the user starred the command
+    pub favorite: bool,
+    /// Tags attached by the user, e.g. `deploy`
+    pub tags: Vec<String>,
+    /// How some of the placeholders are filled in, only set when importing or exporting
+    pub variables: Vec<Variable>,
 }
 
 impl Command {
@@ -24,6 +84,205 @@ impl Command {
         Command {
             name: name.to_string(),
             description: description.to_string(),
+            platform: "common".to_string(),
+            language: "en".to_string(),
+            source: TLDR_SOURCE.to_string(),
+            ..Default::default()
         }
     }
+
+    /// Sets the source of the command.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - Where the command comes from, e.g. `USER_SOURCE`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let command = Command::new("make deploy", "Deploy the stack:").with_source(USER_SOURCE);
+    /// ```
+    pub fn with_source(mut self, source: &str) -> Self {
+        self.source = source.to_string();
+        self
+    }
+
+    /// Sets the page metadata of the command.
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - The page the command example was taken from.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let page = Page::new("pwd", "linux", "en");
+    /// let command = Command::new("pwd", "Print the current directory:").with_page(&page);
+    /// ```
+    pub fn with_page(mut self, page: &Page) -> Self {
+        self.page = page.name.clone();
+        self.platform = page.platform.clone();
+        self.language = page.language.clone();
+        self.summary = page.summary.clone();
+        self.url = page.url.clone();
+        self
+    }
+}
+
+/// How the value of a `{{name}}` placeholder is filled in.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Variable {
+    pub name: String,
+    pub kind: VariableKind,
+    /// The shell command listing the values, for `VariableKind::Command` (navi's `$ name: command`)
+    pub generator: String,
+    /// The values to choose from, for `VariableKind::Choice`
+    pub choices: Vec<String>,
+    /// The value used when the user enters nothing, empty if there is none
+    pub default: String,
+}
+
+/// The kinds of values a placeholder takes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum VariableKind {
+    /// Any text typed by the user
+    #[default]
+    Text,
+    /// A number typed by the user
+    Number,
+    /// One of the `choices` of the variable
+    Choice,
+    /// One of the lines printed by the `generator` of the variable
+    Command,
+}
+
+impl VariableKind {
+    /// Gets the kind from its name, as written in cheatsheets and stored in the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - `text`, `number`, `choice` or `command`.
+    ///
+    /// # Returns
+    ///
+    /// The kind, `None` for unknown names.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(VariableKind::Text),
+            "number" => Some(VariableKind::Number),
+            "choice" => Some(VariableKind::Choice),
+            "command" => Some(VariableKind::Command),
+            _ => None,
+        }
+    }
+
+    /// Gets the name of the kind, see `from_name`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            VariableKind::Text => "text",
+            VariableKind::Number => "number",
+            VariableKind::Choice => "choice",
+            VariableKind::Command => "command",
+        }
+    }
+}
+
+/// Metadata of a page (a tldr markdown file) shared by all of its examples.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Page {
+    pub name: String,
+    pub platform: String,
+    pub language: String,
+    pub summary: String,
+    pub url: String,
+}
+
+impl Page {
+    /// Create a new page instance without summary and link.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - name of the page, usually the name of the tool
+    /// * `platform` - the platform directory of the page
+    /// * `language` - the language code of the page
+    pub fn new(name: &str, platform: &str, language: &str) -> Self {
+        Page {
+            name: name.to_string(),
+            platform: platform.to_string(),
+            language: language.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Gets the tldr platform directory of the system cmd-cat runs on, e.g. `osx` on macOS.
+///
+/// # Returns
+///
+/// The platform, as in `Page::platform`.
+pub fn native_platform() -> &'static str {
+    match std::env::consts::OS {
+        "macos" => "osx",
+        "solaris" | "illumos" => "sunos",
+        os => os,
+    }
+}
+
+/// A tool (an executable) along with the options it documents.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Tool {
+    pub name: String,
+    /// Manual section of the page the tool was read from, e.g. `1`
+    pub section: String,
+    /// One line description of the tool
+    pub summary: String,
+    /// How the tool is invoked, as shown in the SYNOPSIS section
+    pub synopsis: String,
+    /// Where the tool was read from, e.g. `MAN_SOURCE`
+    pub source: String,
+    pub options: Vec<ToolOption>,
+    /// Subcommands of the tool (e.g. `build` for `cargo`), the name is kept in `flag`
+    pub subcommands: Vec<ToolOption>,
+}
+
+/// An option of a tool as documented, e.g. `-f, --file=ARCHIVE` and its description,
+/// or a subcommand and its description.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ToolOption {
+    pub flag: String,
+    pub description: String,
+}
+
+impl ToolOption {
+    /// Gets the spellings of the option, without their arguments.
+    ///
+    /// # Returns
+    ///
+    /// The flags of the option, e.g. `["-f", "--file"]` for `-f, --file=ARCHIVE`.
+    pub fn flags(&self) -> Vec<&str> {
+        self.flag
+            .split([',', '|'])
+            .filter_map(|alternative| {
+                alternative
+                    .split(|c: char| c.is_whitespace() || matches!(c, '=' | '[' | '<'))
+                    .find(|part| !part.is_empty())
+                    .map(|flag| flag.trim_end_matches("..."))
+            })
+            .filter(|flag| flag.starts_with('-') || flag.starts_with('+'))
+            .collect()
+    }
+
+    /// Checks if the option is spelled `flag` (an argument after `=` is ignored).
+    ///
+    /// # Arguments
+    ///
+    /// * `flag` - A flag as written in a command, e.g. `--file=a.tar`.
+    ///
+    /// # Returns
+    ///
+    /// `true` if `flag` is one of the spellings of the option.
+    pub fn matches(&self, flag: &str) -> bool {
+        let flag = flag.split('=').next().unwrap_or(flag);
+        self.flags().contains(&flag)
+    }
 }
@@ -0,0 +1,199 @@
+use crate::commands::{Command, USER_SOURCE};
+use crate::custom_styling::color_style;
+use crate::database::SqliteDatabase;
+use crate::utils::menu::{self, get_custom_confirmation};
+
+/// Prompts for a command and its description, and stores it as a personal command.
+/// Personal commands are searched like tldr examples but survive `--update`.
+///
+/// # Arguments
+///
+/// * `db` - The database instance.
+pub fn add_user_command(db: &SqliteDatabase) {
+    let name = menu::get_text_input("Command:", "");
+    if name.trim().is_empty() {
+        return;
+    }
+    let description = menu::get_text_input("Description:", "");
+
+    let command = Command::new(name.trim(), description.trim()).with_source(USER_SOURCE);
+    if save_user_command(&command, db).expect("Failed to insert command into the database") {
+        println!("Added personal command: {}", command.name);
+    } else {
+        print_duplicate(&command);
+    }
+}
+
+/// Lets the user pick one of the personal commands, and edit its command and description.
+///
+/// # Arguments
+///
+/// * `db` - The database instance.
+pub fn edit_user_command(db: &SqliteDatabase) {
+    let Some(mut command) = choose_user_command(db) else {
+        return;
+    };
+    let name = menu::get_text_input("Command:", &command.name);
+    if name.trim().is_empty() {
+        return;
+    }
+    command.name = name.trim().to_owned();
+    command.description = menu::get_text_input("Description:", &command.description)
+        .trim()
+        .to_owned();
+
+    if save_user_command(&command, db).expect("Failed to update command") {
+        println!("Updated personal command: {}", command.name);
+    } else {
+        print_duplicate(&command);
+    }
+}
+
+/// Lets the user pick one of the personal commands, and removes it after confirmation.
+///
+/// # Arguments
+///
+/// * `db` - The database instance.
+pub fn remove_user_command(db: &SqliteDatabase) {
+    let Some(command) = choose_user_command(db) else {
+        return;
+    };
+    if !get_custom_confirmation("Do you want to remove the command?") {
+        return;
+    }
+
+    db.remove_user_command(command.id)
+        .expect("Failed to remove command");
+    println!("Removed personal command: {}", command.name);
+}
+
+/// Shows a menu with all of the personal commands.
+///
+/// # Arguments
+///
+/// * `db` - The database instance.
+///
+/// # Returns
+///
+/// The chosen command, `None` if there are no personal commands or the menu was cancelled.
+fn choose_user_command(db: &SqliteDatabase) -> Option<Command> {
+    let commands = db
+        .commands_by_source(USER_SOURCE)
+        .expect("Failed to get personal commands");
+    if commands.is_empty() {
+        println!("No personal commands yet, add one with `cmd_cat --add`.");
+        return None;
+    }
+
+    let choice = menu::handle_multiple_returned_command(&commands, "").ok()?;
+    Some(commands[choice].clone())
+}
+
+/// Stores a personal command, added if its `id` is 0 and updated otherwise,
+/// unless another personal command of the same page already has that text.
+///
+/// # Arguments
+///
+/// * `command` - The command to store.
+/// * `db` - The database instance.
+///
+/// # Returns
+///
+/// `false` if nothing was stored because the command would be a duplicate.
+fn save_user_command(command: &Command, db: &SqliteDatabase) -> rusqlite::Result<bool> {
+    let duplicate = db.commands_by_source(USER_SOURCE)?.iter().any(|stored| {
+        stored.id != command.id
+            && stored.name == command.name
+            && stored.page == command.page
+            && stored.platform == command.platform
+            && stored.language == command.language
+    });
+    if duplicate {
+        return Ok(false);
+    }
+    if command.id == 0 {
+        db.insert(command)?;
+    } else {
+        db.update_user_command(command)?;
+    }
+    Ok(true)
+}
+
+/// Tells the user that the command was not saved because it is already a personal command.
+///
+/// # Arguments
+///
+/// * `command` - The command that could not be saved.
+fn print_duplicate(command: &Command) {
+    println!(
+        "{}",
+        color_style::color_light_red(&format!(
+            "Nothing changed, `{}` already is a personal command.",
+            command.name
+        ))
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database_with_commands() -> (SqliteDatabase, Command) {
+        let db = SqliteDatabase::open(":memory:").unwrap();
+        let deploy = Command::new("make deploy", "Deploy:").with_source(USER_SOURCE);
+        assert!(save_user_command(&deploy, &db).unwrap());
+        let mut test = Command::new("make test", "Test:").with_source(USER_SOURCE);
+        assert!(save_user_command(&test, &db).unwrap());
+        test.id = db.commands_by_source(USER_SOURCE).unwrap()[1].id;
+        (db, test)
+    }
+
+    fn stored(db: &SqliteDatabase) -> Vec<(String, String)> {
+        db.commands_by_source(USER_SOURCE)
+            .unwrap()
+            .into_iter()
+            .map(|command| (command.name, command.description))
+            .collect()
+    }
+
+    #[test]
+    fn adding_an_existing_command_keeps_the_stored_one() {
+        let (db, _) = database_with_commands();
+        let duplicate = Command::new("make deploy", "Something else:").with_source(USER_SOURCE);
+
+        assert!(!save_user_command(&duplicate, &db).unwrap());
+        assert_eq!(
+            stored(&db),
+            [
+                ("make deploy".to_owned(), "Deploy:".to_owned()),
+                ("make test".to_owned(), "Test:".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn renaming_to_an_existing_command_keeps_both() {
+        let (db, mut test) = database_with_commands();
+        test.name = "make deploy".to_owned();
+
+        assert!(!save_user_command(&test, &db).unwrap());
+        assert_eq!(
+            stored(&db),
+            [
+                ("make deploy".to_owned(), "Deploy:".to_owned()),
+                ("make test".to_owned(), "Test:".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn editing_keeps_the_id() {
+        let (db, mut test) = database_with_commands();
+        test.description = "Run the tests:".to_owned();
+
+        assert!(save_user_command(&test, &db).unwrap());
+        let commands = db.commands_by_source(USER_SOURCE).unwrap();
+        assert_eq!(commands[1].id, test.id);
+        assert_eq!(commands[1].description, "Run the tests:");
+    }
+}
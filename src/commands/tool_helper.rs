@@ -0,0 +1,250 @@
+use crate::commands::{Tool, ToolOption};
+use crate::custom_styling::color_style;
+use crate::database::file_parse::help::read_help_output;
+use crate::database::file_parse::import_man_pages;
+use crate::database::SqliteDatabase;
+
+/// Where man pages are read from when no folder is given.
+const DEFAULT_MAN_FOLDER: &str = "/usr/share/man";
+
+/// Longest option description shown in an explanation, longer ones are cut.
+const MAX_EXPLANATION_LENGTH: usize = 100;
+
+/// Imports the options of the tools documented by the man pages of the system,
+/// replacing the ones imported before.
+///
+/// # Arguments
+///
+/// * `input` - `[folder]`, `/usr/share/man` if not given.
+/// * `db` - The database instance.
+pub fn import_man(input: &str, db: &SqliteDatabase) {
+    let folder_path = match input.trim() {
+        "" => DEFAULT_MAN_FOLDER,
+        folder_path => folder_path,
+    };
+    println!("Reading the man pages in {folder_path}...");
+    match import_man_pages(db, folder_path) {
+        Ok((tools, options)) => println!("Imported {options} options of {tools} tools."),
+        Err(err) => println!(
+            "{}",
+            color_style::color_light_red(&format!(
+                "Import failed, the database was left unchanged: {err}"
+            ))
+        ),
+    }
+}
+
+/// Reads the options and subcommands of tools from their `--help` output,
+/// replacing what was read from it before.
+///
+/// # Arguments
+///
+/// * `input` - The names of the tools, e.g. `kubectl deployctl`.
+/// * `db` - The database instance.
+pub fn import_help(input: &str, db: &SqliteDatabase) {
+    if input.trim().is_empty() {
+        println!("Usage: --import-help <tool>...");
+        return;
+    }
+    for tool_name in input.split_whitespace() {
+        match read_help_output(tool_name) {
+            Ok(Some(tool)) => {
+                db.replace_tool(&tool).expect("Failed to store tool");
+                println!(
+                    "Read {} options and {} subcommands of {tool_name}.",
+                    tool.options.len(),
+                    tool.subcommands.len()
+                );
+            }
+            Ok(None) => println!(
+                "{}",
+                color_style::color_light_red(&format!(
+                    "No options found in the help of {tool_name}."
+                ))
+            ),
+            Err(err) => println!(
+                "{}",
+                color_style::color_light_red(&format!("Failed to run {tool_name}: {err}"))
+            ),
+        }
+    }
+}
+
+/// Explains a command line: what the tool does and what each of its flags means.
+///
+/// # Arguments
+///
+/// * `input` - The command line, e.g. `tar -xvf archive.tar`.
+/// * `db` - The database instance.
+pub fn explain_command(input: &str, db: &SqliteDatabase) {
+    let Some(tool_name) = input.split_whitespace().next() else {
+        println!("Usage: --explain <command>");
+        return;
+    };
+    match db.get_tool(tool_name).expect("Failed to get tool") {
+        Some(tool) => {
+            println!(
+                "{} - {}",
+                color_style::bold_text(&tool.name),
+                tool.summary
+            );
+            let (options, unknown_flags) = explain_flags(input, &tool);
+            print_option_explanations(&options);
+            print_unknown_flags(&unknown_flags, &tool);
+        }
+        None => println!("No documentation found for {tool_name}, run `cmd_cat --import-man` to read the man pages."),
+    }
+}
+
+/// Prints what the flags of a command line mean, and warns about the flags
+/// the tool does not document, if the tool is documented.
+///
+/// # Arguments
+///
+/// * `command_line` - The command line, e.g. `tar -xvf archive.tar`.
+/// * `db` - The database instance.
+pub fn print_flag_explanations(command_line: &str, db: &SqliteDatabase) {
+    let Some(tool_name) = command_line.split_whitespace().next() else {
+        return;
+    };
+    if let Ok(Some(tool)) = db.get_tool(tool_name) {
+        let (options, unknown_flags) = explain_flags(command_line, &tool);
+        print_option_explanations(&options);
+        print_unknown_flags(&unknown_flags, &tool);
+    }
+}
+
+/// Prints the options of a tool matching a flag or description.
+///
+/// # Arguments
+///
+/// * `text` - The text to look for.
+/// * `db` - The database instance.
+///
+/// # Returns
+///
+/// `true` if any option matched.
+pub fn print_matching_options(text: &str, db: &SqliteDatabase) -> bool {
+    let options = db.search_options(text).unwrap_or_default();
+    for (tool_name, option) in &options {
+        println!(
+            "  {} {}  {}",
+            color_style::bold_text(tool_name),
+            color_style::color_light_cyan(&option.flag),
+            short_description(&option.description)
+        );
+    }
+    !options.is_empty()
+}
+
+/// Finds the documented options and the subcommand used in a command line.
+/// Grouped short flags such as `-xvf` are split when the group itself is not an option.
+/// Flags are only reported as unknown before the first argument, as the flags after
+/// a subcommand or a file can belong to the subcommand or be arguments themselves.
+///
+/// # Arguments
+///
+/// * `command_line` - The command line.
+/// * `tool` - The tool of the command line, with its options.
+///
+/// # Returns
+///
+/// The options used in order, and the flags the tool does not document.
+fn explain_flags<'a>(command_line: &str, tool: &'a Tool) -> (Vec<&'a ToolOption>, Vec<String>) {
+    let find_option = |flag: &str| tool.options.iter().find(|option| option.matches(flag));
+    let mut explained = Vec::new();
+    let mut unknown_flags = Vec::new();
+    let mut validate = !tool.options.is_empty();
+    let mut subcommand_found = false;
+
+    for word in command_line.split_whitespace().skip(1) {
+        if word == "--" {
+            break;
+        }
+        if !word.starts_with('-') || word == "-" {
+            validate = false;
+            if !subcommand_found {
+                let subcommand = tool
+                    .subcommands
+                    .iter()
+                    .find(|subcommand| subcommand.flag.split(',').any(|name| name.trim() == word));
+                if let Some(subcommand) = subcommand {
+                    explained.push(subcommand);
+                    subcommand_found = true;
+                }
+            }
+            continue;
+        }
+        if let Some(option) = find_option(word) {
+            explained.push(option);
+        } else if !word.starts_with("--") {
+            let group: Vec<_> = word[1..]
+                .chars()
+                .map(|c| find_option(&format!("-{c}")))
+                .collect();
+            if group.iter().all(Option::is_some) {
+                explained.extend(group.into_iter().flatten());
+            } else if validate && !word[1..].chars().all(|c| c.is_ascii_digit()) {
+                unknown_flags.push(word.to_owned());
+            }
+        } else if validate {
+            unknown_flags.push(word.split('=').next().unwrap_or(word).to_owned());
+        }
+    }
+    explained.dedup();
+    (explained, unknown_flags)
+}
+
+/// Warns about the flags a tool does not document.
+///
+/// # Arguments
+///
+/// * `unknown_flags` - The flags.
+/// * `tool` - The tool.
+fn print_unknown_flags(unknown_flags: &[String], tool: &Tool) {
+    for flag in unknown_flags {
+        println!(
+            "  {}",
+            color_style::color_light_red(&format!(
+                "{flag}  not documented by {} ({})",
+                tool.name, tool.source
+            ))
+        );
+    }
+}
+
+/// Prints each option with the first sentence of its description.
+///
+/// # Arguments
+///
+/// * `options` - The options to print.
+fn print_option_explanations(options: &[&ToolOption]) {
+    for option in options {
+        println!(
+            "  {}  {}",
+            color_style::color_light_cyan(&option.flag),
+            color_style::color_grey(&short_description(&option.description))
+        );
+    }
+}
+
+/// Gets the first sentence of a description, cut to `MAX_EXPLANATION_LENGTH` characters.
+///
+/// # Arguments
+///
+/// * `description` - The description of an option.
+///
+/// # Returns
+///
+/// The short description.
+fn short_description(description: &str) -> String {
+    let sentence = description
+        .split_once(". ")
+        .map_or(description, |(sentence, _)| sentence);
+    if sentence.chars().count() > MAX_EXPLANATION_LENGTH {
+        let cut: String = sentence.chars().take(MAX_EXPLANATION_LENGTH).collect();
+        format!("{cut}...")
+    } else {
+        sentence.to_owned()
+    }
+}
@@ -31,6 +31,10 @@ pub fn color_light_cyan(text: &str) -> String {
     colorize_text(text, color::LightCyan)
 }
 
+pub fn color_grey(text: &str) -> String {
+    colorize_text(text, color::LightBlack)
+}
+
 /// Makes the given text bold.
 ///
 /// # Arguments
@@ -0,0 +1,603 @@
+use crate::commands::{Command, USER_SOURCE};
+use crate::database::file_parse::sheets::{placeholder_variable, SheetPlaceholder};
+use crate::database::file_parse::{_read_folder, print_parse_errors, read_page_file};
+use crate::database::{command_from_row, SqliteDatabase, COMMAND_COLUMNS};
+use rusqlite::{params, OptionalExtension, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// File formats commands can be exported to and imported from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExchangeFormat {
+    Json,
+    Toml,
+    /// A directory laid out like tldr-pages: `pages[.<language>]/<platform>/<page>.md`
+    Markdown,
+}
+
+impl ExchangeFormat {
+    /// Gets the format from its name as given with `--format`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - `json`, `toml`, `md` or `markdown`.
+    ///
+    /// # Returns
+    ///
+    /// The format, `None` for unknown names.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "json" => Some(ExchangeFormat::Json),
+            "toml" => Some(ExchangeFormat::Toml),
+            "md" | "markdown" => Some(ExchangeFormat::Markdown),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format from the file extension, paths without extension are Markdown directories.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file or directory to export to or import from.
+    ///
+    /// # Returns
+    ///
+    /// The format, `None` for unknown extensions.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) => Self::from_name(extension),
+            None => Some(ExchangeFormat::Markdown),
+        }
+    }
+}
+
+/// Restricts which commands are exported, `None` fields match every command.
+#[derive(Debug, Default)]
+pub struct ExportFilter {
+    pub source: Option<String>,
+    pub tag: Option<String>,
+    pub page: Option<String>,
+}
+
+/// Number of commands added and merged into existing ones by an import.
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    pub added: usize,
+    pub merged: usize,
+}
+
+/// A command as written to JSON and TOML files.
+#[derive(Serialize, Deserialize)]
+struct CommandRecord {
+    command: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    page: String,
+    #[serde(default = "default_platform")]
+    platform: String,
+    #[serde(default = "default_language")]
+    language: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    summary: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    url: String,
+    #[serde(default = "default_source")]
+    source: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default)]
+    favorite: bool,
+    /// How the placeholders are filled in, written like the placeholders of cheatsheets
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    placeholders: Vec<SheetPlaceholder>,
+}
+
+/// The top level of JSON and TOML files, TOML needs a table at the top.
+#[derive(Serialize, Deserialize)]
+struct CommandFile {
+    commands: Vec<CommandRecord>,
+}
+
+fn default_platform() -> String {
+    "common".to_string()
+}
+
+fn default_language() -> String {
+    "en".to_string()
+}
+
+fn default_source() -> String {
+    USER_SOURCE.to_string()
+}
+
+impl From<&Command> for CommandRecord {
+    fn from(command: &Command) -> Self {
+        CommandRecord {
+            command: command.name.clone(),
+            description: command.description.clone(),
+            page: command.page.clone(),
+            platform: command.platform.clone(),
+            language: command.language.clone(),
+            summary: command.summary.clone(),
+            url: command.url.clone(),
+            source: command.source.clone(),
+            tags: command.tags.clone(),
+            favorite: command.favorite,
+            placeholders: command
+                .variables
+                .iter()
+                .map(SheetPlaceholder::from)
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<CommandRecord> for Command {
+    type Error = String;
+
+    fn try_from(record: CommandRecord) -> Result<Self, Self::Error> {
+        let variables = record
+            .placeholders
+            .into_iter()
+            .map(placeholder_variable)
+            .collect::<Result<_, _>>()
+            .map_err(|message| format!("{}: {message}", record.command))?;
+        Ok(Command {
+            name: record.command,
+            description: record.description,
+            page: record.page,
+            platform: record.platform,
+            language: record.language,
+            summary: record.summary,
+            url: record.url,
+            source: record.source,
+            tags: record.tags,
+            favorite: record.favorite,
+            id: 0,
+            variables,
+        })
+    }
+}
+
+/// Writes commands to a file (JSON, TOML) or a directory of pages (Markdown).
+/// Markdown pages carry no tags, favorites, source or variables.
+///
+/// # Arguments
+///
+/// * `commands` - The commands to write.
+/// * `format` - The format to write.
+/// * `path` - The file or directory to write to, created if needed.
+///
+/// # Returns
+///
+/// Result indicating success or failure
+pub fn write_commands(commands: &[Command], format: ExchangeFormat, path: &Path) -> io::Result<()> {
+    let command_file = || CommandFile {
+        commands: commands.iter().map(CommandRecord::from).collect(),
+    };
+    match format {
+        ExchangeFormat::Json => {
+            let content =
+                serde_json::to_string_pretty(&command_file()).map_err(io::Error::other)?;
+            fs::write(path, content)
+        }
+        ExchangeFormat::Toml => {
+            let content = toml::to_string_pretty(&command_file()).map_err(io::Error::other)?;
+            fs::write(path, content)
+        }
+        ExchangeFormat::Markdown => write_markdown_pages(commands, path),
+    }
+}
+
+/// Reads commands from a file (JSON, TOML) or a directory of pages (Markdown).
+/// The commands read are personal commands, whatever source they were exported from,
+/// as the commands of other sources are replaced when those are updated.
+///
+/// # Arguments
+///
+/// * `path` - The file or directory to read.
+/// * `format` - The format to read.
+///
+/// # Returns
+///
+/// The commands read.
+pub fn read_commands(path: &Path, format: ExchangeFormat) -> io::Result<Vec<Command>> {
+    let invalid_data = |err: String| io::Error::new(io::ErrorKind::InvalidData, err);
+    let command_file: CommandFile = match format {
+        ExchangeFormat::Json => serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| invalid_data(e.to_string()))?,
+        ExchangeFormat::Toml => {
+            toml::from_str(&fs::read_to_string(path)?).map_err(|e| invalid_data(e.to_string()))?
+        }
+        ExchangeFormat::Markdown => return read_markdown_pages(path),
+    };
+    command_file
+        .commands
+        .into_iter()
+        .map(|record| {
+            Command::try_from(record)
+                .map(|command| command.with_source(USER_SOURCE))
+                .map_err(invalid_data)
+        })
+        .collect()
+}
+
+/// Writes one tldr-style page per page, platform and language.
+///
+/// # Arguments
+///
+/// * `commands` - The commands to write.
+/// * `dir` - The directory the `pages` directories are created in.
+///
+/// # Returns
+///
+/// Result indicating success or failure
+fn write_markdown_pages(commands: &[Command], dir: &Path) -> io::Result<()> {
+    let mut pages: BTreeMap<PathBuf, Vec<&Command>> = BTreeMap::new();
+    for command in commands {
+        pages
+            .entry(markdown_page_path(command))
+            .or_default()
+            .push(command);
+    }
+
+    for (page_path, page_commands) in pages {
+        let path = dir.join(page_path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, render_markdown_page(&page_commands))?;
+    }
+    Ok(())
+}
+
+/// Gets the path of the page a command is written to, commands without page are grouped by source.
+///
+/// # Arguments
+///
+/// * `command` - The command to place.
+///
+/// # Returns
+///
+/// The path relative to the export directory.
+fn markdown_page_path(command: &Command) -> PathBuf {
+    let pages_dir = match command.language.as_str() {
+        "en" | "" => "pages".to_string(),
+        language => format!("pages.{language}"),
+    };
+    let page = if command.page.is_empty() {
+        &command.source
+    } else {
+        &command.page
+    };
+    PathBuf::from(pages_dir)
+        .join(&command.platform)
+        .join(format!("{}.md", page.replace('/', "-")))
+}
+
+/// Renders the commands of one page in the tldr page format.
+///
+/// # Arguments
+///
+/// * `commands` - The commands of the page, all sharing the page metadata.
+///
+/// # Returns
+///
+/// The page content.
+fn render_markdown_page(commands: &[&Command]) -> String {
+    let first = commands[0];
+    let title = if first.page.is_empty() {
+        &first.source
+    } else {
+        &first.page
+    };
+
+    let mut content = format!("# {title}\n");
+    if !first.summary.is_empty() || !first.url.is_empty() {
+        content += "\n";
+    }
+    if !first.summary.is_empty() {
+        content += &format!("> {}\n", first.summary);
+    }
+    if !first.url.is_empty() {
+        content += &format!("> More information: <{}>.\n", first.url);
+    }
+    for command in commands {
+        content += &format!(
+            "\n- {}\n\n{}\n",
+            command.description,
+            inline_code(&command.name)
+        );
+    }
+    content
+}
+
+/// Writes a command as inline code, fenced with more backticks than it holds in a row.
+///
+/// # Arguments
+///
+/// * `command` - The command.
+///
+/// # Returns
+///
+/// The command between backticks, doubled for a command like `` echo `date` ``.
+fn inline_code(command: &str) -> String {
+    let longest_run = command.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    if longest_run == 0 {
+        return format!("`{command}`");
+    }
+    let fence = "`".repeat(longest_run + 1);
+    format!("{fence} {command} {fence}")
+}
+
+/// Reads the commands of a single page or of every page in a directory.
+/// The page written for personal commands without page (named after `USER_SOURCE`) is read back without page.
+///
+/// # Arguments
+///
+/// * `path` - The page or directory.
+///
+/// # Returns
+///
+/// The commands of the pages, as personal commands.
+fn read_markdown_pages(path: &Path) -> io::Result<Vec<Command>> {
+    let path = path.to_string_lossy();
+    let filepaths = if Path::new(path.as_ref()).is_dir() {
+        _read_folder(&path)
+    } else {
+        vec![path.into_owned()]
+    };
+
+    let mut commands = Vec::new();
+    for filepath in filepaths
+        .iter()
+        .filter(|filepath| filepath.ends_with(".md"))
+    {
+        let (page_commands, errors) =
+            read_page_file(filepath).map_err(|err| io::Error::other(err.to_string()))?;
+        print_parse_errors(filepath, &errors);
+        commands.extend(page_commands.into_iter().map(|mut command| {
+            if command.page == USER_SOURCE {
+                command.page.clear();
+            }
+            command.with_source(USER_SOURCE)
+        }));
+    }
+    Ok(commands)
+}
+
+impl SqliteDatabase {
+    /// Get the commands matching the export filter, along with their variables, in insertion order.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - The source, tag and page to restrict the export to.
+    ///
+    /// # Returns
+    ///
+    /// A vector of the commands to export
+    pub fn export_commands(&self, filter: &ExportFilter) -> Result<Vec<Command>> {
+        let mut stmt = self.connection.prepare(&format!(
+            "SELECT {COMMAND_COLUMNS} FROM commands
+            WHERE (?1 IS NULL OR commands.source = ?1)
+            AND (?2 IS NULL OR EXISTS(SELECT 1 FROM tags WHERE tags.command_id = commands.id AND tags.tag = ?2))
+            AND (?3 IS NULL OR commands.page = ?3)
+            ORDER BY commands.id"
+        ))?;
+        let rows = stmt.query_map(
+            params![filter.source, filter.tag, filter.page],
+            command_from_row,
+        )?;
+        rows.map(|command| {
+            let mut command = command?;
+            command.variables = self.get_variables(command.id)?;
+            Ok(command)
+        })
+        .collect()
+    }
+
+    /// Imports commands in a single transaction. A command with the same text, page, platform
+    /// and language as a stored command of any source is merged into it (tags and star are added,
+    /// and its variables are replaced if the imported command has any),
+    /// any other command is inserted with its own source.
+    ///
+    /// # Arguments
+    ///
+    /// * `commands` - The commands to import.
+    ///
+    /// # Returns
+    ///
+    /// The number of added and merged commands.
+    pub fn import_commands(&self, commands: &[Command]) -> Result<ImportSummary> {
+        let transaction = self.connection.unchecked_transaction()?;
+        let mut summary = ImportSummary::default();
+
+        for command in commands {
+            let existing_id: Option<i64> = self
+                .connection
+                .query_row(
+                    "SELECT id FROM commands WHERE command_name = ?1 AND page = ?2
+                    AND platform = ?3 AND language = ?4
+                    ORDER BY id LIMIT 1",
+                    params![
+                        command.name,
+                        command.page,
+                        command.platform,
+                        command.language
+                    ],
+                    |row| row.get(0),
+                )
+                .optional()?;
+
+            let id = match existing_id {
+                Some(id) => {
+                    summary.merged += 1;
+                    id
+                }
+                None => {
+                    summary.added += 1;
+                    self.insert(command)?
+                }
+            };
+            for tag in &command.tags {
+                self.add_tag(id, tag)?;
+            }
+            if command.favorite {
+                self.set_favorite(id, true)?;
+            }
+            if !command.variables.is_empty() {
+                self.set_variables(id, &command.variables)?;
+            }
+        }
+
+        transaction.commit()?;
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{Page, Variable, VariableKind, TLDR_SOURCE};
+
+    fn database_with_variables() -> (SqliteDatabase, Vec<Variable>) {
+        let db = SqliteDatabase::open(":memory:").unwrap();
+        let command =
+            Command::new("kill {{pid}} -s {{signal}}", "Send a signal:").with_source(USER_SOURCE);
+        let id = db.insert(&command).unwrap();
+        let variables = vec![
+            Variable {
+                name: "pid".to_owned(),
+                kind: VariableKind::Command,
+                generator: "ps -eo pid=".to_owned(),
+                ..Default::default()
+            },
+            Variable {
+                name: "signal".to_owned(),
+                kind: VariableKind::Choice,
+                choices: vec!["TERM".to_owned(), "KILL".to_owned()],
+                default: "TERM".to_owned(),
+                ..Default::default()
+            },
+        ];
+        db.set_variables(id, &variables).unwrap();
+        (db, variables)
+    }
+
+    #[test]
+    fn variables_survive_export_and_import() {
+        for (format, extension) in [
+            (ExchangeFormat::Json, "json"),
+            (ExchangeFormat::Toml, "toml"),
+        ] {
+            let (db, variables) = database_with_variables();
+            let path = std::env::temp_dir().join(format!(
+                "cmd-cat-exchange-{}.{extension}",
+                std::process::id()
+            ));
+            let commands = db.export_commands(&ExportFilter::default()).unwrap();
+            assert_eq!(commands[0].variables, variables);
+            write_commands(&commands, format, &path).unwrap();
+
+            let imported = read_commands(&path, format).unwrap();
+            fs::remove_file(&path).unwrap();
+            assert_eq!(imported[0].variables, variables);
+
+            let target = SqliteDatabase::open(":memory:").unwrap();
+            assert_eq!(target.import_commands(&imported).unwrap().added, 1);
+            let exported = target.export_commands(&ExportFilter::default()).unwrap();
+            assert_eq!(exported[0].variables, variables);
+        }
+    }
+
+    #[test]
+    fn unknown_placeholder_type_is_rejected() {
+        let path =
+            std::env::temp_dir().join(format!("cmd-cat-exchange-bad-{}.json", std::process::id()));
+        fs::write(
+            &path,
+            r#"{"commands": [{"command": "ls {{dir}}", "placeholders": [{"name": "dir", "type": "folder"}]}]}"#,
+        )
+        .unwrap();
+        let result = read_commands(&path, ExchangeFormat::Json);
+        fs::remove_file(&path).unwrap();
+        let err = result.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("ls {{dir}}: "), "{err}");
+    }
+
+    #[test]
+    fn exported_pages_merge_into_the_pages_they_come_from() {
+        let db = SqliteDatabase::open(":memory:").unwrap();
+        let page = Page::new("tar", "common", "en");
+        let command = Command::new("tar xf {{source.tar}}", "Extract an archive:")
+            .with_page(&page)
+            .with_source(TLDR_SOURCE);
+        let id = db.insert(&command).unwrap();
+        let dir = std::env::temp_dir().join(format!("cmd-cat-exchange-md-{}", std::process::id()));
+        let commands = db.export_commands(&ExportFilter::default()).unwrap();
+        write_commands(&commands, ExchangeFormat::Markdown, &dir).unwrap();
+
+        let imported = read_commands(&dir, ExchangeFormat::Markdown).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        let summary = db.import_commands(&imported).unwrap();
+        assert_eq!((summary.added, summary.merged), (0, 1));
+        let stored = db.export_commands(&ExportFilter::default()).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!((stored[0].id, stored[0].source.as_str()), (id, TLDR_SOURCE));
+    }
+
+    #[test]
+    fn imported_commands_are_personal_commands() {
+        let path = std::env::temp_dir().join(format!(
+            "cmd-cat-exchange-sources-{}.json",
+            std::process::id()
+        ));
+        fs::write(
+            &path,
+            r#"{"commands": [
+                {"command": "ls -la", "source": "tldr"},
+                {"command": "ll", "source": "alias", "tags": ["alias"]},
+                {"command": "pwd"}
+            ]}"#,
+        )
+        .unwrap();
+        let imported = read_commands(&path, ExchangeFormat::Json);
+        fs::remove_file(&path).unwrap();
+
+        let db = SqliteDatabase::open(":memory:").unwrap();
+        assert_eq!(db.import_commands(&imported.unwrap()).unwrap().added, 3);
+        let sources: Vec<String> = db
+            .export_commands(&ExportFilter::default())
+            .unwrap()
+            .into_iter()
+            .map(|command| command.source)
+            .collect();
+        assert_eq!(sources, [USER_SOURCE; 3]);
+    }
+
+    #[test]
+    fn commands_with_backticks_get_a_longer_fence() {
+        assert_eq!(inline_code("ls -la"), "`ls -la`");
+        assert_eq!(inline_code("echo `date`"), "`` echo `date` ``");
+        assert_eq!(inline_code("echo ``x``"), "``` echo ``x`` ```");
+
+        let db = SqliteDatabase::open(":memory:").unwrap();
+        for name in ["echo `date`", "`pwd`"] {
+            db.insert(&Command::new(name, "Print:").with_source(USER_SOURCE))
+                .unwrap();
+        }
+        let dir =
+            std::env::temp_dir().join(format!("cmd-cat-exchange-fence-{}", std::process::id()));
+        let commands = db.export_commands(&ExportFilter::default()).unwrap();
+        write_commands(&commands, ExchangeFormat::Markdown, &dir).unwrap();
+        let imported = read_commands(&dir, ExchangeFormat::Markdown).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        let names: Vec<&str> = imported.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["echo `date`", "`pwd`"]);
+    }
+}
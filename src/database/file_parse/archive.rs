@@ -0,0 +1,62 @@
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// The archive formats pages can be read from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArchiveFormat {
+    Tar,
+    /// A tar archive compressed with gzip, as GitHub serves repositories
+    TarGz,
+    /// As the tldr-pages releases are published
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Gets the format of an archive from its extension.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the archive, e.g. `tldr.zip` or `tldr-main.tar.gz`.
+    ///
+    /// # Returns
+    ///
+    /// The format, `None` if the path is not an archive.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let file_name = path.file_name()?.to_str()?.to_lowercase();
+        if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else if file_name.ends_with(".tar") {
+            Some(ArchiveFormat::Tar)
+        } else if file_name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+/// Extracts an archive into a folder. Entries pointing outside of the folder are skipped.
+///
+/// # Arguments
+///
+/// * `archive_path` - The path of the archive.
+/// * `format` - The format of the archive.
+/// * `destination` - The folder to extract to, created if needed.
+///
+/// # Returns
+///
+/// Result indicating success or failure
+pub fn extract_archive(
+    archive_path: &Path,
+    format: ArchiveFormat,
+    destination: &Path,
+) -> io::Result<()> {
+    let file = File::open(archive_path)?;
+    match format {
+        ArchiveFormat::Tar => tar::Archive::new(file).unpack(destination),
+        ArchiveFormat::TarGz => tar::Archive::new(GzDecoder::new(file)).unpack(destination),
+        ArchiveFormat::Zip => Ok(zip::ZipArchive::new(file)?.extract(destination)?),
+    }
+}
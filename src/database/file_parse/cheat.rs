@@ -0,0 +1,257 @@
+use crate::commands::{Command, CHEAT_SOURCE};
+use crate::database::file_parse::navi::convert_variables;
+use crate::database::file_parse::tldr::ParseError;
+use serde::Deserialize;
+
+/// Parses a cheatsheet of `cheat`, with its optional YAML front matter:
+///
+/// ```text
+/// ---
+/// syntax: bash
+/// tags: [ vcs, development ]
+/// ---
+/// # To stage all changes in the current directory:
+/// git add --all
+/// ```
+///
+/// Each line of a command block is a command described by the comment above it,
+/// lines ending with `\` continue on the next line. Commands without comment are described
+/// by themselves. `<name>` words become `{{name}}` placeholders, as in navi cheatsheets.
+///
+/// # Arguments
+///
+/// * `input` - The content of the cheatsheet.
+///
+/// # Returns
+///
+/// The commands, with `CHEAT_SOURCE` as source and the tags of the front matter,
+/// and the errors found in the cheatsheet.
+pub fn parse_cheatsheet(input: &str) -> (Vec<Command>, Vec<ParseError>) {
+    let mut commands = Vec::new();
+    let mut errors = Vec::new();
+    let mut lines = input.lines().enumerate().peekable();
+
+    let mut tags = Vec::new();
+    if lines
+        .peek()
+        .is_some_and(|(_, line)| line.trim_end() == "---")
+    {
+        lines.next();
+        let mut front_matter = Vec::new();
+        let mut closed = false;
+        for (_, line) in lines.by_ref() {
+            if line.trim_end() == "---" {
+                closed = true;
+                break;
+            }
+            front_matter.push(line);
+        }
+        if !closed {
+            errors.push(ParseError::new(
+                1,
+                "the front matter is never closed with `---`",
+            ));
+            return (commands, errors);
+        }
+        match parse_front_matter(&front_matter.join("\n")) {
+            Ok(front_matter) => tags = front_matter.tags,
+            Err(err) => {
+                // the front matter starts on line 2, after the opening `---`
+                let line = err.location().map_or(1, |location| location.line() + 1);
+                errors.push(ParseError::new(
+                    line,
+                    &format!("invalid front matter: {err}"),
+                ));
+            }
+        }
+    }
+
+    let mut description = String::new();
+    let mut comment_continues = false;
+    let mut continued_command = String::new();
+    for (index, line) in lines {
+        let trimmed_line = line.trim();
+        if let Some(comment) = trimmed_line.strip_prefix('#') {
+            if !comment_continues {
+                description.clear();
+            }
+            if !description.is_empty() {
+                description.push(' ');
+            }
+            description.push_str(comment.trim().trim_end_matches(':'));
+            comment_continues = true;
+            continue;
+        }
+        comment_continues = false;
+        if trimmed_line.is_empty() {
+            if !continued_command.is_empty() {
+                // `index` is the number of the line above, ending with `\`
+                errors.push(ParseError::new(
+                    index,
+                    "the command continues on an empty line",
+                ));
+                continued_command.clear();
+            }
+            description.clear();
+            continue;
+        }
+
+        if let Some(start) = trimmed_line.strip_suffix('\\') {
+            continued_command.push_str(start.trim_end());
+            continued_command.push(' ');
+            continue;
+        }
+        continued_command.push_str(trimmed_line);
+        let (command_text, _) = convert_variables(&continued_command);
+        continued_command.clear();
+
+        let command_description = match description.as_str() {
+            "" => command_text.clone(),
+            text => text.to_owned(),
+        };
+        let mut command =
+            Command::new(&command_text, &command_description).with_source(CHEAT_SOURCE);
+        command.tags = tags.clone();
+        commands.push(command);
+    }
+
+    if !continued_command.is_empty() {
+        errors.push(ParseError::new(
+            input.lines().count(),
+            "the command continues past the end of the cheatsheet",
+        ));
+    }
+
+    (commands, errors)
+}
+
+/// The YAML front matter of a cheatsheet, only the tags are read.
+#[derive(Default, Deserialize)]
+struct FrontMatter {
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// Parses the YAML front matter of a cheatsheet.
+///
+/// # Arguments
+///
+/// * `content` - The lines between the `---` lines.
+///
+/// # Returns
+///
+/// The front matter, empty if there is nothing but whitespace and comments.
+fn parse_front_matter(content: &str) -> Result<FrontMatter, serde_yaml::Error> {
+    let value: serde_yaml::Value = serde_yaml::from_str(content)?;
+    match value {
+        serde_yaml::Value::Null => Ok(FrontMatter::default()),
+        value => serde_yaml::from_value(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names_and_descriptions(commands: &[Command]) -> Vec<(&str, &str)> {
+        commands
+            .iter()
+            .map(|command| (command.name.as_str(), command.description.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_cheatsheet() {
+        let input = "---\n\
+            syntax: bash\n\
+            tags: [ vcs, \"development\" ]\n\
+            ---\n\
+            # To stage all changes\n\
+            # in the current directory:\n\
+            git add --all\n\
+            git add <file>\n\
+            \n\
+            git commit \\\n\
+              --amend\n";
+
+        let (commands, errors) = parse_cheatsheet(input);
+
+        assert_eq!(errors, []);
+        assert_eq!(
+            names_and_descriptions(&commands),
+            [
+                (
+                    "git add --all",
+                    "To stage all changes in the current directory"
+                ),
+                (
+                    "git add {{file}}",
+                    "To stage all changes in the current directory"
+                ),
+                ("git commit --amend", "git commit --amend"),
+            ]
+        );
+        for command in &commands {
+            assert_eq!(command.tags, ["vcs", "development"]);
+            assert_eq!(command.source, CHEAT_SOURCE);
+        }
+    }
+
+    #[test]
+    fn reads_tags_of_block_lists() {
+        let input = "---\ntags:\n  - vcs\n  - 'git'\nsyntax: bash\n---\ngit status\n";
+        let (commands, errors) = parse_cheatsheet(input);
+        assert_eq!(errors, []);
+        assert_eq!(commands[0].tags, ["vcs", "git"]);
+    }
+
+    #[test]
+    fn reads_cheatsheets_without_tags() {
+        for input in [
+            "git status\n",
+            "---\n---\ngit status\n",
+            "---\nsyntax: bash\n---\ngit status\n",
+        ] {
+            let (commands, errors) = parse_cheatsheet(input);
+            assert_eq!(errors, [], "{input}");
+            assert_eq!(
+                names_and_descriptions(&commands),
+                [("git status", "git status")]
+            );
+            assert!(commands[0].tags.is_empty());
+        }
+    }
+
+    #[test]
+    fn reports_invalid_front_matter() {
+        let (commands, errors) = parse_cheatsheet("---\nsyntax: bash\ntags: [a\n---\nls\n");
+        assert_eq!(commands.len(), 1);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.starts_with("invalid front matter"));
+        // the list is still open at the closing `---`
+        assert_eq!(errors[0].line, 4);
+
+        let (commands, errors) = parse_cheatsheet("---\ntags: [a]\nls\n");
+        assert!(commands.is_empty());
+        assert_eq!(
+            errors,
+            [ParseError::new(
+                1,
+                "the front matter is never closed with `---`"
+            )]
+        );
+    }
+
+    #[test]
+    fn reports_unfinished_commands() {
+        let (commands, errors) = parse_cheatsheet("ls \\\n\nls -l \\\n");
+        assert!(commands.is_empty());
+        assert_eq!(
+            errors,
+            [
+                ParseError::new(1, "the command continues on an empty line"),
+                ParseError::new(3, "the command continues past the end of the cheatsheet"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,566 @@
+use crate::commands::{Tool, ToolOption, HELP_SOURCE};
+use std::io::{self, Read};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{self, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use std::{env, thread};
+
+/// How long a tool gets to print its help before it is killed.
+const HELP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often a running tool is checked for completion.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Runs a tool to get its help and parses the options and subcommands out of it.
+/// `--help` is tried first, `-h` and `help` only when the first output mentions them,
+/// since they are not help flags for every tool.
+/// The tool runs with an empty environment, no input, and is killed after `HELP_TIMEOUT`.
+///
+/// # Arguments
+///
+/// * `tool_name` - The name of the tool, looked up in `PATH`, or a path to it.
+///
+/// # Returns
+///
+/// The tool, `None` if it printed no options or subcommands.
+///
+/// # Errors
+///
+/// Returns an error if the tool could not be found or started.
+pub fn read_help_output(tool_name: &str) -> io::Result<Option<Tool>> {
+    let program = find_executable(tool_name).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{tool_name} is not in PATH"),
+        )
+    })?;
+    let name = Path::new(tool_name)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(tool_name);
+
+    let mut output = run_with_timeout(&program, "--help")?.unwrap_or_default();
+    let mut tool = parse_help_output(name, &output);
+    for fallback in ["-h", "help"] {
+        if !tool.options.is_empty() || !tool.subcommands.is_empty() {
+            break;
+        }
+        let mentioned = output
+            .split_whitespace()
+            .any(|word| word.trim_matches(|c: char| !c.is_alphanumeric() && c != '-') == fallback);
+        if !mentioned {
+            continue;
+        }
+        output = run_with_timeout(&program, fallback)?.unwrap_or_default();
+        tool = parse_help_output(name, &output);
+    }
+
+    if tool.options.is_empty() && tool.subcommands.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(tool))
+}
+
+/// Parses the help of a tool. The option lists of clap, argparse, GNU getopt and cobra
+/// are recognised: an entry starts with its flags (or subcommand name), followed by two spaces
+/// and the description, which can continue on more indented lines.
+/// Subcommands are read from sections such as `Commands:` or `Available Commands:`,
+/// and from the `{a,b}` choices of argparse.
+///
+/// # Arguments
+///
+/// * `name` - The name of the tool.
+/// * `output` - The help printed by the tool.
+///
+/// # Returns
+///
+/// The tool with its options and subcommands.
+pub fn parse_help_output(name: &str, output: &str) -> Tool {
+    let mut tool = Tool {
+        name: name.to_owned(),
+        source: HELP_SOURCE.to_owned(),
+        ..Default::default()
+    };
+    let mut in_commands = false;
+    // cobra prints the usage on the line after `Usage:`
+    let mut usage_follows = false;
+    let mut choices: Vec<String> = Vec::new();
+    // the entry being read, its indentation and whether it is a subcommand
+    let mut entry: Option<(ToolOption, usize, bool)> = None;
+
+    for line in output.lines() {
+        let trimmed_line = line.trim();
+        let indent = line.len() - line.trim_start().len();
+
+        if trimmed_line.is_empty() {
+            push_entry(&mut tool, entry.take());
+            continue;
+        }
+        if let Some(usage) = strip_prefix_ignore_case(trimmed_line, "usage:") {
+            push_entry(&mut tool, entry.take());
+            if tool.synopsis.is_empty() {
+                tool.synopsis = usage.trim().to_owned();
+                usage_follows = tool.synopsis.is_empty();
+            }
+            continue;
+        }
+        if usage_follows {
+            tool.synopsis = trimmed_line.to_owned();
+            usage_follows = false;
+            continue;
+        }
+        if indent == 0 && trimmed_line.ends_with(':') && !trimmed_line.contains("  ") {
+            push_entry(&mut tool, entry.take());
+            let heading = trimmed_line.to_lowercase();
+            in_commands = heading.contains("command");
+            continue;
+        }
+        if let Some(names) = trimmed_line
+            .strip_prefix('{')
+            .and_then(|names| names.split_once('}'))
+            .map(|(names, _)| names)
+        {
+            choices.extend(names.split(',').map(|choice| choice.trim().to_owned()));
+            continue;
+        }
+
+        let (tag, description) = split_entry(trimmed_line);
+        let is_option =
+            tag.starts_with('-') && tag[1..].starts_with(|c: char| c.is_alphanumeric() || c == '-');
+        let is_subcommand = !is_option
+            && indent > 0
+            && !description.is_empty()
+            && (in_commands || choices.iter().any(|choice| choice == tag))
+            && tag
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | ',' | ' ' | ':'));
+
+        if is_option || is_subcommand {
+            push_entry(&mut tool, entry.take());
+            let option = ToolOption {
+                flag: tag.to_owned(),
+                description: description.to_owned(),
+            };
+            entry = Some((option, indent, is_subcommand));
+        } else if let Some((option, entry_indent, _)) = entry.as_mut() {
+            if indent > *entry_indent {
+                if !option.description.is_empty() {
+                    option.description.push(' ');
+                }
+                option.description.push_str(trimmed_line);
+            } else {
+                push_entry(&mut tool, entry.take());
+            }
+        } else if tool.summary.is_empty()
+            && tool.options.is_empty()
+            && indent == 0
+            && !is_version_line(name, trimmed_line)
+        {
+            tool.summary = trimmed_line.to_owned();
+        }
+    }
+    push_entry(&mut tool, entry.take());
+
+    for choice in choices {
+        if !tool
+            .subcommands
+            .iter()
+            .any(|subcommand| subcommand.flag == choice)
+        {
+            tool.subcommands.push(ToolOption {
+                flag: choice,
+                description: String::new(),
+            });
+        }
+    }
+    tool
+}
+
+/// Adds the entry being read to the options or subcommands of the tool,
+/// subcommands listed twice are only added once.
+///
+/// # Arguments
+///
+/// * `tool` - The tool being read.
+/// * `entry` - The entry, its indentation and whether it is a subcommand.
+fn push_entry(tool: &mut Tool, entry: Option<(ToolOption, usize, bool)>) {
+    let Some((option, _, is_subcommand)) = entry else {
+        return;
+    };
+    if !is_subcommand {
+        tool.options.push(option);
+    } else if !tool
+        .subcommands
+        .iter()
+        .any(|subcommand| subcommand.flag == option.flag)
+    {
+        tool.subcommands.push(option);
+    }
+}
+
+/// Splits an entry of an option list into its flags (or name) and its description,
+/// separated by at least two spaces or a tab.
+///
+/// # Arguments
+///
+/// * `line` - The trimmed line, e.g. `-a, --all   do not ignore entries starting with .`.
+///
+/// # Returns
+///
+/// The flags and the description, empty if it is on the next lines.
+fn split_entry(line: &str) -> (&str, &str) {
+    let separator = [line.find("  "), line.find('\t')]
+        .into_iter()
+        .flatten()
+        .min();
+    match separator {
+        Some(index) => (line[..index].trim(), line[index..].trim()),
+        None => (line, ""),
+    }
+}
+
+/// Checks if a line only gives the version of the tool, e.g. `cargo 1.80.0` or `tool v2`.
+///
+/// # Arguments
+///
+/// * `name` - The name of the tool.
+/// * `line` - The trimmed line.
+///
+/// # Returns
+///
+/// `true` if the line is the name of the tool followed by a version.
+fn is_version_line(name: &str, line: &str) -> bool {
+    line.strip_prefix(name)
+        .map(|rest| rest.trim_start().trim_start_matches(['v', 'V']))
+        .is_some_and(|version| version.starts_with(|c: char| c.is_ascii_digit()))
+}
+
+/// Strips a prefix from a string, ignoring ASCII case.
+///
+/// # Arguments
+///
+/// * `text` - The string.
+/// * `prefix` - The prefix, in lowercase.
+///
+/// # Returns
+///
+/// The rest of the string, `None` if it does not start with the prefix.
+fn strip_prefix_ignore_case<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    let start = text.get(..prefix.len())?;
+    start
+        .eq_ignore_ascii_case(prefix)
+        .then(|| &text[prefix.len()..])
+}
+
+/// Finds the executable of a tool, as the shell would.
+///
+/// # Arguments
+///
+/// * `tool_name` - The name of the tool, or a path to it.
+///
+/// # Returns
+///
+/// The path of the executable, `None` if there is none.
+fn find_executable(tool_name: &str) -> Option<PathBuf> {
+    if tool_name.contains('/') {
+        let path = PathBuf::from(tool_name);
+        return path.is_file().then_some(path);
+    }
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(tool_name))
+        .find(|path| path.is_file())
+}
+
+/// Runs a program with one argument, an empty environment and no input,
+/// and collects what it prints on stdout and stderr.
+///
+/// # Arguments
+///
+/// * `program` - The path of the program.
+/// * `arg` - The argument, e.g. `--help`.
+///
+/// # Returns
+///
+/// The output, `None` if the program was killed after `HELP_TIMEOUT`, or if its output
+/// was still not closed by then (a process it started in the background keeps the pipes open).
+fn run_with_timeout(program: &Path, arg: &str) -> io::Result<Option<String>> {
+    let name = program.file_name().unwrap_or(program.as_os_str());
+    let mut child = process::Command::new(program)
+        .arg0(name)
+        .arg(arg)
+        .env_clear()
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // read both pipes while waiting, a tool printing a long help would block on a full pipe
+    let (sender, receiver) = mpsc::channel();
+    let pipes: Vec<_> = [
+        child
+            .stdout
+            .take()
+            .map(|pipe| Box::new(pipe) as Box<dyn Read + Send>),
+        child
+            .stderr
+            .take()
+            .map(|pipe| Box::new(pipe) as Box<dyn Read + Send>),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    let pipe_count = pipes.len();
+    for (index, mut pipe) in pipes.into_iter().enumerate() {
+        let sender = sender.clone();
+        thread::spawn(move || {
+            let mut output = Vec::new();
+            let _ = pipe.read_to_end(&mut output);
+            let _ = sender.send((index, output));
+        });
+    }
+
+    let deadline = Instant::now() + HELP_TIMEOUT;
+    while child.try_wait()?.is_none() {
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(None);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    // stdout first, then stderr
+    let mut outputs = vec![Vec::new(); pipe_count];
+    for _ in 0..pipe_count {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        match receiver.recv_timeout(timeout) {
+            Ok((index, output)) => outputs[index] = output,
+            Err(_) => return Ok(None),
+        }
+    }
+    Ok(Some(
+        String::from_utf8_lossy(&outputs.concat()).into_owned(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const CLAP_HELP: &str = "\
+Rust's package manager
+
+Usage: cargo [OPTIONS] [COMMAND]
+
+Options:
+  -V, --version                  Print version info and exit
+      --list                     List installed commands
+  -v, --verbose...               Use verbose output (-vv very verbose/build.rs output)
+  -C <DIRECTORY>                 Change to DIRECTORY before doing anything
+                                 (nightly-only)
+  -h, --help                     Print help
+
+Commands:
+    build, b    Compile the current package
+    check, c    Analyze the current package and report errors
+    new         Create a new cargo package
+";
+
+    const ARGPARSE_HELP: &str = "\
+usage: pip-tool [-h] [--verbose] {install,remove} ...
+
+Manage packages.
+
+positional arguments:
+  {install,remove}
+    install       Install a package
+    remove        Remove a package
+
+options:
+  -h, --help      show this help message and exit
+  --verbose, -v   print more output
+  --index-url URL
+                  base URL of the package index
+";
+
+    const GETOPT_HELP: &str = "\
+Usage: ls [OPTION]... [FILE]...
+List information about the FILEs (the current directory by default).
+
+Mandatory arguments to long options are mandatory for short options too.
+  -a, --all                  do not ignore entries starting with .
+  -B, --ignore-backups       do not list implied entries ending with ~
+      --block-size=SIZE      with -l, scale sizes by SIZE when printing them;
+                               e.g., '--block-size=M'; see SIZE format below
+  -w, --width=COLS           set output width to COLS.  0 means no limit
+";
+
+    const COBRA_HELP: &str = "\
+kubectl controls the Kubernetes cluster manager.
+
+Usage:
+  kubectl [flags] [options]
+
+Available Commands:
+  apply         Apply a configuration to a resource by file name or stdin
+  get           Display one or many resources
+
+Flags:
+  -n, --namespace string   If present, the namespace scope for this CLI request
+      --kubeconfig string  Path to the kubeconfig file to use for CLI requests.
+";
+
+    fn entries(options: &[ToolOption]) -> Vec<(&str, &str)> {
+        options
+            .iter()
+            .map(|option| (option.flag.as_str(), option.description.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_clap_help() {
+        let tool = parse_help_output("cargo", CLAP_HELP);
+        assert_eq!(tool.source, HELP_SOURCE);
+        assert_eq!(tool.summary, "Rust's package manager");
+        assert_eq!(tool.synopsis, "cargo [OPTIONS] [COMMAND]");
+        assert_eq!(
+            entries(&tool.options),
+            [
+                ("-V, --version", "Print version info and exit"),
+                ("--list", "List installed commands"),
+                (
+                    "-v, --verbose...",
+                    "Use verbose output (-vv very verbose/build.rs output)"
+                ),
+                (
+                    "-C <DIRECTORY>",
+                    "Change to DIRECTORY before doing anything (nightly-only)"
+                ),
+                ("-h, --help", "Print help"),
+            ]
+        );
+        assert_eq!(
+            entries(&tool.subcommands),
+            [
+                ("build, b", "Compile the current package"),
+                ("check, c", "Analyze the current package and report errors"),
+                ("new", "Create a new cargo package"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_argparse_help() {
+        let tool = parse_help_output("pip-tool", ARGPARSE_HELP);
+        assert_eq!(tool.summary, "Manage packages.");
+        assert_eq!(
+            tool.synopsis,
+            "pip-tool [-h] [--verbose] {install,remove} ..."
+        );
+        assert_eq!(
+            entries(&tool.options),
+            [
+                ("-h, --help", "show this help message and exit"),
+                ("--verbose, -v", "print more output"),
+                ("--index-url URL", "base URL of the package index"),
+            ]
+        );
+        assert_eq!(
+            entries(&tool.subcommands),
+            [
+                ("install", "Install a package"),
+                ("remove", "Remove a package"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_getopt_help() {
+        let tool = parse_help_output("ls", GETOPT_HELP);
+        assert_eq!(
+            tool.summary,
+            "List information about the FILEs (the current directory by default)."
+        );
+        assert_eq!(tool.synopsis, "ls [OPTION]... [FILE]...");
+        assert_eq!(
+            entries(&tool.options),
+            [
+                ("-a, --all", "do not ignore entries starting with ."),
+                ("-B, --ignore-backups", "do not list implied entries ending with ~"),
+                (
+                    "--block-size=SIZE",
+                    "with -l, scale sizes by SIZE when printing them; e.g., '--block-size=M'; see SIZE format below"
+                ),
+                ("-w, --width=COLS", "set output width to COLS.  0 means no limit"),
+            ]
+        );
+        assert!(tool.subcommands.is_empty());
+        assert!(tool.options[0].matches("--all"));
+        assert!(tool.options[2].matches("--block-size=K"));
+    }
+
+    #[test]
+    fn parses_cobra_help() {
+        let tool = parse_help_output("kubectl", COBRA_HELP);
+        assert_eq!(
+            tool.summary,
+            "kubectl controls the Kubernetes cluster manager."
+        );
+        assert_eq!(tool.synopsis, "kubectl [flags] [options]");
+        assert_eq!(
+            entries(&tool.options),
+            [
+                (
+                    "-n, --namespace string",
+                    "If present, the namespace scope for this CLI request"
+                ),
+                (
+                    "--kubeconfig string",
+                    "Path to the kubeconfig file to use for CLI requests."
+                ),
+            ]
+        );
+        assert_eq!(
+            entries(&tool.subcommands),
+            [
+                (
+                    "apply",
+                    "Apply a configuration to a resource by file name or stdin"
+                ),
+                ("get", "Display one or many resources"),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_the_version_line() {
+        let tool = parse_help_output("tool", "tool v2.1.0\nDoes things.\n\n  -q  quiet\n");
+        assert_eq!(tool.summary, "Does things.");
+        assert_eq!(entries(&tool.options), [("-q", "quiet")]);
+    }
+
+    #[test]
+    fn does_not_wait_for_background_processes() {
+        let script = env::temp_dir().join(format!("cmd-cat-help-{}", process::id()));
+        fs::write(&script, "/bin/sleep 30 &\necho '  -q  quiet'\n").unwrap();
+
+        let start = Instant::now();
+        let output = run_with_timeout(Path::new("/bin/sh"), script.to_str().unwrap()).unwrap();
+        fs::remove_file(&script).unwrap();
+        assert_eq!(output, None);
+        assert!(start.elapsed() < HELP_TIMEOUT + Duration::from_secs(2));
+    }
+
+    #[test]
+    fn reads_stdout_then_stderr() {
+        let script = env::temp_dir().join(format!("cmd-cat-help-order-{}", process::id()));
+        fs::write(&script, "echo err >&2\necho out\n").unwrap();
+
+        let output = run_with_timeout(Path::new("/bin/sh"), script.to_str().unwrap()).unwrap();
+        fs::remove_file(&script).unwrap();
+        assert_eq!(output.as_deref(), Some("out\nerr\n"));
+    }
+}
@@ -0,0 +1,326 @@
+use crate::database::history::HistoryEntry;
+
+/// The formats of the shell history files.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HistoryFormat {
+    /// `~/.bash_history`, with `#<timestamp>` lines when `HISTTIMEFORMAT` is set
+    Bash,
+    /// `~/.zsh_history`, with `: <timestamp>:<duration>;<command>` lines when `EXTENDED_HISTORY` is set
+    Zsh,
+    /// `~/.local/share/fish/fish_history`
+    Fish,
+}
+
+impl HistoryFormat {
+    /// Gets the format of a history file from its name.
+    ///
+    /// # Arguments
+    ///
+    /// * `filepath` - The path of the file.
+    ///
+    /// # Returns
+    ///
+    /// `Fish` or `Zsh` if the file name says so, `Bash` otherwise.
+    pub fn from_path(filepath: &str) -> Self {
+        let file_name = filepath.rsplit('/').next().unwrap_or(filepath);
+        if file_name.contains("fish") {
+            HistoryFormat::Fish
+        } else if file_name.contains("zsh") || file_name.contains("zhistory") {
+            HistoryFormat::Zsh
+        } else {
+            HistoryFormat::Bash
+        }
+    }
+}
+
+/// Parses a shell history file. Multi-line commands are skipped, they would not fit in the menu.
+///
+/// # Arguments
+///
+/// * `content` - The content of the file, zsh histories are unmetafied first (see `unmetafy`).
+/// * `format` - The format of the file.
+///
+/// # Returns
+///
+/// The entries of the history, oldest first.
+pub fn parse_history(content: &[u8], format: HistoryFormat) -> Vec<HistoryEntry> {
+    match format {
+        HistoryFormat::Bash => parse_bash_history(&String::from_utf8_lossy(content)),
+        HistoryFormat::Zsh => parse_zsh_history(&String::from_utf8_lossy(&unmetafy(content))),
+        HistoryFormat::Fish => parse_fish_history(&String::from_utf8_lossy(content)),
+    }
+}
+
+/// Parses a bash history, a `#<timestamp>` line gives the time of the command below it.
+///
+/// # Arguments
+///
+/// * `input` - The content of the history.
+///
+/// # Returns
+///
+/// The entries of the history, oldest first.
+fn parse_bash_history(input: &str) -> Vec<HistoryEntry> {
+    let mut entries = Vec::new();
+    let mut timestamp = 0;
+    for line in input.lines() {
+        if let Some(time) = line.strip_prefix('#').and_then(|time| time.parse().ok()) {
+            timestamp = time;
+            continue;
+        }
+        push_entry(&mut entries, line, timestamp);
+        timestamp = 0;
+    }
+    entries
+}
+
+/// Parses a zsh history, in the extended format or not. Lines ending with `\` continue
+/// the command on the next line.
+///
+/// # Arguments
+///
+/// * `input` - The unmetafied content of the history.
+///
+/// # Returns
+///
+/// The entries of the history, oldest first.
+fn parse_zsh_history(input: &str) -> Vec<HistoryEntry> {
+    let mut entries = Vec::new();
+    let mut lines = input.lines();
+    while let Some(line) = lines.next() {
+        let (timestamp, mut command) = line
+            .strip_prefix(": ")
+            .and_then(|rest| rest.split_once(';'))
+            .and_then(|(times, command)| {
+                let timestamp = times.split(':').next()?.trim().parse().ok()?;
+                Some((timestamp, command.to_owned()))
+            })
+            .unwrap_or((0, line.to_owned()));
+        let mut is_multiline = false;
+        while command.ends_with('\\') {
+            is_multiline = true;
+            match lines.next() {
+                Some(next_line) => command = next_line.to_owned(),
+                None => break,
+            }
+        }
+        if !is_multiline {
+            push_entry(&mut entries, &command, timestamp);
+        }
+    }
+    entries
+}
+
+/// Parses a fish history:
+///
+/// ```text
+/// - cmd: git status
+///   when: 1700000000
+/// ```
+///
+/// # Arguments
+///
+/// * `input` - The content of the history.
+///
+/// # Returns
+///
+/// The entries of the history, oldest first.
+fn parse_fish_history(input: &str) -> Vec<HistoryEntry> {
+    let mut entries = Vec::new();
+    let mut command: Option<String> = None;
+    let mut timestamp = 0;
+    for line in input.lines() {
+        if let Some(text) = line.strip_prefix("- cmd: ") {
+            if let Some(command) = command.take() {
+                push_entry(&mut entries, &command, timestamp);
+            }
+            timestamp = 0;
+            command = Some(unescape_fish_command(text).unwrap_or_default());
+        } else if let Some(time) = line.trim_start().strip_prefix("when: ") {
+            timestamp = time.trim().parse().unwrap_or(0);
+        }
+    }
+    if let Some(command) = command {
+        push_entry(&mut entries, &command, timestamp);
+    }
+    entries
+}
+
+/// Undoes the escaping of the commands of fish histories, where newlines are written as `\n`
+/// and backslashes as `\\`.
+///
+/// # Arguments
+///
+/// * `text` - The command as written in the history.
+///
+/// # Returns
+///
+/// The command, `None` for multi-line commands.
+fn unescape_fish_command(text: &str) -> Option<String> {
+    let mut command = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => return None,
+            ('\\', Some('\\')) => {
+                chars.next();
+                command.push('\\');
+            }
+            (c, _) => command.push(c),
+        }
+    }
+    Some(command)
+}
+
+/// Adds a command to the entries, unless it is empty.
+///
+/// # Arguments
+///
+/// * `entries` - The entries read so far.
+/// * `command` - The command.
+/// * `timestamp` - When the command was run, 0 if unknown.
+fn push_entry(entries: &mut Vec<HistoryEntry>, command: &str, timestamp: i64) {
+    let command = command.trim();
+    if !command.is_empty() {
+        entries.push(HistoryEntry {
+            command: command.to_owned(),
+            timestamp,
+        });
+    }
+}
+
+/// Undoes the metafication of zsh history files, where some bytes are written
+/// as `0x83` followed by the byte xor `0x20`.
+///
+/// # Arguments
+///
+/// * `content` - The content of the file.
+///
+/// # Returns
+///
+/// The content with the original bytes.
+fn unmetafy(content: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(content.len());
+    let mut iter = content.iter();
+    while let Some(&byte) = iter.next() {
+        match byte {
+            0x83 => bytes.extend(iter.next().map(|next| next ^ 0x20)),
+            byte => bytes.push(byte),
+        }
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(command: &str, timestamp: i64) -> HistoryEntry {
+        HistoryEntry {
+            command: command.to_owned(),
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn parses_bash_history() {
+        let input = "ls -l\n\
+            #1700000000\n\
+            git status\n\
+            \n\
+            #1700000100\n\
+            #1700000200\n\
+            make\n\
+            echo after\n\
+            # a comment\n";
+
+        assert_eq!(
+            parse_history(input.as_bytes(), HistoryFormat::Bash),
+            [
+                entry("ls -l", 0),
+                entry("git status", 1700000000),
+                entry("make", 1700000200),
+                entry("echo after", 0),
+                entry("# a comment", 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_zsh_history() {
+        let input = ": 1700000000:0;git status\n\
+            : 1700000100:12;for f in *; do\\\n\
+            echo $f\\\n\
+            done\n\
+            ls -la\n\
+            : 1700000200:0;make\n";
+
+        assert_eq!(
+            parse_history(input.as_bytes(), HistoryFormat::Zsh),
+            [
+                entry("git status", 1700000000),
+                entry("ls -la", 0),
+                entry("make", 1700000200),
+            ]
+        );
+    }
+
+    #[test]
+    fn unmetafies_zsh_history() {
+        // `é` is 0xc3 0xa9, zsh writes 0xa9 as 0x83 0x89
+        let mut input = b": 1700000000:0;echo caf\xc3".to_vec();
+        input.extend([0x83, 0xa9 ^ 0x20]);
+        input.extend(b"\n");
+
+        assert_eq!(
+            parse_history(&input, HistoryFormat::Zsh),
+            [entry("echo café", 1700000000)]
+        );
+        assert_eq!(
+            unmetafy(&[b'a', 0x83, 0xa3, b'b', 0x83]),
+            [b'a', 0x83, b'b']
+        );
+    }
+
+    #[test]
+    fn parses_fish_history() {
+        let input = "- cmd: git status\n\
+            \x20 when: 1700000000\n\
+            - cmd: echo a\\\\b\n\
+            \x20 when: 1700000100\n\
+            \x20 paths:\n\
+            \x20   - a\\\\b\n\
+            - cmd: for f in *\\n  echo $f\\nend\n\
+            \x20 when: 1700000200\n\
+            - cmd: make\n";
+
+        assert_eq!(
+            parse_history(input.as_bytes(), HistoryFormat::Fish),
+            [
+                entry("git status", 1700000000),
+                entry("echo a\\b", 1700000100),
+                entry("make", 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_format_from_path() {
+        assert_eq!(
+            HistoryFormat::from_path("/home/me/.local/share/fish/fish_history"),
+            HistoryFormat::Fish
+        );
+        assert_eq!(
+            HistoryFormat::from_path("/home/me/.zsh_history"),
+            HistoryFormat::Zsh
+        );
+        assert_eq!(
+            HistoryFormat::from_path("/home/me/.zhistory"),
+            HistoryFormat::Zsh
+        );
+        assert_eq!(
+            HistoryFormat::from_path("/home/me/.bash_history"),
+            HistoryFormat::Bash
+        );
+    }
+}
@@ -0,0 +1,617 @@
+use crate::commands::{Tool, ToolOption, MAN_SOURCE};
+use std::mem;
+
+/// Macros of mdoc (BSD style) pages that only style the words following them.
+const MDOC_STYLE_MACROS: &[&str] = &[
+    "Ar", "Cm", "Ic", "Li", "Pa", "Op", "Oo", "Oc", "Xr", "Nm", "Em", "Sy", "Dq", "Qq", "Sq", "Ql",
+    "Va", "Ev", "Pq", "Ek", "Bk", "No", "Ns", "Xo",
+];
+
+/// What the next text line of a page is.
+#[derive(PartialEq)]
+enum TextState {
+    /// Text of the current section or option
+    Body,
+    /// The tag (e.g. the flags of an option) of a `.TP` paragraph
+    Tag,
+    /// Another tag of the same paragraph, following `.TQ`
+    ExtraTag,
+    /// The rest of a tag spanning several lines, from `.It ... Xo` to `.Xc`
+    ExtendedTag,
+}
+
+/// Parses a man page written in roff, with the `man` or the `mdoc` macros.
+/// The NAME and SYNOPSIS sections describe the tool, and every tagged paragraph whose tag
+/// is a flag (`.TP`, `.IP`, `.It Fl`) in the other sections becomes an option.
+///
+/// # Arguments
+///
+/// * `name` - The name of the tool, from the file name of the page.
+/// * `section` - The manual section of the page.
+/// * `input` - The content of the page.
+///
+/// # Returns
+///
+/// The tool, `None` if the page only links to another page (`.so`) or has no NAME section.
+pub fn parse_man_page(name: &str, section: &str, input: &str) -> Option<Tool> {
+    let mut tool = Tool {
+        name: name.to_owned(),
+        section: section.to_owned(),
+        source: MAN_SOURCE.to_owned(),
+        ..Default::default()
+    };
+    let mut current_section = String::new();
+    let mut name_text = String::new();
+    let mut synopsis = String::new();
+    let mut state = TextState::Body;
+    // words are not separated by spaces after `.Sm off`
+    let mut spacing = true;
+    // flags and description of the option being read
+    let mut option: Option<(String, String)> = None;
+    // flags of the options without description, documented together with the next one
+    let mut shared_flags = String::new();
+
+    for line in input.lines() {
+        if line.starts_with(".\\\"") || line.starts_with("'\\\"") || line.starts_with("\\\"") {
+            continue;
+        }
+
+        let text = match line.strip_prefix('.').or_else(|| line.strip_prefix('\'')) {
+            Some(request) => {
+                let (macro_name, args) = split_request(request);
+                match macro_name.as_str() {
+                    "so" => return None,
+                    "SH" | "Sh" => {
+                        push_option(&mut tool, option.take(), &mut shared_flags);
+                        current_section = args.join(" ").to_uppercase();
+                        state = TextState::Body;
+                        continue;
+                    }
+                    "TP" => {
+                        push_option(&mut tool, option.take(), &mut shared_flags);
+                        state = TextState::Tag;
+                        continue;
+                    }
+                    "TQ" => {
+                        state = TextState::ExtraTag;
+                        continue;
+                    }
+                    "IP" => {
+                        // an untagged `.IP` continues the description
+                        if let Some(tag) = args.first().filter(|tag| !tag.is_empty()) {
+                            push_option(&mut tool, option.take(), &mut shared_flags);
+                            option = Some((clean_text(tag), String::new()));
+                        }
+                        state = TextState::Body;
+                        continue;
+                    }
+                    "PP" | "P" | "LP" | "HP" | "Pp" | "El" => {
+                        push_option(&mut tool, option.take(), &mut shared_flags);
+                        state = TextState::Body;
+                        continue;
+                    }
+                    "It" => {
+                        push_option(&mut tool, option.take(), &mut shared_flags);
+                        let mut flags = mdoc_text(&args);
+                        state = match args.last().map(String::as_str) {
+                            Some("Xo") => {
+                                flags.push(' ');
+                                TextState::ExtendedTag
+                            }
+                            _ => TextState::Body,
+                        };
+                        option = Some((flags, String::new()));
+                        continue;
+                    }
+                    "Xc" => {
+                        state = TextState::Body;
+                        continue;
+                    }
+                    "Sm" => {
+                        spacing = args.first().map(String::as_str) != Some("off");
+                        continue;
+                    }
+                    "Nm" if current_section == "NAME" => mdoc_text(&args),
+                    "Nd" => format!("- {}", mdoc_text(&args)),
+                    "B" | "I" | "SM" | "SB" => clean_text(&args.join(" ")),
+                    "BR" | "RB" | "BI" | "IB" | "IR" | "RI" => clean_text(&args.concat()),
+                    name if name == "Fl" || MDOC_STYLE_MACROS.contains(&name) => {
+                        // inline mdoc macros, e.g. `.Fl v` or `.Ar file`
+                        let mut words = vec![macro_name];
+                        words.extend(args);
+                        mdoc_text(&words)
+                    }
+                    _ => continue,
+                }
+            }
+            None => clean_text(line),
+        };
+
+        match state {
+            TextState::Tag => {
+                option.replace((text, String::new()));
+                state = TextState::Body;
+                continue;
+            }
+            TextState::ExtraTag => {
+                if let Some((flags, _)) = option.as_mut() {
+                    flags.push_str(", ");
+                    flags.push_str(&text);
+                }
+                state = TextState::Body;
+                continue;
+            }
+            TextState::ExtendedTag => {
+                if let Some((flags, _)) = option.as_mut() {
+                    if spacing {
+                        push_words(flags, &text);
+                    } else {
+                        flags.push_str(&text.replace(' ', ""));
+                    }
+                }
+                continue;
+            }
+            TextState::Body => {}
+        }
+
+        let buffer = match current_section.as_str() {
+            "NAME" => &mut name_text,
+            "SYNOPSIS" => &mut synopsis,
+            _ => match option.as_mut() {
+                Some((_, description)) => description,
+                None => continue,
+            },
+        };
+        push_words(buffer, &text);
+    }
+    push_option(&mut tool, option.take(), &mut shared_flags);
+
+    let (_, summary) = name_text.split_once(" - ")?;
+    tool.summary = summary.trim().to_owned();
+    tool.synopsis = synopsis;
+    Some(tool)
+}
+
+/// Gets the tool name and manual section of a man page from its file name,
+/// e.g. `tar.1.gz` or `ip-address.8`.
+///
+/// # Arguments
+///
+/// * `file_name` - The file name of the page.
+///
+/// # Returns
+///
+/// The name and section, `None` if the file name has no section.
+pub fn man_page_name(file_name: &str) -> Option<(&str, &str)> {
+    let file_name = file_name.strip_suffix(".gz").unwrap_or(file_name);
+    let (name, section) = file_name.rsplit_once('.')?;
+    if name.is_empty() || !section.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    Some((name, section))
+}
+
+/// Adds the option being read to the tool if its tag is a flag.
+/// An option without description is documented together with the next option,
+/// its flags are kept in `shared_flags` until then.
+///
+/// # Arguments
+///
+/// * `tool` - The tool being read.
+/// * `option` - The flags and description of the option.
+/// * `shared_flags` - The flags of the previous options without description.
+fn push_option(tool: &mut Tool, option: Option<(String, String)>, shared_flags: &mut String) {
+    let Some((flag, description)) = option else {
+        return;
+    };
+    let flag = flag.trim();
+    if !(flag.starts_with('-') || flag.starts_with('+')) || flag.len() < 2 {
+        shared_flags.clear();
+        return;
+    }
+
+    if !shared_flags.is_empty() {
+        shared_flags.push_str(", ");
+    }
+    shared_flags.push_str(flag);
+    if !description.trim().is_empty() {
+        tool.options.push(ToolOption {
+            flag: mem::take(shared_flags),
+            description: description.trim().to_owned(),
+        });
+    }
+}
+
+/// Appends the text of a line to a paragraph, separated by a space.
+///
+/// # Arguments
+///
+/// * `buffer` - The paragraph.
+/// * `text` - The text of the line.
+fn push_words(buffer: &mut String, text: &str) {
+    let text = text.trim();
+    if text.is_empty() {
+        return;
+    }
+    if !buffer.is_empty() && !buffer.ends_with(' ') {
+        buffer.push(' ');
+    }
+    buffer.push_str(text);
+}
+
+/// Splits a request line (without the leading dot) into the macro name and its arguments,
+/// arguments with spaces are wrapped in double quotes.
+///
+/// # Arguments
+///
+/// * `request` - The request, e.g. `IP "-f, --file" 4`.
+///
+/// # Returns
+///
+/// The macro name and arguments.
+fn split_request(request: &str) -> (String, Vec<String>) {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+    let mut chars = request.trim_start().chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                word.push('"');
+            }
+            '"' => quoted = !quoted,
+            ' ' | '\t' if !quoted => {
+                if !word.is_empty() {
+                    words.push(mem::take(&mut word));
+                }
+            }
+            '\\' if chars.peek() == Some(&'"') => break,
+            _ => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    let macro_name = if words.is_empty() {
+        String::new()
+    } else {
+        words.remove(0)
+    };
+    (macro_name, words)
+}
+
+/// Renders the arguments of mdoc macros as text: `Fl v Ar file` becomes `-v file`,
+/// and `Ns` joins its neighbours without space.
+///
+/// # Arguments
+///
+/// * `words` - The words of the line.
+///
+/// # Returns
+///
+/// The text of the line.
+fn mdoc_text(words: &[String]) -> String {
+    let mut text: Vec<String> = Vec::new();
+    let mut flag = false;
+    let mut no_space = false;
+    let mut optional = 0;
+    for word in words {
+        let word = match word.as_str() {
+            "Fl" => {
+                flag = true;
+                continue;
+            }
+            "Ns" => {
+                no_space = true;
+                continue;
+            }
+            "Op" => {
+                optional += 1;
+                "[".to_owned()
+            }
+            "Oo" => "[".to_owned(),
+            "Oc" => "]".to_owned(),
+            word if MDOC_STYLE_MACROS.contains(&word) => continue,
+            word if flag => {
+                flag = false;
+                format!("-{}", clean_text(word))
+            }
+            word => clean_text(word),
+        };
+        let attached = mem::take(&mut no_space);
+        match text.last_mut() {
+            Some(last) if attached => last.push_str(&word),
+            _ => text.push(word),
+        }
+    }
+    if flag {
+        text.push("-".to_owned());
+    }
+    text.extend((0..optional).map(|_| "]".to_owned()));
+    text.join(" ").replace("[ ", "[").replace(" ]", "]")
+}
+
+/// Removes the roff escapes of a line of text: font changes are dropped and
+/// special characters (`\-`, `\(aq`, `\[em]`, ...) are replaced by the character they stand for.
+///
+/// # Arguments
+///
+/// * `line` - The line of text.
+///
+/// # Returns
+///
+/// The plain text of the line.
+fn clean_text(line: &str) -> String {
+    let mut text = String::new();
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        let Some(escape) = chars.next() else {
+            break;
+        };
+        match escape {
+            '"' => break,
+            '-' => text.push('-'),
+            'e' | '\\' => text.push('\\'),
+            ' ' | '~' | '0' => text.push(' '),
+            '&' | '|' | '^' | ')' | ',' | '/' | ':' | 'c' | '%' => {}
+            '(' => {
+                let name: String = chars.by_ref().take(2).collect();
+                text.push_str(special_character(&name));
+            }
+            '[' => {
+                let name: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                text.push_str(special_character(&name));
+            }
+            '*' => {
+                let name = escape_name(&mut chars);
+                text.push_str(special_character(&name));
+            }
+            'f' | 'F' | 'n' | 'k' | 'm' | 'M' | 'g' | 'V' | 'Y' => {
+                escape_name(&mut chars);
+            }
+            's' => {
+                let mut rest = chars.clone();
+                if matches!(rest.next(), Some('+' | '-')) {
+                    chars.next();
+                }
+                let mut rest = chars.clone();
+                while rest.next().is_some_and(|c| c.is_ascii_digit()) {
+                    chars.next();
+                }
+            }
+            'h' | 'v' | 'w' | 'l' | 'L' | 'o' | 'D' | 'b' | 'x' | 'X' | 'Z' | 'N' | 'S' | 'H' => {
+                // escapes with a quoted argument, e.g. \h'2n'
+                if let Some(delimiter) = chars.next() {
+                    for c in chars.by_ref() {
+                        if c == delimiter {
+                            break;
+                        }
+                    }
+                }
+            }
+            _ => text.push(escape),
+        }
+    }
+    text
+}
+
+/// Reads the name of an escape, `x`, `(xx` or `[name]`.
+///
+/// # Arguments
+///
+/// * `chars` - The characters following the escape.
+///
+/// # Returns
+///
+/// The name.
+fn escape_name(chars: &mut std::str::Chars) -> String {
+    match chars.next() {
+        Some('(') => chars.by_ref().take(2).collect(),
+        Some('[') => chars.by_ref().take_while(|&c| c != ']').collect(),
+        Some(c) => c.to_string(),
+        None => String::new(),
+    }
+}
+
+/// Gets the text of a named roff character or string, e.g. `em` or `aq`.
+///
+/// # Arguments
+///
+/// * `name` - The name of the character.
+///
+/// # Returns
+///
+/// The text of the character, empty if it is unknown.
+fn special_character(name: &str) -> &'static str {
+    match name {
+        "em" | "en" | "mi" | "hy" | "-" => "-",
+        "aq" | "cq" | "oq" => "'",
+        "dq" | "lq" | "rq" | "Lq" | "Rq" => "\"",
+        "bu" => "•",
+        "co" => "©",
+        "rg" | "R" => "®",
+        "ti" | "ap" => "~",
+        "ha" | "a^" => "^",
+        "rs" => "\\",
+        "lB" => "[",
+        "rB" => "]",
+        "lC" => "{",
+        "rC" => "}",
+        "ga" => "`",
+        "<=" => "≤",
+        ">=" => "≥",
+        "->" => "→",
+        "mu" => "×",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TAR_PAGE: &str = r#".\" Manual page of tar
+.TH TAR 1 "2024" "GNU"
+.SH NAME
+tar \- an archiving utility
+.SH SYNOPSIS
+.B tar
+[\fIOPTION\fR...] [\fIFILE\fR]...
+.SH DESCRIPTION
+GNU \fBtar\fR saves many files together.
+.SH OPTIONS
+.TP
+\fB\-c\fR, \fB\-\-create\fR
+Create a new archive.
+.TP
+\fB\-f\fR, \fB\-\-file\fR=\fIARCHIVE\fR
+Use archive file
+or device ARCHIVE.
+.TP
+\fB\-v\fR
+.TQ
+\fB\-\-verbose\fR
+Verbosely list files processed.
+.IP "\-z, \-\-gzip" 4
+Filter the archive through gzip \(em the default compressor.
+.IP
+Also for \(aqtar \-x\(aq.
+.TP
+.B \-\-xattrs
+.TP
+.B \-\-acls
+Store extended attributes and ACLs.
+.SH "SEE ALSO"
+.TP
+\fBgzip\fR(1)
+The compressor.
+"#;
+
+    const LS_PAGE: &str = r#".Dd $Mdocdate$
+.Dt LS 1
+.Os
+.Sh NAME
+.Nm ls
+.Nd list directory contents
+.Sh SYNOPSIS
+.Nm ls
+.Op Fl al
+.Op Ar
+.Sh DESCRIPTION
+.Bl -tag -width indent
+.It Fl a
+Include directory entries whose names begin with a dot.
+.It Fl D Ar format
+Use
+.Ar format
+to show the dates.
+.It Xo
+.Fl -color Ns = Ns Ar when
+.Xc
+Colorize the output.
+.El
+"#;
+
+    fn options(tool: &Tool) -> Vec<(&str, &str)> {
+        tool.options
+            .iter()
+            .map(|option| (option.flag.as_str(), option.description.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_name_and_synopsis() {
+        let tool = parse_man_page("tar", "1", TAR_PAGE).unwrap();
+        assert_eq!(tool.name, "tar");
+        assert_eq!(tool.section, "1");
+        assert_eq!(tool.source, MAN_SOURCE);
+        assert_eq!(tool.summary, "an archiving utility");
+        assert_eq!(tool.synopsis, "tar [OPTION...] [FILE]...");
+    }
+
+    #[test]
+    fn parses_tagged_paragraphs_as_options() {
+        let tool = parse_man_page("tar", "1", TAR_PAGE).unwrap();
+        assert_eq!(
+            options(&tool),
+            [
+                ("-c, --create", "Create a new archive."),
+                ("-f, --file=ARCHIVE", "Use archive file or device ARCHIVE."),
+                ("-v, --verbose", "Verbosely list files processed."),
+                (
+                    "-z, --gzip",
+                    "Filter the archive through gzip - the default compressor. Also for 'tar -x'."
+                ),
+                ("--xattrs, --acls", "Store extended attributes and ACLs."),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_mdoc_lists() {
+        let tool = parse_man_page("ls", "1", LS_PAGE).unwrap();
+        assert_eq!(tool.summary, "list directory contents");
+        assert_eq!(tool.synopsis, "ls [-al] []");
+        assert_eq!(
+            options(&tool),
+            [
+                (
+                    "-a",
+                    "Include directory entries whose names begin with a dot."
+                ),
+                ("-D format", "Use format to show the dates."),
+                ("--color=when", "Colorize the output."),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_redirects_and_pages_without_name() {
+        assert_eq!(parse_man_page("gunzip", "1", ".so man1/gzip.1\n"), None);
+        assert_eq!(
+            parse_man_page("broken", "1", ".SH OPTIONS\n.TP\n\\-a\nAll.\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn removes_escapes() {
+        assert_eq!(
+            clean_text(r"\fB\-\-file\fR=\fIARCHIVE\fP"),
+            "--file=ARCHIVE"
+        );
+        assert_eq!(clean_text(r"a \(em b \[en] c"), "a - b - c");
+        assert_eq!(
+            clean_text(r"\(aqquoted\(aq \*(lqtext\*(rq"),
+            "'quoted' \"text\""
+        );
+        assert_eq!(clean_text(r"\s-1SMALL\s0 \h'2n'gap\&."), "SMALL gap.");
+        assert_eq!(clean_text(r#"text \" comment"#), "text ");
+    }
+
+    #[test]
+    fn renders_mdoc_macros() {
+        let words = |line: &str| line.split(' ').map(String::from).collect::<Vec<_>>();
+        assert_eq!(mdoc_text(&words("Fl v Ar file")), "-v file");
+        assert_eq!(mdoc_text(&words("Op Fl f Ar file")), "[-f file]");
+        assert_eq!(mdoc_text(&words("Fl")), "-");
+    }
+
+    #[test]
+    fn reads_names_of_page_files() {
+        assert_eq!(man_page_name("tar.1.gz"), Some(("tar", "1")));
+        assert_eq!(man_page_name("ip-address.8"), Some(("ip-address", "8")));
+        assert_eq!(man_page_name("perl.1perl"), Some(("perl", "1perl")));
+        assert_eq!(man_page_name("README"), None);
+        assert_eq!(man_page_name("notes.txt"), None);
+        assert_eq!(man_page_name(".1"), None);
+    }
+}
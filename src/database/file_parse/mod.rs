@@ -0,0 +1,1010 @@
+use crate::commands::{
+    Command, Page, Tool, ALIAS_SOURCE, CHEAT_SOURCE, MAN_SOURCE, NAVI_SOURCE, SHEET_SOURCE,
+};
+use crate::custom_styling::color_style;
+use crate::database::page_files::{PageFile, PageImport};
+use crate::database::SqliteDatabase;
+use archive::ArchiveFormat;
+use flate2::read::GzDecoder;
+use history::HistoryFormat;
+use indicatif::{ProgressBar, ProgressStyle};
+use rayon::prelude::*;
+use sheets::SheetFormat;
+use shell::ShellSyntax;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{fmt, fs, io};
+use tldr::{ParseError, TldrPage};
+
+pub mod archive;
+pub mod cheat;
+pub mod help;
+pub mod history;
+pub mod man;
+pub mod navi;
+pub mod sheets;
+pub mod shell;
+pub mod tldr;
+
+/// Error of a database update, the database is left unchanged when it happens.
+#[derive(Debug)]
+pub enum UpdateError {
+    /// A page could not be read
+    Read(String, io::Error),
+    /// A file is not valid, with a message describing the problem
+    Parse(String, String),
+    /// The commands could not be written to the database
+    Database(rusqlite::Error),
+    /// The folder holds no tldr pages, e.g. a wrong path or an empty archive
+    NoPages(String),
+}
+
+/// Number of pages updated (added or changed) and removed by a database update,
+/// and of examples and errors found in the updated pages.
+#[derive(Debug, Default)]
+pub struct UpdateSummary {
+    pub updated: usize,
+    pub removed: usize,
+    pub examples: usize,
+    pub errors: usize,
+}
+
+impl fmt::Display for UpdateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpdateError::Read(filepath, err) => write!(f, "failed to read {filepath}: {err}"),
+            UpdateError::Parse(filepath, message) => write!(f, "invalid {filepath}: {message}"),
+            UpdateError::Database(err) => write!(f, "failed to write the database: {err}"),
+            UpdateError::NoPages(folder_path) => write!(f, "no tldr pages found in {folder_path}"),
+        }
+    }
+}
+
+impl From<rusqlite::Error> for UpdateError {
+    fn from(err: rusqlite::Error) -> Self {
+        UpdateError::Database(err)
+    }
+}
+
+/// Reads the examples of a tldr page along with the page metadata.
+///
+/// # Arguments
+///
+/// * `filepath` - The path to the page.
+///
+/// # Returns
+///
+/// The commands of the page, with `TLDR_SOURCE` as source, and the errors found in the page.
+pub fn read_page_file(filepath: &str) -> Result<(Vec<Command>, Vec<ParseError>), UpdateError> {
+    let (_, commands, errors) = parse_page_content(filepath, &read_file(filepath)?);
+    Ok((commands, errors))
+}
+
+/// Parses the examples of a tldr page along with the page metadata.
+///
+/// # Arguments
+///
+/// * `filepath` - The path to the page, giving its platform and language.
+/// * `file_content` - The content of the page.
+///
+/// # Returns
+///
+/// The page metadata, the commands of the page and the errors found in the page.
+fn parse_page_content(filepath: &str, file_content: &str) -> (Page, Vec<Command>, Vec<ParseError>) {
+    let (tldr_page, errors) = tldr::parse_page(file_content);
+    let page = page_metadata(filepath, &tldr_page);
+
+    let commands = tldr_page
+        .examples
+        .iter()
+        .map(|example| Command::new(&example.command, &example.description).with_page(&page))
+        .collect();
+    (page, commands, errors)
+}
+
+/// Checks a tldr page against the format and the placeholder rules, see `tldr::lint_page`.
+///
+/// # Arguments
+///
+/// * `filepath` - The path to the page.
+///
+/// # Returns
+///
+/// The problems found in the page, sorted by line.
+pub fn lint_page_file(filepath: &str) -> Result<Vec<ParseError>, UpdateError> {
+    let file_stem = Path::new(filepath)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    Ok(tldr::lint_page(&read_file(filepath)?, &file_stem))
+}
+
+/// Prints the errors found in a page, one `file:line: message` per line.
+///
+/// # Arguments
+///
+/// * `filepath` - The path to the page.
+/// * `errors` - The errors found in the page.
+pub fn print_parse_errors(filepath: &str, errors: &[ParseError]) {
+    for error in errors {
+        println!(
+            "{}",
+            color_style::color_light_red(&format!("{filepath}:{}: {}", error.line, error.message))
+        );
+    }
+}
+
+/// Updates the tldr commands of the database from the pages in the specified folder.
+/// Commands added by the user are kept.
+/// Only the pages added or changed since the last update (compared by content hash) are parsed,
+/// and the commands of removed pages are deleted. The first update parses every page.
+/// All of the pages are parsed before the database is touched, and the changes are written
+/// in a single transaction, so a failed update leaves the old data intact.
+/// A folder without pages is refused rather than treated as every page being removed.
+/// This is an internal function and should not be used directly.
+/// Should be used for updating the databse only
+///
+/// # Arguments
+///
+/// * `db` - The database to update.
+/// * `folder_path` - The path to the folder containing the files to process.
+/// * `revision` - The revision of the pages (e.g. the git commit), recorded with the updated pages.
+///
+/// # Returns
+///
+/// The number of pages updated and removed.
+pub fn _clean_update_database(
+    db: &SqliteDatabase,
+    folder_path: &str,
+    revision: &str,
+) -> Result<UpdateSummary, UpdateError> {
+    let stored_files: HashMap<String, PageFile> = db
+        .get_page_files()?
+        .into_iter()
+        .map(|file| (file.path.clone(), file))
+        .collect();
+    let filepaths = page_filepaths(folder_path);
+    if filepaths.is_empty() {
+        return Err(UpdateError::NoPages(folder_path.to_owned()));
+    }
+
+    let progress = ProgressBar::new(filepaths.len() as u64).with_style(
+        ProgressStyle::with_template("{bar:30} {pos}/{len} pages, {msg}")
+            .expect("Invalid progress bar template"),
+    );
+    let example_count = AtomicUsize::new(0);
+    let error_count = AtomicUsize::new(0);
+    let results: Vec<_> = filepaths
+        .par_iter()
+        .map(|filepath| {
+            let result = read_changed_page(filepath, folder_path, &stored_files, revision);
+            if let Ok(ReadPage {
+                changed: Some((import, errors)),
+                ..
+            }) = &result
+            {
+                example_count.fetch_add(import.commands.len(), Ordering::Relaxed);
+                error_count.fetch_add(errors.len(), Ordering::Relaxed);
+            }
+            progress.set_message(format!(
+                "{} examples, {} errors",
+                example_count.load(Ordering::Relaxed),
+                error_count.load(Ordering::Relaxed)
+            ));
+            progress.inc(1);
+            result
+        })
+        .collect();
+    progress.finish_and_clear();
+
+    let mut present_paths = HashSet::new();
+    let mut imports = Vec::new();
+    for (filepath, result) in filepaths.iter().zip(results) {
+        let read_page = result?;
+        present_paths.insert(read_page.relative_path);
+        if let Some((import, errors)) = read_page.changed {
+            print_parse_errors(filepath, &errors);
+            imports.push(import);
+        }
+    }
+
+    let removed: Vec<PageFile> = stored_files
+        .into_values()
+        .filter(|file| !present_paths.contains(&file.path))
+        .collect();
+    if !imports.is_empty() && present_paths.len() == imports.len() {
+        // every page is new, e.g. the first update, the old tldr commands are all replaced
+        db.replace_page_files(&imports)?;
+    } else {
+        db.update_page_files(&imports, &removed)?;
+    }
+    Ok(UpdateSummary {
+        updated: imports.len(),
+        removed: removed.len(),
+        examples: example_count.into_inner(),
+        errors: error_count.into_inner(),
+    })
+}
+
+/// A page read by an update.
+struct ReadPage {
+    /// The path of the page within the folder of the pages
+    relative_path: String,
+    /// The page to import along with the errors found in it, if it changed since the last update
+    changed: Option<(PageImport, Vec<ParseError>)>,
+}
+
+/// Reads a page and parses it if it was added or changed since the last update.
+///
+/// # Arguments
+///
+/// * `filepath` - The path to the page.
+/// * `folder_path` - The folder of the pages, the page is recorded by its path within it.
+/// * `stored_files` - The page files recorded by the last update, by path.
+/// * `revision` - The revision of the pages.
+///
+/// # Returns
+///
+/// The page, parsed if it changed.
+fn read_changed_page(
+    filepath: &str,
+    folder_path: &str,
+    stored_files: &HashMap<String, PageFile>,
+    revision: &str,
+) -> Result<ReadPage, UpdateError> {
+    let file_content = read_file(filepath)?;
+    let relative_path = Path::new(filepath)
+        .strip_prefix(folder_path)
+        .map_or(filepath.to_owned(), |path| {
+            path.to_string_lossy().into_owned()
+        });
+    let hash = content_hash(&file_content);
+    if stored_files
+        .get(&relative_path)
+        .is_some_and(|file| file.hash == hash)
+    {
+        return Ok(ReadPage {
+            relative_path,
+            changed: None,
+        });
+    }
+
+    let (page, commands, errors) = parse_page_content(filepath, &file_content);
+    let import = PageImport {
+        file: PageFile {
+            path: relative_path.clone(),
+            hash,
+            revision: revision.to_owned(),
+            page: page.name,
+            platform: page.platform,
+            language: page.language,
+        },
+        commands,
+    };
+    Ok(ReadPage {
+        relative_path,
+        changed: Some((import, errors)),
+    })
+}
+
+/// Computes the FNV-1a hash of the content of a page, stable across versions and platforms.
+///
+/// # Arguments
+///
+/// * `content` - The content of the page.
+///
+/// # Returns
+///
+/// The hash, as hexadecimal.
+fn content_hash(content: &str) -> String {
+    let hash = content.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    });
+    format!("{hash:016x}")
+}
+
+/// Finds the folder holding the `pages*` folders of a tldr checkout.
+/// Archives of a repository wrap it in a folder (e.g. `tldr-main/pages`), which is looked into.
+///
+/// # Arguments
+///
+/// * `folder_path` - The checkout, or the folder an archive was extracted to.
+///
+/// # Returns
+///
+/// The folder holding the pages, `folder_path` itself if the layout is not recognised.
+pub fn find_pages_root(folder_path: &Path) -> PathBuf {
+    let has_pages = |dir: &Path| {
+        fs::read_dir(dir).is_ok_and(|entries| {
+            entries.flatten().any(|entry| {
+                entry.path().is_dir() && entry.file_name().to_string_lossy().starts_with("pages")
+            })
+        })
+    };
+    if has_pages(folder_path) {
+        return folder_path.to_path_buf();
+    }
+    let subfolders: Vec<PathBuf> = fs::read_dir(folder_path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .collect()
+        })
+        .unwrap_or_default();
+    match subfolders.as_slice() {
+        [subfolder] if has_pages(subfolder) => subfolder.clone(),
+        _ => folder_path.to_path_buf(),
+    }
+}
+
+/// Updates the database from a tldr archive, through `_clean_update_database`.
+/// The name of the archive is recorded as the revision of the pages.
+///
+/// # Arguments
+///
+/// * `db` - The database to update.
+/// * `archive_path` - The archive, e.g. a release `tldr.zip` or a `.tar.gz` of the repository.
+/// * `format` - The format of the archive.
+/// * `extract_folder` - An empty folder to extract the archive to, left for the caller to remove.
+///
+/// # Returns
+///
+/// The number of pages updated and removed.
+pub fn update_from_archive(
+    db: &SqliteDatabase,
+    archive_path: &Path,
+    format: ArchiveFormat,
+    extract_folder: &Path,
+) -> Result<UpdateSummary, UpdateError> {
+    archive::extract_archive(archive_path, format, extract_folder)
+        .map_err(|err| UpdateError::Read(archive_path.display().to_string(), err))?;
+    let pages_root = find_pages_root(extract_folder);
+    let revision = archive_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    _clean_update_database(db, &pages_root.to_string_lossy(), &revision)
+}
+
+/// Lists the pages in the specified folder.
+///
+/// # Arguments
+///
+/// * `folder_path` - The path to the folder containing the files to process.
+///
+/// # Returns
+///
+/// The paths of the pages, sorted.
+pub fn page_filepaths(folder_path: &str) -> Vec<String> {
+    let mut filepaths: Vec<String> = _read_folder(folder_path)
+        .into_iter()
+        .filter(|filepath| filepath.ends_with(".md"))
+        .collect();
+    // checkouts and archives also hold READMEs and such, when the pages are laid out
+    // as `pages[.<language>]/<platform>/<page>.md` only those are read
+    let in_pages_folder = |filepath: &String| {
+        Path::new(filepath)
+            .parent()
+            .and_then(Path::parent)
+            .and_then(Path::file_name)
+            .is_some_and(|name| name.to_string_lossy().starts_with("pages"))
+    };
+    if filepaths.iter().any(in_pages_folder) {
+        filepaths.retain(in_pages_folder);
+    }
+    filepaths.sort();
+    filepaths
+}
+
+/// Replaces the commands imported from navi cheatsheets with the `.cheat` files
+/// at the specified path, in a single transaction.
+/// Each command is put on a page named after its file.
+///
+/// # Arguments
+///
+/// * `db` - The database to update.
+/// * `path` - A `.cheat` file, or a folder searched recursively for them.
+///
+/// # Returns
+///
+/// The number of commands imported.
+pub fn import_navi_files(db: &SqliteDatabase, path: &str) -> Result<usize, UpdateError> {
+    let mut filepaths = if Path::new(path).is_dir() {
+        _read_folder(path)
+    } else {
+        vec![path.to_owned()]
+    };
+    filepaths.retain(|filepath| filepath.ends_with(".cheat"));
+    filepaths.sort();
+
+    let mut commands = Vec::new();
+    for filepath in &filepaths {
+        let (cheat_commands, errors) = navi::parse_cheat(&read_file(filepath)?);
+        print_parse_errors(filepath, &errors);
+        let page_name = Path::new(filepath)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("");
+        commands.extend(cheat_commands.into_iter().map(|mut command| {
+            command.page = page_name.to_owned();
+            command
+        }));
+    }
+    db.replace_source(NAVI_SOURCE, &commands)?;
+    Ok(commands.len())
+}
+
+/// Replaces the aliases and functions imported from shell startup files with the ones
+/// of the specified files, in a single transaction. When an alias is defined more than once,
+/// the last definition wins, as in the shell.
+///
+/// # Arguments
+///
+/// * `db` - The database to update.
+/// * `paths` - Startup files, e.g. `~/.bashrc`, or folders searched recursively for `.fish` files.
+///
+/// # Returns
+///
+/// The number of aliases and functions imported.
+pub fn import_alias_files(db: &SqliteDatabase, paths: &[PathBuf]) -> Result<usize, UpdateError> {
+    let mut filepaths = Vec::new();
+    for path in paths {
+        let path = path.to_string_lossy();
+        if Path::new(path.as_ref()).is_dir() {
+            let mut fish_files = _read_folder(&path);
+            fish_files.retain(|filepath| filepath.ends_with(".fish"));
+            fish_files.sort();
+            filepaths.append(&mut fish_files);
+        } else {
+            filepaths.push(path.into_owned());
+        }
+    }
+
+    let mut commands: Vec<Command> = Vec::new();
+    for filepath in &filepaths {
+        let syntax = ShellSyntax::from_path(filepath);
+        let (aliases, errors) = shell::parse_aliases(&read_file(filepath)?, syntax);
+        print_parse_errors(filepath, &errors);
+        for alias in aliases {
+            commands.retain(|command| command.page != alias.page || command.tags != alias.tags);
+            commands.push(alias);
+        }
+    }
+    db.replace_source(ALIAS_SOURCE, &commands)?;
+    Ok(commands.len())
+}
+
+/// Replaces the shell history with the commands of the specified history files,
+/// in a single transaction. The format of each file is told by its name (see `HistoryFormat`).
+///
+/// # Arguments
+///
+/// * `db` - The database to update.
+/// * `paths` - The history files, e.g. `~/.bash_history`.
+///
+/// # Returns
+///
+/// The number of distinct commands stored.
+pub fn import_history_files(db: &SqliteDatabase, paths: &[PathBuf]) -> Result<usize, UpdateError> {
+    let mut entries = Vec::new();
+    for path in paths {
+        let filepath = path.to_string_lossy();
+        let content = fs::read(path).map_err(|err| UpdateError::Read(filepath.to_string(), err))?;
+        let format = HistoryFormat::from_path(&filepath);
+        entries.extend(history::parse_history(&content, format));
+    }
+    // the files are read one after the other, the oldest entries of all of them go first
+    entries.sort_by_key(|entry| entry.timestamp);
+    Ok(db.replace_history(&entries)?)
+}
+
+/// Replaces the commands imported from `cheat` cheatsheets with the cheatsheets
+/// at the specified path, in a single transaction.
+/// Cheatsheets are files without extension, hidden files and folders (such as `.git`) are skipped.
+/// Each command is put on a page named after its cheatsheet.
+///
+/// # Arguments
+///
+/// * `db` - The database to update.
+/// * `path` - A cheatsheet, or a folder searched recursively for them.
+///
+/// # Returns
+///
+/// The number of commands imported.
+pub fn import_cheatsheets(db: &SqliteDatabase, path: &str) -> Result<usize, UpdateError> {
+    let mut filepaths = if Path::new(path).is_dir() {
+        _read_folder(path)
+            .into_iter()
+            .filter(|filepath| {
+                !is_hidden(filepath, path) && Path::new(filepath).extension().is_none()
+            })
+            .collect()
+    } else {
+        vec![path.to_owned()]
+    };
+    filepaths.sort();
+
+    let mut commands = Vec::new();
+    for filepath in &filepaths {
+        let (sheet_commands, errors) = cheat::parse_cheatsheet(&read_file(filepath)?);
+        print_parse_errors(filepath, &errors);
+        let sheet_name = Path::new(filepath)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("");
+        commands.extend(sheet_commands.into_iter().map(|mut command| {
+            command.page = sheet_name.to_owned();
+            command
+        }));
+    }
+    db.replace_source(CHEAT_SOURCE, &commands)?;
+    Ok(commands.len())
+}
+
+/// Loads the TOML and YAML cheatsheets of a folder, replacing the commands loaded before,
+/// if any file of the folder changed since then.
+/// Hidden files and folders (such as `.git`) are skipped.
+/// When a cheatsheet is invalid, nothing is replaced and the folder is loaded again next time.
+///
+/// # Arguments
+///
+/// * `db` - The database to update.
+/// * `folder_path` - The cheatsheet folder, see `sheets::parse_sheet` for the format of its files.
+///
+/// # Returns
+///
+/// The number of commands loaded, `None` if nothing changed.
+pub fn sync_sheet_folder(
+    db: &SqliteDatabase,
+    folder_path: &str,
+) -> Result<Option<usize>, UpdateError> {
+    let mut filepaths: Vec<(String, SheetFormat)> = _read_folder(folder_path)
+        .into_iter()
+        .filter(|filepath| !is_hidden(filepath, folder_path))
+        .filter_map(|filepath| {
+            let extension = Path::new(&filepath).extension()?.to_str()?;
+            let format = SheetFormat::from_extension(extension)?;
+            Some((filepath, format))
+        })
+        .collect();
+    filepaths.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let fingerprint = folder_fingerprint(folder_path, filepaths.iter().map(|(path, _)| path));
+    if db.get_folder_fingerprint(SHEET_SOURCE)?.as_deref() == Some(fingerprint.as_str()) {
+        return Ok(None);
+    }
+
+    let mut commands = Vec::new();
+    for (filepath, format) in &filepaths {
+        let page_name = Path::new(filepath)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("");
+        let mut sheet_commands = sheets::parse_sheet(&read_file(filepath)?, *format, page_name)
+            .map_err(|message| UpdateError::Parse(filepath.clone(), message))?;
+        commands.append(&mut sheet_commands);
+    }
+    db.replace_source(SHEET_SOURCE, &commands)?;
+    db.set_folder_fingerprint(SHEET_SOURCE, &fingerprint)?;
+    Ok(Some(commands.len()))
+}
+
+/// Computes a fingerprint of files, changing whenever a file is added, removed or modified.
+///
+/// # Arguments
+///
+/// * `folder_path` - The folder of the files, a different folder gives a different fingerprint.
+/// * `filepaths` - The paths of the files, sorted.
+///
+/// # Returns
+///
+/// The fingerprint, from the path, size and modification time of every file.
+fn folder_fingerprint<'a>(
+    folder_path: &str,
+    filepaths: impl Iterator<Item = &'a String>,
+) -> String {
+    let mut hasher = DefaultHasher::new();
+    folder_path.hash(&mut hasher);
+    for filepath in filepaths {
+        filepath.hash(&mut hasher);
+        if let Ok(metadata) = fs::metadata(filepath) {
+            metadata.len().hash(&mut hasher);
+            metadata.modified().ok().hash(&mut hasher);
+        }
+    }
+    format!("{:016x}", hasher.finish())
+}
+
+/// Checks if a file is hidden, or inside a hidden folder, within a folder.
+///
+/// # Arguments
+///
+/// * `filepath` - The path of the file.
+/// * `folder_path` - The folder the file was found in.
+///
+/// # Returns
+///
+/// `true` if a component of the path below the folder starts with `.`.
+fn is_hidden(filepath: &str, folder_path: &str) -> bool {
+    let path = Path::new(filepath);
+    path.strip_prefix(folder_path)
+        .unwrap_or(path)
+        .iter()
+        .any(|component| component.to_string_lossy().starts_with('.'))
+}
+
+/// Replaces the tools read from man pages with the pages in the specified folder,
+/// in a single transaction. Only the pages of user commands and administration commands
+/// (sections 1 and 8) are read, from `man1` and `man8` if the folder has them.
+///
+/// # Arguments
+///
+/// * `db` - The database to update.
+/// * `folder_path` - The man page folder, e.g. `/usr/share/man`.
+///
+/// # Returns
+///
+/// The number of tools and options imported.
+pub fn import_man_pages(
+    db: &SqliteDatabase,
+    folder_path: &str,
+) -> Result<(usize, usize), UpdateError> {
+    let section_folders: Vec<String> = ["man1", "man8"]
+        .iter()
+        .map(|section| Path::new(folder_path).join(section))
+        .filter(|path| path.is_dir())
+        .filter_map(|path| path.to_str().map(String::from))
+        .collect();
+    let mut filepaths: Vec<String> = if section_folders.is_empty() {
+        _read_folder(folder_path)
+    } else {
+        section_folders
+            .iter()
+            .flat_map(|folder| _read_folder(folder))
+            .collect()
+    };
+    filepaths.sort();
+
+    let mut tools = Vec::new();
+    for filepath in &filepaths {
+        if let Some(tool) = read_man_page(filepath)? {
+            tools.push(tool);
+        }
+    }
+    db.replace_tools(MAN_SOURCE, &tools)?;
+    Ok((
+        tools.len(),
+        tools.iter().map(|tool| tool.options.len()).sum(),
+    ))
+}
+
+/// Reads a man page, compressed with gzip if it ends with `.gz`.
+///
+/// # Arguments
+///
+/// * `filepath` - The path to the page, e.g. `/usr/share/man/man1/tar.1.gz`.
+///
+/// # Returns
+///
+/// The tool documented by the page, `None` if it is not a page of section 1 or 8
+/// or documents no tool.
+pub fn read_man_page(filepath: &str) -> Result<Option<Tool>, UpdateError> {
+    let file_name = Path::new(filepath)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("");
+    let Some((name, section)) = man::man_page_name(file_name) else {
+        return Ok(None);
+    };
+    if !section.starts_with(['1', '8']) {
+        return Ok(None);
+    }
+
+    let read_error = |err| UpdateError::Read(filepath.to_owned(), err);
+    let mut content = Vec::new();
+    let mut file = fs::File::open(filepath).map_err(read_error)?;
+    if filepath.ends_with(".gz") {
+        GzDecoder::new(file).read_to_end(&mut content)
+    } else {
+        file.read_to_end(&mut content)
+    }
+    .map_err(read_error)?;
+
+    // old pages are often in latin-1
+    let content = String::from_utf8_lossy(&content);
+    Ok(man::parse_man_page(name, section, &content))
+}
+
+/// Reads the contents of a file, basically a error handler
+///
+/// # Arguments
+///
+/// * `filepath` - The path to the file to read.
+///
+/// # Returns
+///
+/// The contents of the file as a `String`.
+fn read_file(filepath: &str) -> Result<String, UpdateError> {
+    fs::read_to_string(filepath).map_err(|err| UpdateError::Read(filepath.to_owned(), err))
+}
+
+/// Gets the page metadata of a parsed tldr page, along with the platform and language from the page location.
+///
+/// # Arguments
+///
+/// * `filepath` - The path of the page, e.g. `tldr-page/pages.de/linux/tar.md`.
+/// * `tldr_page` - The parsed page.
+///
+/// # Returns
+///
+/// The `Page` metadata, named after the file if the title is missing.
+fn page_metadata(filepath: &str, tldr_page: &TldrPage) -> Page {
+    let path = Path::new(filepath);
+    let (platform, language) = page_location(path);
+    let name = match tldr_page.title.as_str() {
+        "" => path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or(""),
+        title => title,
+    };
+    let mut page = Page::new(name, &platform, &language);
+    page.summary = tldr_page.summary.clone();
+    page.url = tldr_page.url.clone();
+    page
+}
+
+/// Gets the platform and language of a page from its location in a tldr checkout,
+/// pages are stored as `pages[.<language>]/<platform>/<page>.md`.
+///
+/// # Arguments
+///
+/// * `path` - The path of the page.
+///
+/// # Returns
+///
+/// The platform and language of the page, ("common", "en") if the layout is not recognised.
+fn page_location(path: &Path) -> (String, String) {
+    let dir_name = |dir: Option<&Path>| {
+        dir.and_then(|dir| dir.file_name())
+            .and_then(|name| name.to_str())
+            .unwrap_or("")
+            .to_owned()
+    };
+    let platform_dir = path.parent();
+    let platform = dir_name(platform_dir);
+    let pages_dir = dir_name(platform_dir.and_then(|dir| dir.parent()));
+
+    match pages_dir.strip_prefix("pages") {
+        Some(suffix) => {
+            let language = suffix.strip_prefix('.').unwrap_or("en");
+            (platform, language.to_owned())
+        }
+        None => ("common".to_owned(), "en".to_owned()),
+    }
+}
+
+/// Recursively reads all file paths within a folder.
+///
+/// # Arguments
+///
+/// * `dirpath` - The path to the folder to read.
+///
+/// # Returns
+///
+/// A vector of file paths.
+pub fn _read_folder(dirpath: &str) -> Vec<String> {
+    let mut filepaths = Vec::new();
+
+    if let Ok(entries) = fs::read_dir(dirpath) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_file() {
+                if let Some(filepath_str) = path.to_str() {
+                    filepaths.push(filepath_str.to_owned());
+                }
+            } else if path.is_dir() {
+                let sub_dir_path = path.to_str().unwrap();
+                let mut sub_dir_filepaths = _read_folder(sub_dir_path);
+                filepaths.append(&mut sub_dir_filepaths);
+            }
+        }
+    }
+
+    filepaths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::TLDR_SOURCE;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    const TAR_PAGE: &str = "# tar\n\n> Archiving utility.\n\n\
+        - Create an archive from files:\n\n`tar cf {{target.tar}} {{file1 file2}}`\n\n\
+        - Extract an archive:\n\n`tar xf {{source.tar}}`\n";
+
+    /// Creates an empty folder for a test, removing what an earlier run left in it.
+    fn test_folder(name: &str) -> PathBuf {
+        let folder =
+            std::env::temp_dir().join(format!("cmd-cat-file-parse-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&folder);
+        fs::create_dir_all(&folder).unwrap();
+        folder
+    }
+
+    /// Opens an in-memory database holding the commands of a single tldr page.
+    fn database_with_page(folder: &Path) -> SqliteDatabase {
+        let db = SqliteDatabase::open(":memory:").unwrap();
+        fs::create_dir_all(folder.join("pages/common")).unwrap();
+        fs::write(folder.join("pages/common/tar.md"), TAR_PAGE).unwrap();
+        let summary = _clean_update_database(&db, &folder.to_string_lossy(), "1").unwrap();
+        assert_eq!((summary.updated, summary.examples), (1, 2));
+        db
+    }
+
+    #[test]
+    fn update_from_empty_folder_leaves_database_unchanged() {
+        let pages = test_folder("pages");
+        let db = database_with_page(&pages);
+        let empty = test_folder("empty");
+
+        let result = _clean_update_database(&db, &empty.to_string_lossy(), "2");
+
+        assert!(matches!(result, Err(UpdateError::NoPages(_))));
+        assert_eq!(db.commands_by_source(TLDR_SOURCE).unwrap().len(), 2);
+        assert_eq!(db.get_page_files().unwrap().len(), 1);
+        fs::remove_dir_all(pages).unwrap();
+        fs::remove_dir_all(empty).unwrap();
+    }
+
+    #[test]
+    fn update_from_empty_archive_leaves_database_unchanged() {
+        let pages = test_folder("archive-pages");
+        let db = database_with_page(&pages);
+        let folder = test_folder("archive");
+        let archive_path = folder.join("tldr.tar.gz");
+        let encoder = GzEncoder::new(
+            fs::File::create(&archive_path).unwrap(),
+            Compression::default(),
+        );
+        tar::Builder::new(encoder)
+            .into_inner()
+            .unwrap()
+            .finish()
+            .unwrap();
+
+        let result = update_from_archive(
+            &db,
+            &archive_path,
+            ArchiveFormat::TarGz,
+            &folder.join("extracted"),
+        );
+
+        assert!(matches!(result, Err(UpdateError::NoPages(_))));
+        assert_eq!(db.commands_by_source(TLDR_SOURCE).unwrap().len(), 2);
+        assert_eq!(db.get_page_files().unwrap().len(), 1);
+        fs::remove_dir_all(pages).unwrap();
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    /// Gets the stored tldr commands as `(page, command, description)` by id.
+    fn tldr_commands(db: &SqliteDatabase) -> Vec<(i64, String, String, String)> {
+        db.commands_by_source(TLDR_SOURCE)
+            .unwrap()
+            .into_iter()
+            .map(|command| (command.id, command.page, command.name, command.description))
+            .collect()
+    }
+
+    #[test]
+    fn update_only_replaces_changed_pages_and_keeps_ids() {
+        let folder = test_folder("incremental");
+        let pages = folder.join("pages/common");
+        fs::create_dir_all(&pages).unwrap();
+        fs::write(pages.join("tar.md"), TAR_PAGE).unwrap();
+        fs::write(
+            pages.join("ls.md"),
+            "# ls\n\n> List files.\n\n- List all files:\n\n`ls -a`\n",
+        )
+        .unwrap();
+        fs::write(
+            pages.join("cp.md"),
+            "# cp\n\n> Copy files.\n\n- Copy a file:\n\n`cp {{source}} {{target}}`\n",
+        )
+        .unwrap();
+        fs::write(
+            pages.join("cat.md"),
+            "# cat\n\n> Print files.\n\n- Print a file:\n\n`cat {{file}}`\n",
+        )
+        .unwrap();
+        let folder_path = folder.to_string_lossy();
+        let db = SqliteDatabase::open(":memory:").unwrap();
+        _clean_update_database(&db, &folder_path, "1").unwrap();
+        let before = tldr_commands(&db);
+        let id_of = |name: &str| before.iter().find(|command| command.2 == name).unwrap().0;
+        let create_id = id_of("tar cf {{target.tar}} {{file1 file2}}");
+        let extract_id = id_of("tar xf {{source.tar}}");
+        let copy_id = id_of("cp {{source}} {{target}}");
+        db.set_favorite(extract_id, true).unwrap();
+
+        // tar is edited, ls is deleted, cp is renamed and cat is unchanged
+        fs::write(
+            pages.join("tar.md"),
+            TAR_PAGE
+                .replace("Extract an archive:", "Extract an archive in place:")
+                .replace("- Create an archive from files:\n\n`tar cf {{target.tar}} {{file1 file2}}`\n\n", "")
+                + "\n- List an archive:\n\n`tar tf {{source.tar}}`\n",
+        )
+        .unwrap();
+        fs::remove_file(pages.join("ls.md")).unwrap();
+        fs::rename(pages.join("cp.md"), pages.join("cp-renamed.md")).unwrap();
+        let summary = _clean_update_database(&db, &folder_path, "2").unwrap();
+        assert_eq!((summary.updated, summary.removed), (2, 2));
+
+        let after = tldr_commands(&db);
+        let cat = before.iter().find(|command| command.1 == "cat").unwrap();
+        let list_id = after
+            .iter()
+            .find(|command| command.2 == "tar tf {{source.tar}}")
+            .unwrap()
+            .0;
+        assert_eq!(
+            after,
+            [
+                cat.clone(),
+                (
+                    copy_id,
+                    "cp".to_owned(),
+                    "cp {{source}} {{target}}".to_owned(),
+                    "Copy a file:".to_owned()
+                ),
+                (
+                    extract_id,
+                    "tar".to_owned(),
+                    "tar xf {{source.tar}}".to_owned(),
+                    "Extract an archive in place:".to_owned()
+                ),
+                (
+                    list_id,
+                    "tar".to_owned(),
+                    "tar tf {{source.tar}}".to_owned(),
+                    "List an archive:".to_owned()
+                ),
+            ]
+        );
+        assert!(!after.iter().any(|command| command.0 == create_id));
+        assert!(
+            db.get_command("tar xf {{source.tar}}")
+                .unwrap()
+                .unwrap()
+                .favorite
+        );
+        let paths: Vec<String> = db
+            .get_page_files()
+            .unwrap()
+            .into_iter()
+            .map(|file| file.path)
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                "pages/common/cat.md",
+                "pages/common/cp-renamed.md",
+                "pages/common/tar.md"
+            ]
+        );
+
+        let summary = _clean_update_database(&db, &folder_path, "3").unwrap();
+        assert_eq!((summary.updated, summary.removed), (0, 0));
+        assert_eq!(tldr_commands(&db), after);
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
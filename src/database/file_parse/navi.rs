@@ -0,0 +1,318 @@
+use crate::commands::{Command, Variable, VariableKind, NAVI_SOURCE};
+use crate::database::file_parse::tldr::ParseError;
+
+/// The commands of a `%` section of a cheatsheet, along with the names of the variables
+/// they use, waiting for the variables of the section.
+#[derive(Default)]
+struct Section {
+    tags: Vec<String>,
+    commands: Vec<(Command, Vec<String>)>,
+    variables: Vec<Variable>,
+}
+
+/// Parses a navi cheatsheet:
+///
+/// ```text
+/// % git, code
+///
+/// # Change branch
+/// git checkout <branch>
+///
+/// $ branch: git branch | awk '{print $NF}'
+/// ```
+///
+/// `%` lines give the tags of the commands below them, `#` lines describe the next command,
+/// and `$ name: command` lines list the values of the `<name>` variables of their section.
+/// Variables become `{{name}}` placeholders, so the commands are filled in like tldr examples.
+/// Lines of a command block are joined, with `; ` unless the line ends with `\`.
+/// Extensions (`@`) and the fzf options of variables (after `---`) are not supported.
+///
+/// # Arguments
+///
+/// * `input` - The content of the cheatsheet.
+///
+/// # Returns
+///
+/// The commands, with `NAVI_SOURCE` as source, and the errors found in the cheatsheet.
+pub fn parse_cheat(input: &str) -> (Vec<Command>, Vec<ParseError>) {
+    let mut commands = Vec::new();
+    let mut errors = Vec::new();
+    let mut section = Section::default();
+    let mut description = String::new();
+    let mut description_continues = false;
+    let mut block: Vec<&str> = Vec::new();
+
+    for (index, line) in input.lines().enumerate() {
+        let line_number = index + 1;
+        let trimmed_line = line.trim();
+        let is_command_line =
+            !trimmed_line.is_empty() && !trimmed_line.starts_with(['%', '#', '$', ';', '@']);
+        if !is_command_line {
+            push_command(&mut section, &mut block, &mut description);
+        }
+
+        if let Some(tags) = trimmed_line.strip_prefix('%') {
+            push_section(&mut commands, &mut section);
+            section.tags = tags
+                .split(',')
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(String::from)
+                .collect();
+        } else if let Some(text) = trimmed_line.strip_prefix('#') {
+            if !description_continues {
+                description.clear();
+            }
+            if !description.is_empty() {
+                description.push(' ');
+            }
+            description.push_str(text.trim());
+        } else if let Some(variable) = trimmed_line.strip_prefix('$') {
+            match variable.split_once(':') {
+                Some((name, generator)) => {
+                    let generator = generator
+                        .split_once(" --- ")
+                        .map_or(generator, |(generator, _)| generator);
+                    section.variables.push(Variable {
+                        name: name.trim().to_owned(),
+                        kind: VariableKind::Command,
+                        generator: generator.trim().to_owned(),
+                        ..Default::default()
+                    });
+                }
+                None => errors.push(ParseError::new(
+                    line_number,
+                    "the variable should be written as `$ name: command`",
+                )),
+            }
+        } else if is_command_line {
+            block.push(trimmed_line);
+        }
+        description_continues = trimmed_line.starts_with('#');
+    }
+    push_command(&mut section, &mut block, &mut description);
+    push_section(&mut commands, &mut section);
+
+    (commands, errors)
+}
+
+/// Adds the command block being read to the section, described by `description`
+/// or by the command itself.
+///
+/// # Arguments
+///
+/// * `section` - The section being read.
+/// * `block` - The lines of the command, emptied.
+/// * `description` - The description of the command, cleared.
+fn push_command(section: &mut Section, block: &mut Vec<&str>, description: &mut String) {
+    if block.is_empty() {
+        return;
+    }
+    let mut command_text = String::new();
+    let mut continued = false;
+    for line in block.drain(..) {
+        if !command_text.is_empty() {
+            command_text.push_str(if continued { " " } else { "; " });
+        }
+        continued = line.ends_with('\\');
+        command_text.push_str(line.trim_end_matches('\\').trim_end());
+    }
+
+    let (command_text, names) = convert_variables(&command_text);
+    let command_description = match description.as_str() {
+        "" => command_text.clone(),
+        text => text.to_owned(),
+    };
+    let mut command = Command::new(&command_text, &command_description).with_source(NAVI_SOURCE);
+    command.tags = section.tags.clone();
+    section.commands.push((command, names));
+    description.clear();
+}
+
+/// Adds the commands of a section, along with the variables they use, to the commands.
+///
+/// # Arguments
+///
+/// * `commands` - The commands of the cheatsheet.
+/// * `section` - The section, replaced by an empty one.
+fn push_section(commands: &mut Vec<Command>, section: &mut Section) {
+    let section = std::mem::take(section);
+    for (mut command, names) in section.commands {
+        command.variables = section
+            .variables
+            .iter()
+            .filter(|variable| names.contains(&variable.name))
+            .cloned()
+            .collect();
+        commands.push(command);
+    }
+}
+
+/// Converts the `<name>` variables of a navi command to `{{name}}` placeholders,
+/// the braces already in the command are escaped so they are kept as they are.
+///
+/// # Arguments
+///
+/// * `command` - The command, e.g. `git checkout <branch>`.
+///
+/// # Returns
+///
+/// The converted command and the names of its variables.
+pub fn convert_variables(command: &str) -> (String, Vec<String>) {
+    let is_name_char = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+    let mut converted = String::new();
+    let mut names = Vec::new();
+    let mut rest = command;
+
+    while let Some(start) = rest.find(['<', '{', '}']) {
+        converted.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let name = rest
+            .strip_prefix('<')
+            .and_then(|after| after.split_once('>'))
+            .map(|(name, _)| name)
+            .filter(|name| !name.is_empty() && name.chars().all(is_name_char));
+        if let Some(name) = name {
+            converted.push_str(&format!("{{{{{name}}}}}"));
+            names.push(name.to_owned());
+            rest = &rest[name.len() + 2..];
+        } else if rest.starts_with("{{") || rest.starts_with("}}") {
+            converted.push_str(if rest.starts_with('{') {
+                "\\{\\{"
+            } else {
+                "\\}\\}"
+            });
+            rest = &rest[2..];
+        } else {
+            converted.push_str(&rest[..1]);
+            rest = &rest[1..];
+        }
+    }
+    converted.push_str(rest);
+    (converted, names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command_variable(name: &str, generator: &str) -> Variable {
+        Variable {
+            name: name.to_owned(),
+            kind: VariableKind::Command,
+            generator: generator.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parses_cheatsheet() {
+        let input = "% git, code\n\
+            \n\
+            # Change\n\
+            # branch\n\
+            git checkout <branch>\n\
+            \n\
+            git status\n\
+            \n\
+            # Build and test\n\
+            make \\\n\
+              -j4\n\
+            make test\n\
+            \n\
+            $ branch: git branch | awk '{print $NF}' --- --preview 'git log {}'\n\
+            $ unused: echo a\n\
+            \n\
+            % docker\n\
+            \n\
+            # Show the logs\n\
+            docker logs <branch>\n";
+
+        let (commands, errors) = parse_cheat(input);
+
+        assert_eq!(errors, []);
+        let summary: Vec<(&str, &str, Vec<String>)> = commands
+            .iter()
+            .map(|command| {
+                (
+                    command.name.as_str(),
+                    command.description.as_str(),
+                    command.tags.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (
+                    "git checkout {{branch}}",
+                    "Change branch",
+                    vec!["git".to_owned(), "code".to_owned()]
+                ),
+                (
+                    "git status",
+                    "git status",
+                    vec!["git".to_owned(), "code".to_owned()]
+                ),
+                (
+                    "make -j4; make test",
+                    "Build and test",
+                    vec!["git".to_owned(), "code".to_owned()]
+                ),
+                (
+                    "docker logs {{branch}}",
+                    "Show the logs",
+                    vec!["docker".to_owned()]
+                ),
+            ]
+        );
+        assert!(commands.iter().all(|command| command.source == NAVI_SOURCE));
+        assert_eq!(
+            commands[0].variables,
+            [command_variable("branch", "git branch | awk '{print $NF}'")]
+        );
+        assert_eq!(commands[1].variables, []);
+        // variables belong to the section they are written in
+        assert_eq!(commands[3].variables, []);
+    }
+
+    #[test]
+    fn reports_invalid_variables() {
+        let (commands, errors) = parse_cheat("% a\n\necho <x>\n\n$ x git branch\n");
+        assert_eq!(commands.len(), 1);
+        assert_eq!(
+            errors,
+            [ParseError::new(
+                5,
+                "the variable should be written as `$ name: command`"
+            )]
+        );
+    }
+
+    #[test]
+    fn converts_variables_to_placeholders() {
+        assert_eq!(
+            convert_variables("cp <source_file> <target-dir> <source_file>"),
+            (
+                "cp {{source_file}} {{target-dir}} {{source_file}}".to_owned(),
+                vec![
+                    "source_file".to_owned(),
+                    "target-dir".to_owned(),
+                    "source_file".to_owned()
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn keeps_other_text_as_it_is() {
+        assert_eq!(
+            convert_variables("echo {{x}} <a b> a<b 2>&1 <> {y} <ok>"),
+            (
+                "echo \\{\\{x\\}\\} <a b> a<b 2>&1 <> {y} {{ok}}".to_owned(),
+                vec!["ok".to_owned()]
+            )
+        );
+    }
+}
@@ -0,0 +1,304 @@
+use crate::commands::{Command, Variable, VariableKind, SHEET_SOURCE};
+use serde::{Deserialize, Serialize};
+
+/// The formats of the files of the cheatsheet folder.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SheetFormat {
+    Toml,
+    Yaml,
+}
+
+impl SheetFormat {
+    /// Gets the format of a cheatsheet from its extension.
+    ///
+    /// # Arguments
+    ///
+    /// * `extension` - `toml`, `yaml` or `yml`.
+    ///
+    /// # Returns
+    ///
+    /// The format, `None` for other files.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "toml" => Some(SheetFormat::Toml),
+            "yaml" | "yml" => Some(SheetFormat::Yaml),
+            _ => None,
+        }
+    }
+}
+
+/// The top level of a cheatsheet.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SheetFile {
+    /// The page of the commands that do not give one
+    #[serde(default)]
+    page: String,
+    #[serde(default)]
+    commands: Vec<SheetCommand>,
+}
+
+/// A command of a cheatsheet.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SheetCommand {
+    command: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    page: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    placeholders: Vec<SheetPlaceholder>,
+}
+
+/// A placeholder of a command of a cheatsheet, also how variables are written by `--export`.
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SheetPlaceholder {
+    name: String,
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    kind: Option<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    default: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    choices: Vec<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    command: String,
+}
+
+impl From<&Variable> for SheetPlaceholder {
+    fn from(variable: &Variable) -> Self {
+        SheetPlaceholder {
+            name: variable.name.clone(),
+            kind: Some(variable.kind.name().to_owned()),
+            default: variable.default.clone(),
+            choices: variable.choices.clone(),
+            command: variable.generator.clone(),
+        }
+    }
+}
+
+/// Parses a TOML or YAML cheatsheet:
+///
+/// ```toml
+/// page = "deploy"
+///
+/// [[commands]]
+/// command = "kubectl rollout restart deployment/{{deployment}} -n {{namespace}}"
+/// description = "Restart a deployment"
+/// tags = ["k8s"]
+/// placeholders = [
+///     { name = "deployment", type = "command", command = "kubectl get deploy -o name | cut -d/ -f2" },
+///     { name = "namespace", type = "choice", choices = ["prod", "staging"], default = "staging" },
+/// ]
+/// ```
+///
+/// The type of a placeholder is `text`, `number`, `choice` or `command`, it defaults to `choice`
+/// when `choices` are given, to `command` when a `command` is given, and to `text` otherwise.
+///
+/// # Arguments
+///
+/// * `content` - The content of the cheatsheet.
+/// * `format` - The format of the cheatsheet.
+/// * `default_page` - The page of the commands when the cheatsheet gives none.
+///
+/// # Returns
+///
+/// The commands, with `SHEET_SOURCE` as source.
+///
+/// # Errors
+///
+/// Returns a message describing the problem if the cheatsheet is invalid.
+pub fn parse_sheet(
+    content: &str,
+    format: SheetFormat,
+    default_page: &str,
+) -> Result<Vec<Command>, String> {
+    let sheet: SheetFile = match format {
+        SheetFormat::Toml => toml::from_str(content).map_err(|err| err.to_string())?,
+        SheetFormat::Yaml => serde_yaml::from_str(content).map_err(|err| err.to_string())?,
+    };
+    let sheet_page = match sheet.page.as_str() {
+        "" => default_page,
+        page => page,
+    };
+
+    let mut commands = Vec::new();
+    for sheet_command in sheet.commands {
+        let description = match sheet_command.description.as_str() {
+            "" => sheet_command.command.as_str(),
+            description => description,
+        };
+        let mut command =
+            Command::new(&sheet_command.command, description).with_source(SHEET_SOURCE);
+        command.page = match sheet_command.page.as_str() {
+            "" => sheet_page.to_owned(),
+            page => page.to_owned(),
+        };
+        command.tags = sheet_command.tags;
+        for placeholder in sheet_command.placeholders {
+            let variable = placeholder_variable(placeholder)
+                .map_err(|message| format!("{}: {message}", sheet_command.command))?;
+            command.variables.push(variable);
+        }
+        commands.push(command);
+    }
+    Ok(commands)
+}
+
+/// Converts a placeholder of a cheatsheet to the variable stored with its command.
+///
+/// # Arguments
+///
+/// * `placeholder` - The placeholder.
+///
+/// # Returns
+///
+/// The variable, or a message if the type is unknown or misses its `choices` or `command`.
+pub fn placeholder_variable(placeholder: SheetPlaceholder) -> Result<Variable, String> {
+    let kind = match placeholder.kind.as_deref() {
+        Some(name) => VariableKind::from_name(name).ok_or_else(|| {
+            format!(
+                "unknown type `{name}` of placeholder `{}`, expected text, number, choice or command",
+                placeholder.name
+            )
+        })?,
+        None if !placeholder.choices.is_empty() => VariableKind::Choice,
+        None if !placeholder.command.is_empty() => VariableKind::Command,
+        None => VariableKind::Text,
+    };
+    if kind == VariableKind::Choice && placeholder.choices.is_empty() {
+        return Err(format!("placeholder `{}` has no choices", placeholder.name));
+    }
+    if kind == VariableKind::Command && placeholder.command.is_empty() {
+        return Err(format!("placeholder `{}` has no command", placeholder.name));
+    }
+    Ok(Variable {
+        name: placeholder.name,
+        kind,
+        generator: placeholder.command,
+        choices: placeholder.choices,
+        default: placeholder.default,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_toml_sheet() {
+        let content = r#"
+page = "deploy"
+
+[[commands]]
+command = "kubectl rollout restart deployment/{{deployment}} -n {{namespace}}"
+description = "Restart a deployment"
+tags = ["k8s"]
+placeholders = [
+    { name = "deployment", type = "command", command = "kubectl get deploy -o name" },
+    { name = "namespace", choices = ["prod", "staging"], default = "staging" },
+]
+
+[[commands]]
+command = "kubectl get pods"
+page = "kubectl"
+"#;
+
+        let commands = parse_sheet(content, SheetFormat::Toml, "ops").unwrap();
+
+        assert_eq!(commands.len(), 2);
+        let restart = &commands[0];
+        assert_eq!(restart.description, "Restart a deployment");
+        assert_eq!(
+            (restart.page.as_str(), restart.source.as_str()),
+            ("deploy", SHEET_SOURCE)
+        );
+        assert_eq!(restart.tags, ["k8s"]);
+        assert_eq!(
+            restart.variables,
+            [
+                Variable {
+                    name: "deployment".to_owned(),
+                    kind: VariableKind::Command,
+                    generator: "kubectl get deploy -o name".to_owned(),
+                    ..Default::default()
+                },
+                Variable {
+                    name: "namespace".to_owned(),
+                    kind: VariableKind::Choice,
+                    choices: vec!["prod".to_owned(), "staging".to_owned()],
+                    default: "staging".to_owned(),
+                    ..Default::default()
+                },
+            ]
+        );
+        assert_eq!(commands[1].page, "kubectl");
+        assert_eq!(commands[1].description, "kubectl get pods");
+    }
+
+    #[test]
+    fn parses_yaml_sheet() {
+        let content = "commands:
+  - command: docker logs -f {{container}}
+    description: Follow the logs of a container
+    placeholders:
+      - name: container
+        command: docker ps --format '{{.Names}}'
+  - command: sleep {{seconds}}
+    placeholders:
+      - name: seconds
+        type: number
+        default: \"5\"
+";
+
+        let commands = parse_sheet(content, SheetFormat::Yaml, "docker").unwrap();
+
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[0].page, "docker");
+        assert_eq!(commands[0].variables[0].kind, VariableKind::Command);
+        assert_eq!(
+            commands[0].variables[0].generator,
+            "docker ps --format '{{.Names}}'"
+        );
+        assert_eq!(commands[1].description, "sleep {{seconds}}");
+        assert_eq!(commands[1].variables[0].kind, VariableKind::Number);
+        assert_eq!(commands[1].variables[0].default, "5");
+    }
+
+    #[test]
+    fn rejects_invalid_sheets() {
+        let unknown_field = "[[commands]]\ncommand = \"ls\"\ndescripton = \"List\"\n";
+        let err = parse_sheet(unknown_field, SheetFormat::Toml, "ls").unwrap_err();
+        assert!(err.contains("unknown field `descripton`"), "{err}");
+
+        let no_choices = "commands:\n  - command: git checkout {{branch}}\n    placeholders:\n      - name: branch\n        type: choice\n";
+        assert_eq!(
+            parse_sheet(no_choices, SheetFormat::Yaml, "git").unwrap_err(),
+            "git checkout {{branch}}: placeholder `branch` has no choices"
+        );
+
+        let no_command = "[[commands]]\ncommand = \"kill {{pid}}\"\nplaceholders = [{ name = \"pid\", type = \"command\" }]\n";
+        assert_eq!(
+            parse_sheet(no_command, SheetFormat::Toml, "kill").unwrap_err(),
+            "kill {{pid}}: placeholder `pid` has no command"
+        );
+
+        let unknown_type = "[[commands]]\ncommand = \"cd {{dir}}\"\nplaceholders = [{ name = \"dir\", type = \"folder\" }]\n";
+        let err = parse_sheet(unknown_type, SheetFormat::Toml, "cd").unwrap_err();
+        assert!(
+            err.starts_with("cd {{dir}}: unknown type `folder`"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn formats_come_from_extensions() {
+        assert_eq!(SheetFormat::from_extension("toml"), Some(SheetFormat::Toml));
+        assert_eq!(SheetFormat::from_extension("yml"), Some(SheetFormat::Yaml));
+        assert_eq!(SheetFormat::from_extension("yaml"), Some(SheetFormat::Yaml));
+        assert_eq!(SheetFormat::from_extension("md"), None);
+    }
+}
@@ -0,0 +1,431 @@
+use crate::commands::{Command, ALIAS_SOURCE, ALIAS_TAG, FUNCTION_TAG};
+use crate::database::file_parse::tldr::ParseError;
+
+/// The syntaxes of the shell startup files.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShellSyntax {
+    /// `.bashrc`, `.zshrc` and such
+    Posix,
+    /// The `.fish` files of `~/.config/fish`
+    Fish,
+}
+
+impl ShellSyntax {
+    /// Gets the syntax of a startup file from its name.
+    ///
+    /// # Arguments
+    ///
+    /// * `filepath` - The path of the file.
+    ///
+    /// # Returns
+    ///
+    /// `Fish` for `.fish` files, `Posix` otherwise.
+    pub fn from_path(filepath: &str) -> Self {
+        if filepath.ends_with(".fish") {
+            ShellSyntax::Fish
+        } else {
+            ShellSyntax::Posix
+        }
+    }
+}
+
+/// Parses the aliases and one-line functions of a shell startup file:
+///
+/// ```sh
+/// # Restart the stack
+/// alias rs='docker compose down && docker compose up -d'
+/// mkcd() { mkdir -p "$1" && cd "$1"; }
+/// ```
+///
+/// In fish files, `alias name 'value'`, `abbr -a name value` and `function name; body; end`
+/// are read instead. A comment on the line above, or at the end of the line, describes the
+/// command, otherwise it is described as `alias <name>`. Each command is put on a page named after
+/// the alias and tagged `alias` or `function`. Other lines are skipped.
+///
+/// # Arguments
+///
+/// * `input` - The content of the file.
+/// * `syntax` - The syntax of the file.
+///
+/// # Returns
+///
+/// The commands, with `ALIAS_SOURCE` as source, and the errors found in the definitions.
+pub fn parse_aliases(input: &str, syntax: ShellSyntax) -> (Vec<Command>, Vec<ParseError>) {
+    let mut commands = Vec::new();
+    let mut errors = Vec::new();
+    let mut comment = String::new();
+
+    for (index, line) in input.lines().enumerate() {
+        let trimmed_line = line.trim();
+        if let Some(text) = trimmed_line.strip_prefix('#') {
+            comment = text.trim().to_owned();
+            continue;
+        }
+
+        let definitions = match syntax {
+            ShellSyntax::Posix => parse_posix_line(trimmed_line),
+            ShellSyntax::Fish => parse_fish_line(trimmed_line),
+        };
+        match definitions {
+            Ok(definitions) => {
+                for definition in definitions {
+                    let description = [definition.comment.as_str(), comment.as_str()]
+                        .into_iter()
+                        .find(|text| !text.is_empty())
+                        .map_or_else(
+                            || format!("{} {}", definition.tag, definition.name),
+                            String::from,
+                        );
+                    let mut command =
+                        Command::new(&definition.value, &description).with_source(ALIAS_SOURCE);
+                    command.page = definition.name;
+                    command.tags = vec![definition.tag.to_owned()];
+                    commands.push(command);
+                }
+            }
+            Err(message) => errors.push(ParseError::new(index + 1, message)),
+        }
+        comment.clear();
+    }
+
+    (commands, errors)
+}
+
+/// An alias or function found on a line.
+struct Definition {
+    name: String,
+    /// What the alias expands to, or the body of the function
+    value: String,
+    /// `ALIAS_TAG` or `FUNCTION_TAG`
+    tag: &'static str,
+    /// The comment at the end of the line, empty if there is none
+    comment: String,
+}
+
+/// Parses a line of a POSIX shell file: `alias a='x' b='y'`, `name() { body; }`
+/// or `function name { body; }`.
+///
+/// # Arguments
+///
+/// * `line` - The trimmed line.
+///
+/// # Returns
+///
+/// The definitions of the line, none for other lines, or a message if a definition is invalid.
+fn parse_posix_line(line: &str) -> Result<Vec<Definition>, &'static str> {
+    if let Some(arguments) = line.strip_prefix("alias ") {
+        let (words, comment) = split_words(arguments)?;
+        return Ok(words
+            .iter()
+            .filter(|word| !word.starts_with('-'))
+            .filter_map(|word| word.split_once('='))
+            .map(|(name, value)| Definition {
+                name: name.to_owned(),
+                value: value.to_owned(),
+                tag: ALIAS_TAG,
+                comment: comment.clone(),
+            })
+            .collect());
+    }
+
+    let header_end = line.find('{').unwrap_or(0);
+    let header = line[..header_end].trim();
+    let header = header.strip_prefix("function ").unwrap_or(header);
+    let name = header.trim_end_matches("()").trim_end();
+    let is_function = (header.ends_with("()") || line.starts_with("function "))
+        && !name.is_empty()
+        && name.chars().all(is_name_char);
+    let body = line[header_end..]
+        .strip_prefix('{')
+        .and_then(|rest| rest.trim_end().strip_suffix('}'));
+    match body {
+        Some(body) if is_function => Ok(vec![Definition {
+            name: name.to_owned(),
+            value: body.trim().trim_end_matches(';').trim_end().to_owned(),
+            tag: FUNCTION_TAG,
+            comment: String::new(),
+        }]),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Parses a line of a fish file: `alias name 'value'`, `alias name='value'`, `abbr -a name value`
+/// or `function name; body; end`.
+///
+/// # Arguments
+///
+/// * `line` - The trimmed line.
+///
+/// # Returns
+///
+/// The definitions of the line, none for other lines, or a message if a definition is invalid.
+fn parse_fish_line(line: &str) -> Result<Vec<Definition>, &'static str> {
+    if let Some(arguments) = line
+        .strip_prefix("alias ")
+        .or_else(|| line.strip_prefix("abbr "))
+    {
+        let (words, comment) = split_words(arguments)?;
+        let mut words = words.into_iter().filter(|word| !word.starts_with('-'));
+        let Some(first_word) = words.next() else {
+            return Ok(Vec::new());
+        };
+        let (name, value) = match first_word.split_once('=') {
+            Some((name, value)) => (name.to_owned(), value.to_owned()),
+            None => (first_word, words.collect::<Vec<_>>().join(" ")),
+        };
+        if value.is_empty() {
+            return Ok(Vec::new());
+        }
+        return Ok(vec![Definition {
+            name,
+            value,
+            tag: ALIAS_TAG,
+            comment,
+        }]);
+    }
+
+    let Some((header, rest)) = line
+        .strip_prefix("function ")
+        .and_then(|rest| rest.split_once(';'))
+    else {
+        return Ok(Vec::new());
+    };
+    let Some(body) = rest.trim_end().strip_suffix("end") else {
+        return Ok(Vec::new());
+    };
+    let (words, _) = split_words(header)?;
+    let Some(name) = words.first().filter(|name| name.chars().all(is_name_char)) else {
+        return Ok(Vec::new());
+    };
+    let description = words
+        .iter()
+        .position(|word| word == "--description" || word == "-d")
+        .and_then(|position| words.get(position + 1))
+        .cloned()
+        .unwrap_or_default();
+    Ok(vec![Definition {
+        name: name.clone(),
+        value: body.trim().trim_end_matches(';').trim_end().to_owned(),
+        tag: FUNCTION_TAG,
+        comment: description,
+    }])
+}
+
+/// Splits shell arguments into words, removing the quotes and backslashes like the shell does.
+/// Words end at unquoted whitespace, and the arguments end at an unquoted `#` starting a word.
+///
+/// # Arguments
+///
+/// * `arguments` - The arguments, e.g. `ll='ls -l' gs="git status" # shortcuts`.
+///
+/// # Returns
+///
+/// The words and the comment at the end, or a message if a quote is never closed.
+fn split_words(arguments: &str) -> Result<(Vec<String>, String), &'static str> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = arguments.char_indices();
+
+    while let Some((position, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '#' if word.is_none() => {
+                let comment = arguments[position + 1..].trim().to_owned();
+                return Ok((words, comment));
+            }
+            '\'' => {
+                let text = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some((_, '\'')) => break,
+                        Some((_, c)) => text.push(c),
+                        None => return Err("the quote is never closed"),
+                    }
+                }
+            }
+            '"' => {
+                let text = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, c)) if matches!(c, '"' | '\\' | '$' | '`') => text.push(c),
+                            Some((_, c)) => {
+                                text.push('\\');
+                                text.push(c);
+                            }
+                            None => return Err("the quote is never closed"),
+                        },
+                        Some((_, c)) => text.push(c),
+                        None => return Err("the quote is never closed"),
+                    }
+                }
+            }
+            '\\' => {
+                let text = word.get_or_insert_with(String::new);
+                if let Some((_, c)) = chars.next() {
+                    text.push(c);
+                }
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok((words, String::new()))
+}
+
+/// Checks if a character can be part of the name of an alias or a function.
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':' | '+' | '@')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The page, text, description and tag of the parsed commands.
+    fn parse(input: &str, syntax: ShellSyntax) -> Vec<(String, String, String, String)> {
+        let (commands, errors) = parse_aliases(input, syntax);
+        assert_eq!(errors, []);
+        commands
+            .into_iter()
+            .map(|command| {
+                assert_eq!(command.source, ALIAS_SOURCE);
+                (
+                    command.page,
+                    command.name,
+                    command.description,
+                    command.tags.join(","),
+                )
+            })
+            .collect()
+    }
+
+    fn definition(
+        page: &str,
+        text: &str,
+        description: &str,
+        tag: &str,
+    ) -> (String, String, String, String) {
+        (
+            page.to_owned(),
+            text.to_owned(),
+            description.to_owned(),
+            tag.to_owned(),
+        )
+    }
+
+    #[test]
+    fn parses_posix_aliases() {
+        let input = "# Restart the stack\n\
+            alias rs='docker compose down && docker compose up -d'\n\
+            alias gs=\"git status\" ll='ls -l' # shortcuts\n\
+            alias -g G='| grep'\n\
+            alias la=ls\\ -a\n\
+            alias say=\"echo \\\"hi\\\" \\$USER\"\n\
+            \n\
+            # not describing anything\n\
+            export PATH=$PATH:~/bin\n\
+            alias l='ls -CF'\n";
+
+        assert_eq!(
+            parse(input, ShellSyntax::Posix),
+            [
+                definition(
+                    "rs",
+                    "docker compose down && docker compose up -d",
+                    "Restart the stack",
+                    ALIAS_TAG
+                ),
+                definition("gs", "git status", "shortcuts", ALIAS_TAG),
+                definition("ll", "ls -l", "shortcuts", ALIAS_TAG),
+                definition("G", "| grep", "alias G", ALIAS_TAG),
+                definition("la", "ls -a", "alias la", ALIAS_TAG),
+                definition("say", "echo \"hi\" $USER", "alias say", ALIAS_TAG),
+                definition("l", "ls -CF", "alias l", ALIAS_TAG),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_posix_functions() {
+        let input = "# Make a folder and go into it\n\
+            mkcd() { mkdir -p \"$1\" && cd \"$1\"; }\n\
+            function gclone { git clone \"$1\"; }\n\
+            function up() { cd ..; }\n\
+            build() {\n\
+            \x20   make\n\
+            }\n\
+            if [ -f x ]; then { echo; }; fi\n";
+
+        assert_eq!(
+            parse(input, ShellSyntax::Posix),
+            [
+                definition(
+                    "mkcd",
+                    "mkdir -p \"$1\" && cd \"$1\"",
+                    "Make a folder and go into it",
+                    FUNCTION_TAG
+                ),
+                definition(
+                    "gclone",
+                    "git clone \"$1\"",
+                    "function gclone",
+                    FUNCTION_TAG
+                ),
+                definition("up", "cd ..", "function up", FUNCTION_TAG),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_fish_definitions() {
+        let input = "alias gs 'git status'\n\
+            alias ll='ls -l'\n\
+            # Push the branch\n\
+            abbr -a gp git push\n\
+            abbr --add --global gco git checkout # switch branches\n\
+            function mkcd --description 'Make a folder and go into it'; mkdir -p $argv; and cd $argv; end\n\
+            function up; cd ..; end\n\
+            function multi\n\
+            \x20   echo\n\
+            end\n";
+
+        assert_eq!(
+            parse(input, ShellSyntax::Fish),
+            [
+                definition("gs", "git status", "alias gs", ALIAS_TAG),
+                definition("ll", "ls -l", "alias ll", ALIAS_TAG),
+                definition("gp", "git push", "Push the branch", ALIAS_TAG),
+                definition("gco", "git checkout", "switch branches", ALIAS_TAG),
+                definition(
+                    "mkcd",
+                    "mkdir -p $argv; and cd $argv",
+                    "Make a folder and go into it",
+                    FUNCTION_TAG
+                ),
+                definition("up", "cd ..", "function up", FUNCTION_TAG),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_unclosed_quotes() {
+        let (commands, errors) =
+            parse_aliases("alias ok='ls'\nalias broken='ls -l\n", ShellSyntax::Posix);
+        assert_eq!(commands.len(), 1);
+        assert_eq!(errors, [ParseError::new(2, "the quote is never closed")]);
+    }
+
+    #[test]
+    fn reads_syntax_from_path() {
+        assert_eq!(
+            ShellSyntax::from_path("/home/me/.config/fish/conf.d/abbr.fish"),
+            ShellSyntax::Fish
+        );
+        assert_eq!(
+            ShellSyntax::from_path("/home/me/.zshrc"),
+            ShellSyntax::Posix
+        );
+    }
+}
@@ -0,0 +1,719 @@
+use std::collections::HashMap;
+use std::fmt;
+
+/// A page following the tldr-pages format:
+///
+/// ```md
+/// # tar
+///
+/// > Archiving utility.
+/// > More information: <https://www.gnu.org/software/tar>.
+///
+/// - Create an archive from files:
+///
+/// `tar {{[-c|--create]}} {{[-f|--file]}} {{target.tar}} {{file1 file2 ...}}`
+/// ```
+#[derive(Debug, Default, PartialEq)]
+pub struct TldrPage {
+    pub title: String,
+    pub summary: String,
+    pub url: String,
+    pub examples: Vec<Example>,
+}
+
+/// An example of a page, the command keeps its placeholders (see `parse_command`).
+#[derive(Debug, PartialEq)]
+pub struct Example {
+    pub description: String,
+    pub command: String,
+    /// The line of the command in the page, starting at 1
+    pub line: usize,
+    /// The line of the `- ` description in the page
+    pub description_line: usize,
+}
+
+/// A problem found in a page, pointing at the line it was found on.
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(line: usize, message: &str) -> Self {
+        ParseError {
+            line,
+            message: message.to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// A part of an example command, either literal text or a placeholder.
+#[derive(Debug, PartialEq)]
+pub enum Segment {
+    /// Literal text, with the escaped braces (`\{\{`, `\}\}`) already unescaped
+    Text(String),
+    Placeholder(Placeholder),
+}
+
+/// A `{{...}}` placeholder of an example command.
+#[derive(Debug, PartialEq)]
+pub enum Placeholder {
+    /// A single value, e.g. `{{path/to/file}}`
+    Value(String),
+    /// Any number of values, e.g. `{{file1 file2 ...}}`
+    Repeated(String),
+    /// A value within a range, e.g. `{{1..10}}`
+    Range(String, String),
+    /// Alternative spellings of the same option, e.g. `{{[-o|--output]}}`
+    Option(Vec<String>),
+}
+
+impl Placeholder {
+    /// Parses the text between the braces of a placeholder.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text of the placeholder, without the braces.
+    ///
+    /// # Returns
+    ///
+    /// The kind of placeholder.
+    fn parse(text: &str) -> Self {
+        if let Some(options) = text
+            .strip_prefix('[')
+            .and_then(|text| text.strip_suffix(']'))
+        {
+            if options.contains('|') {
+                return Placeholder::Option(
+                    options
+                        .split('|')
+                        .map(str::trim)
+                        .filter(|option| !option.is_empty())
+                        .map(String::from)
+                        .collect(),
+                );
+            }
+        }
+        if text.ends_with("...") {
+            return Placeholder::Repeated(text.to_owned());
+        }
+        match text.split_once("..") {
+            Some((start, end))
+                if !start.is_empty()
+                    && !end.is_empty()
+                    && !end.starts_with('.')
+                    && !text.contains(char::is_whitespace) =>
+            {
+                Placeholder::Range(start.to_owned(), end.to_owned())
+            }
+            _ => Placeholder::Value(text.to_owned()),
+        }
+    }
+
+    /// Gets the spelling of an option placeholder to use in a command, the long one if there is one.
+    ///
+    /// # Returns
+    ///
+    /// The option, `None` for the other placeholders.
+    pub fn preferred_option(&self) -> Option<&str> {
+        match self {
+            Placeholder::Option(options) => options
+                .iter()
+                .find(|option| option.starts_with("--"))
+                .or_else(|| options.last())
+                .map(String::as_str),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Placeholder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Placeholder::Value(text) | Placeholder::Repeated(text) => write!(f, "{text}"),
+            Placeholder::Range(start, end) => write!(f, "{start} to {end}"),
+            Placeholder::Option(options) => write!(f, "{}", options.join("|")),
+        }
+    }
+}
+
+/// Parses a tldr page. Examples are kept whenever possible, everything that does not follow
+/// the format is reported as an error with its line number.
+///
+/// # Arguments
+///
+/// * `input` - The content of the page.
+///
+/// # Returns
+///
+/// The parsed page and the errors found in it.
+pub fn parse_page(input: &str) -> (TldrPage, Vec<ParseError>) {
+    let mut page = TldrPage::default();
+    let mut errors = Vec::new();
+    let mut summary_lines = Vec::new();
+    // description of the example waiting for its command, with its line
+    let mut description: Option<(String, usize)> = None;
+
+    for (index, line) in input.lines().enumerate() {
+        let line_number = index + 1;
+        let trimmed_line = line.trim();
+
+        if trimmed_line.is_empty() {
+            continue;
+        } else if let Some(title) = trimmed_line.strip_prefix('#') {
+            if !page.title.is_empty() {
+                errors.push(ParseError::new(
+                    line_number,
+                    "the page has more than one title",
+                ));
+            } else {
+                page.title = title.trim().to_owned();
+            }
+        } else if let Some(summary_line) = trimmed_line.strip_prefix('>') {
+            if description.is_some() || !page.examples.is_empty() {
+                errors.push(ParseError::new(
+                    line_number,
+                    "the summary should come before the examples",
+                ));
+            }
+            // "More information: <url>." in the language of the page
+            let summary_line = summary_line.trim();
+            match summary_line
+                .split_once("<http")
+                .and_then(|(_, link)| link.split_once('>'))
+            {
+                Some((link, _)) => page.url = format!("http{link}"),
+                None => summary_lines.push(summary_line),
+            }
+        } else if let Some(text) = trimmed_line.strip_prefix('-') {
+            if let Some((_, description_line)) = description {
+                errors.push(ParseError::new(
+                    description_line,
+                    "the example has no command",
+                ));
+            }
+            description = Some((text.trim().to_owned(), line_number));
+        } else if trimmed_line.starts_with('`') {
+            // commands holding backticks are fenced with more of them, e.g. ``echo `date` ``
+            let fence_len = match trimmed_line.trim_start_matches('`') {
+                "" => 1,
+                text => trimmed_line.len() - text.len(),
+            };
+            let (fence, text) = trimmed_line.split_at(fence_len);
+            let command = match text.rfind(fence) {
+                Some(end) => {
+                    let trailing_text = text[end + fence_len..].trim();
+                    if !trailing_text.is_empty() {
+                        errors.push(ParseError::new(
+                            line_number,
+                            &format!("unexpected text after the command: {trailing_text}"),
+                        ));
+                    }
+                    &text[..end]
+                }
+                None => {
+                    errors.push(ParseError::new(
+                        line_number,
+                        "the command has no closing backtick",
+                    ));
+                    text
+                }
+            };
+            let command = command.trim();
+            if !braces_balanced(command) {
+                errors.push(ParseError::new(
+                    line_number,
+                    "unbalanced placeholder braces, use `\\{\\{` and `\\}\\}` for literal braces",
+                ));
+            }
+
+            match description.take() {
+                Some(_) if command.is_empty() => {
+                    errors.push(ParseError::new(line_number, "the command is empty"))
+                }
+                Some((description, description_line)) => page.examples.push(Example {
+                    description,
+                    command: command.to_owned(),
+                    line: line_number,
+                    description_line,
+                }),
+                None => errors.push(ParseError::new(
+                    line_number,
+                    "the command has no description, it should follow a `- ` line",
+                )),
+            }
+        } else if let Some((text, _)) = description.as_mut() {
+            // a description wrapped over several lines
+            text.push(' ');
+            text.push_str(trimmed_line);
+        } else {
+            errors.push(ParseError::new(
+                line_number,
+                &format!("unexpected line: {trimmed_line}"),
+            ));
+        }
+    }
+
+    if let Some((_, description_line)) = description {
+        errors.push(ParseError::new(
+            description_line,
+            "the example has no command",
+        ));
+    }
+    if page.title.is_empty() {
+        errors.push(ParseError::new(1, "the page has no `# title`"));
+    }
+
+    page.summary = summary_lines.join(" ");
+    (page, errors)
+}
+
+/// Checks a tldr page against the format and the placeholder rules, more strictly than
+/// `parse_page`: on top of its errors, the title should match the file name, the page should
+/// have a summary, descriptions should end with `:`, placeholders should not be empty
+/// and no command should be given twice.
+///
+/// # Arguments
+///
+/// * `input` - The content of the page.
+/// * `file_stem` - The name of the page file without `.md`, e.g. `git-commit` for `# git commit`.
+///
+/// # Returns
+///
+/// The problems found in the page, sorted by line.
+pub fn lint_page(input: &str, file_stem: &str) -> Vec<ParseError> {
+    let (page, mut errors) = parse_page(input);
+
+    let title_line = input.lines().position(|line| line.trim().starts_with('#'));
+    if let Some(index) = title_line {
+        let line = input.lines().nth(index).unwrap_or("").trim();
+        if !line.starts_with("# ") || line.starts_with("##") {
+            errors.push(ParseError::new(
+                index + 1,
+                "the title should be written as `# name`",
+            ));
+        }
+        let expected_stem = page.title.to_lowercase().replace(' ', "-");
+        if !page.title.is_empty() && expected_stem != file_stem {
+            errors.push(ParseError::new(
+                index + 1,
+                &format!(
+                    "the title `{}` does not match the file name, expected `{expected_stem}.md`",
+                    page.title
+                ),
+            ));
+        }
+    }
+    if page.summary.is_empty() {
+        errors.push(ParseError::new(
+            title_line.map_or(1, |index| index + 1),
+            "the page has no `> summary`",
+        ));
+    }
+
+    let mut command_lines: HashMap<&str, usize> = HashMap::new();
+    for example in &page.examples {
+        if example.description.is_empty() {
+            errors.push(ParseError::new(
+                example.description_line,
+                "the description is empty",
+            ));
+        } else if !example.description.ends_with(':') {
+            errors.push(ParseError::new(
+                example.description_line,
+                "the description should end with `:`",
+            ));
+        }
+        let has_empty_placeholder = parse_command(&example.command).iter().any(|segment| {
+            matches!(segment, Segment::Placeholder(Placeholder::Value(text)) if text.trim().is_empty())
+        });
+        if has_empty_placeholder {
+            errors.push(ParseError::new(
+                example.line,
+                "the command has an empty placeholder",
+            ));
+        }
+        match command_lines.get(example.command.as_str()) {
+            Some(first_line) => errors.push(ParseError::new(
+                example.line,
+                &format!("the command is the same as the example on line {first_line}"),
+            )),
+            None => {
+                command_lines.insert(&example.command, example.line);
+            }
+        }
+    }
+
+    errors.sort_by_key(|error| error.line);
+    errors
+}
+
+/// Splits an example command into text and placeholders.
+/// Unbalanced braces are kept as text.
+///
+/// # Arguments
+///
+/// * `command` - The command, e.g. `tar {{[-x|--extract]}} {{[-f|--file]}} {{path/to/file.tar}}`.
+///
+/// # Returns
+///
+/// The segments of the command, in order.
+pub fn parse_command(command: &str) -> Vec<Segment> {
+    split_command(command).0
+}
+
+/// Checks that every `{{` of a command is closed and every `}}` was opened.
+///
+/// # Arguments
+///
+/// * `command` - The command to check.
+///
+/// # Returns
+///
+/// `true` if the braces are balanced.
+fn braces_balanced(command: &str) -> bool {
+    split_command(command).1
+}
+
+/// Splits an example command into text and placeholders, see `parse_command`.
+///
+/// # Arguments
+///
+/// * `command` - The command to split.
+///
+/// # Returns
+///
+/// The segments of the command and whether its braces are balanced.
+fn split_command(command: &str) -> (Vec<Segment>, bool) {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut balanced = true;
+    let mut rest = command;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("\\{\\{") {
+            text.push_str("{{");
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("\\}\\}") {
+            text.push_str("}}");
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("{{") {
+            match placeholder_end(after) {
+                Some(end) => {
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    segments.push(Segment::Placeholder(Placeholder::parse(&after[..end])));
+                    rest = &after[end + 2..];
+                }
+                None => {
+                    balanced = false;
+                    text.push_str("{{");
+                    rest = after;
+                }
+            }
+        } else if let Some(after) = rest.strip_prefix("}}") {
+            balanced = false;
+            text.push_str("}}");
+            rest = after;
+        } else {
+            text.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+    (segments, balanced)
+}
+
+/// Finds the closing braces of a placeholder, braces inside of it (`{{{a,b}}}`) are kept.
+///
+/// # Arguments
+///
+/// * `text` - The text following the opening braces.
+///
+/// # Returns
+///
+/// The index of the closing braces, if there are any.
+fn placeholder_end(text: &str) -> Option<usize> {
+    let mut end = text.find("}}")?;
+    if text[..end].contains("{{") {
+        return None;
+    }
+    while text[end + 2..].starts_with('}') {
+        end += 1;
+    }
+    Some(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> Segment {
+        Segment::Text(text.to_owned())
+    }
+
+    fn placeholder(placeholder: Placeholder) -> Segment {
+        Segment::Placeholder(placeholder)
+    }
+
+    #[test]
+    fn parses_page() {
+        let input = "# tar\n\
+            \n\
+            > Archiving utility.\n\
+            > Often combined with a compression method.\n\
+            > More information: <https://www.gnu.org/software/tar>.\n\
+            \n\
+            - Create an archive\n\
+            from files:\n\
+            \n\
+            `tar cf {{target.tar}} {{file1 file2 ...}}`\n\
+            \n\
+            - Extract an archive:\n\
+            \n\
+            `tar xf {{source.tar}}`\n";
+
+        let (page, errors) = parse_page(input);
+
+        assert_eq!(errors, []);
+        assert_eq!(page.title, "tar");
+        assert_eq!(
+            page.summary,
+            "Archiving utility. Often combined with a compression method."
+        );
+        assert_eq!(page.url, "https://www.gnu.org/software/tar");
+        assert_eq!(
+            page.examples,
+            [
+                Example {
+                    description: "Create an archive from files:".to_owned(),
+                    command: "tar cf {{target.tar}} {{file1 file2 ...}}".to_owned(),
+                    line: 10,
+                    description_line: 7,
+                },
+                Example {
+                    description: "Extract an archive:".to_owned(),
+                    command: "tar xf {{source.tar}}".to_owned(),
+                    line: 14,
+                    description_line: 12,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_commands_fenced_with_more_backticks() {
+        let input = "# date\n\
+            \n\
+            - Print the date:\n\
+            \n\
+            `` echo `date` ``\n\
+            \n\
+            - Print the working directory:\n\
+            \n\
+            ```echo ``pwd`` ```\n";
+
+        let (page, errors) = parse_page(input);
+
+        assert_eq!(errors, []);
+        let commands: Vec<&str> = page.examples.iter().map(|e| e.command.as_str()).collect();
+        assert_eq!(commands, ["echo `date`", "echo ``pwd``"]);
+    }
+
+    #[test]
+    fn reports_errors_with_their_line() {
+        let input = "> Summary.\n\
+            \n\
+            - No command here:\n\
+            \n\
+            - Unclosed command:\n\
+            \n\
+            `ls {{path\n\
+            \n\
+            `echo` and more\n\
+            \n\
+            stray text\n\
+            # second\n\
+            \n\
+            - Empty command:\n\
+            \n\
+            ``\n\
+            \n\
+            > Late summary.\n\
+            \n\
+            - Trailing description:\n";
+
+        let (page, errors) = parse_page(input);
+
+        let lines: Vec<(usize, &str)> = errors
+            .iter()
+            .map(|error| (error.line, error.message.as_str()))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                (3, "the example has no command"),
+                (7, "the command has no closing backtick"),
+                (
+                    7,
+                    "unbalanced placeholder braces, use `\\{\\{` and `\\}\\}` for literal braces"
+                ),
+                (9, "unexpected text after the command: and more"),
+                (
+                    9,
+                    "the command has no description, it should follow a `- ` line"
+                ),
+                (11, "unexpected line: stray text"),
+                (16, "the command is empty"),
+                (18, "the summary should come before the examples"),
+                (20, "the example has no command"),
+            ]
+        );
+        assert_eq!(page.title, "second");
+        assert_eq!(page.examples.len(), 1);
+        assert_eq!(page.examples[0].line, 7);
+    }
+
+    #[test]
+    fn reports_missing_and_repeated_titles() {
+        let (_, errors) = parse_page("- Example:\n\n`ls`\n");
+        assert_eq!(errors, [ParseError::new(1, "the page has no `# title`")]);
+
+        let (page, errors) = parse_page("# ls\n# dir\n");
+        assert_eq!(page.title, "ls");
+        assert_eq!(
+            errors,
+            [ParseError::new(2, "the page has more than one title")]
+        );
+    }
+
+    #[test]
+    fn parses_placeholder_kinds() {
+        assert_eq!(
+            parse_command(
+                "cmd {{path/to/file}} {{file1 file2 ...}} {{1..10}} {{[-o|--output]}} {{a..}}"
+            ),
+            [
+                text("cmd "),
+                placeholder(Placeholder::Value("path/to/file".to_owned())),
+                text(" "),
+                placeholder(Placeholder::Repeated("file1 file2 ...".to_owned())),
+                text(" "),
+                placeholder(Placeholder::Range("1".to_owned(), "10".to_owned())),
+                text(" "),
+                placeholder(Placeholder::Option(vec![
+                    "-o".to_owned(),
+                    "--output".to_owned()
+                ])),
+                text(" "),
+                placeholder(Placeholder::Value("a..".to_owned())),
+            ]
+        );
+        assert_eq!(
+            parse_command("{{[-v]}} {{1 .. 10}}"),
+            [
+                placeholder(Placeholder::Value("[-v]".to_owned())),
+                text(" "),
+                placeholder(Placeholder::Value("1 .. 10".to_owned())),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_escaped_and_unbalanced_braces_as_text() {
+        assert_eq!(
+            parse_command("echo \\{\\{x\\}\\} {{{a,b}}}"),
+            [
+                text("echo {{x}} "),
+                placeholder(Placeholder::Value("{a,b}".to_owned())),
+            ]
+        );
+        assert_eq!(parse_command("echo {{a"), [text("echo {{a")]);
+        assert!(!braces_balanced("echo {{a"));
+        assert!(!braces_balanced("echo a}}"));
+        assert!(braces_balanced("echo \\{\\{a {{b}}"));
+    }
+
+    #[test]
+    fn prefers_long_options() {
+        let option = |options: &[&str]| {
+            Placeholder::Option(options.iter().map(|option| option.to_string()).collect())
+        };
+        assert_eq!(
+            option(&["-o", "--output"]).preferred_option(),
+            Some("--output")
+        );
+        assert_eq!(option(&["-o", "-O"]).preferred_option(), Some("-O"));
+        assert_eq!(
+            Placeholder::Value("file".to_owned()).preferred_option(),
+            None
+        );
+    }
+
+    fn lint_messages(input: &str, file_stem: &str) -> Vec<(usize, String)> {
+        lint_page(input, file_stem)
+            .into_iter()
+            .map(|error| (error.line, error.message))
+            .collect()
+    }
+
+    #[test]
+    fn lint_accepts_valid_page() {
+        let input = "# git commit\n\n> Commit files.\n\n- Commit staged files:\n\n`git commit`\n";
+        assert_eq!(lint_messages(input, "git-commit"), []);
+    }
+
+    #[test]
+    fn lint_reports_title_not_matching_file_name() {
+        let input = "# git commit\n\n> Commit files.\n\n- Commit staged files:\n\n`git commit`\n";
+        assert_eq!(
+            lint_messages(input, "commit"),
+            [(
+                1,
+                "the title `git commit` does not match the file name, expected `git-commit.md`"
+                    .to_owned()
+            )]
+        );
+        assert_eq!(
+            lint_messages("#ls\n\n> List.\n\n- List:\n\n`ls`\n", "ls"),
+            [(1, "the title should be written as `# name`".to_owned())]
+        );
+    }
+
+    #[test]
+    fn lint_reports_descriptions_summary_and_placeholders() {
+        let input = "# ls\n\n- List files\n\n`ls {{ }}`\n\n-\n\n`ls -a`\n";
+        assert_eq!(
+            lint_messages(input, "ls"),
+            [
+                (1, "the page has no `> summary`".to_owned()),
+                (3, "the description should end with `:`".to_owned()),
+                (5, "the command has an empty placeholder".to_owned()),
+                (7, "the description is empty".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn lint_reports_duplicate_examples() {
+        let input = "# ls\n\n> List.\n\n- List:\n\n`ls -a`\n\n- List again:\n\n`ls -a`\n";
+        assert_eq!(
+            lint_messages(input, "ls"),
+            [(
+                11,
+                "the command is the same as the example on line 7".to_owned()
+            )]
+        );
+    }
+}
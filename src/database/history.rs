@@ -0,0 +1,107 @@
+use crate::commands::{Command, HISTORY_SOURCE};
+use crate::database::SqliteDatabase;
+use rusqlite::{params, Result};
+
+/// A command read from a shell history file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HistoryEntry {
+    pub command: String,
+    /// When the command was run, in seconds since the epoch, 0 if the history has no timestamps
+    pub timestamp: i64,
+}
+
+impl SqliteDatabase {
+    /// Replaces the shell history with the specified entries, in a single transaction.
+    /// Entries of the same command are stored once, along with the number of runs and the last one.
+    ///
+    /// # Arguments
+    ///
+    /// * `entries` - The entries of the history files, oldest first.
+    ///
+    /// # Returns
+    ///
+    /// The number of distinct commands stored.
+    pub fn replace_history(&self, entries: &[HistoryEntry]) -> Result<usize> {
+        let transaction = self.connection.unchecked_transaction()?;
+        self.connection.execute("DELETE FROM shell_history", [])?;
+        {
+            let mut stmt = self.connection.prepare(
+                "INSERT INTO shell_history (command, last_run, position) VALUES (?1, ?2, ?3)
+                ON CONFLICT (command) DO UPDATE SET
+                    last_run = max(last_run, excluded.last_run),
+                    run_count = run_count + 1,
+                    position = excluded.position",
+            )?;
+            for (position, entry) in entries.iter().enumerate() {
+                stmt.execute(params![entry.command, entry.timestamp, position])?;
+            }
+        }
+        let count = self
+            .connection
+            .query_row("SELECT COUNT(*) FROM shell_history", [], |row| row.get(0))?;
+        transaction.commit()?;
+        Ok(count)
+    }
+
+    /// Get the commands of the shell history matching a full-text query, most recent first.
+    /// Commands already stored as commands are left out, they are found as such.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The FTS5 query string, see `search::SearchQuery`; empty for the whole history.
+    ///
+    /// # Returns
+    ///
+    /// The matching commands, with `HISTORY_SOURCE` as source and a description telling
+    /// how often and how long ago they were run.
+    pub fn search_history(&self, text: &str) -> Result<Vec<Command>> {
+        let mut stmt = self.connection.prepare_cached(
+            "SELECT shell_history.command, shell_history.run_count,
+                CASE WHEN shell_history.last_run = 0 THEN NULL
+                ELSE unixepoch() - shell_history.last_run END
+            FROM shell_history
+            WHERE (?1 = '' OR shell_history.id IN
+                (SELECT rowid FROM shell_history_fts WHERE shell_history_fts MATCH ?1))
+            AND NOT EXISTS (SELECT 1 FROM commands WHERE commands.command_name = shell_history.command)
+            ORDER BY shell_history.last_run DESC, shell_history.position DESC",
+        )?;
+        let rows = stmt.query_map([text], |row| {
+            let description = history_description(row.get(1)?, row.get(2)?);
+            Ok(Command::new(&row.get::<_, String>(0)?, &description).with_source(HISTORY_SOURCE))
+        })?;
+        rows.collect()
+    }
+}
+
+/// Describes a command of the shell history, e.g. `You ran this 3 times, last 2 days ago`.
+///
+/// # Arguments
+///
+/// * `run_count` - How many times the command was run.
+/// * `age` - The seconds since the last run, `None` if unknown.
+///
+/// # Returns
+///
+/// The description.
+fn history_description(run_count: i64, age: Option<i64>) -> String {
+    let age = age.map(|seconds| {
+        let (count, unit) = match seconds.max(0) {
+            seconds if seconds < 3600 => (seconds / 60, "minute"),
+            seconds if seconds < 86400 => (seconds / 3600, "hour"),
+            seconds if seconds < 86400 * 30 => (seconds / 86400, "day"),
+            seconds if seconds < 86400 * 365 => (seconds / (86400 * 30), "month"),
+            seconds => (seconds / (86400 * 365), "year"),
+        };
+        match count {
+            0 => "just now".to_owned(),
+            1 => format!("1 {unit} ago"),
+            count => format!("{count} {unit}s ago"),
+        }
+    });
+    match (run_count, age) {
+        (1, Some(age)) => format!("You ran this {age}"),
+        (1, None) => "You ran this before".to_owned(),
+        (count, Some(age)) => format!("You ran this {count} times, last {age}"),
+        (count, None) => format!("You ran this {count} times"),
+    }
+}
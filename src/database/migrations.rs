@@ -0,0 +1,374 @@
+use rusqlite::{ffi, Connection, Error, Result};
+
+/// Schema migrations, applied in order. Migration `n` (counting from 1) upgrades a database
+/// from `PRAGMA user_version = n - 1` to `n`, so entries must only ever be appended, never edited.
+/// Statements stay idempotent where possible, as databases created before versioning
+/// already contain some of these objects while reporting version 0.
+const MIGRATIONS: &[&str] = &[
+    // 1: the commands table
+    "CREATE TABLE IF NOT EXISTS commands (
+        id INTEGER PRIMARY KEY,
+        command_name TEXT NOT NULL,
+        description TEXT NOT NULL
+    );",
+    // 2: full-text index over commands, kept in sync by triggers
+    "CREATE VIRTUAL TABLE IF NOT EXISTS commands_fts USING fts5(
+        command_name,
+        description,
+        content='commands',
+        content_rowid='id'
+    );
+    CREATE TRIGGER IF NOT EXISTS commands_fts_insert AFTER INSERT ON commands BEGIN
+        INSERT INTO commands_fts(rowid, command_name, description)
+        VALUES (new.id, new.command_name, new.description);
+    END;
+    CREATE TRIGGER IF NOT EXISTS commands_fts_delete AFTER DELETE ON commands BEGIN
+        INSERT INTO commands_fts(commands_fts, rowid, command_name, description)
+        VALUES ('delete', old.id, old.command_name, old.description);
+    END;
+    CREATE TRIGGER IF NOT EXISTS commands_fts_update AFTER UPDATE ON commands BEGIN
+        INSERT INTO commands_fts(commands_fts, rowid, command_name, description)
+        VALUES ('delete', old.id, old.command_name, old.description);
+        INSERT INTO commands_fts(rowid, command_name, description)
+        VALUES (new.id, new.command_name, new.description);
+    END;
+    INSERT INTO commands_fts(commands_fts) VALUES ('rebuild');",
+    // 3: page metadata for every example
+    "ALTER TABLE commands ADD COLUMN page TEXT NOT NULL DEFAULT '';
+    ALTER TABLE commands ADD COLUMN platform TEXT NOT NULL DEFAULT 'common';
+    ALTER TABLE commands ADD COLUMN language TEXT NOT NULL DEFAULT 'en';
+    ALTER TABLE commands ADD COLUMN summary TEXT NOT NULL DEFAULT '';
+    ALTER TABLE commands ADD COLUMN url TEXT NOT NULL DEFAULT '';
+    CREATE INDEX IF NOT EXISTS commands_page ON commands(page, platform, language);",
+    // 4: source of every command, so updates only replace what they imported
+    "ALTER TABLE commands ADD COLUMN source TEXT NOT NULL DEFAULT 'tldr';
+    CREATE INDEX IF NOT EXISTS commands_source ON commands(source);",
+    // 5: every execution of a stored command, used for frecency ranking
+    "CREATE TABLE IF NOT EXISTS executions (
+        id INTEGER PRIMARY KEY,
+        command_id INTEGER NOT NULL REFERENCES commands(id) ON DELETE CASCADE,
+        executed_at INTEGER NOT NULL,
+        cwd TEXT NOT NULL,
+        exit_status INTEGER
+    );
+    CREATE INDEX IF NOT EXISTS executions_command ON executions(command_id, executed_at);",
+    // 6: starred commands and free-form tags
+    "CREATE TABLE IF NOT EXISTS favorites (
+        command_id INTEGER PRIMARY KEY REFERENCES commands(id) ON DELETE CASCADE,
+        added_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS tags (
+        command_id INTEGER NOT NULL REFERENCES commands(id) ON DELETE CASCADE,
+        tag TEXT NOT NULL,
+        PRIMARY KEY (command_id, tag)
+    );
+    CREATE INDEX IF NOT EXISTS tags_tag ON tags(tag);",
+    // 7: one row per command text, page, platform, language and source.
+    // Duplicates are merged into the oldest row: descriptions are joined, and
+    // executions, stars and tags are moved over before the other rows are deleted.
+    "CREATE TEMP TABLE duplicates AS
+        SELECT id, MIN(id) OVER (PARTITION BY command_name, page, platform, language, source) AS keep_id
+        FROM commands;
+    DELETE FROM temp.duplicates WHERE id = keep_id;
+    UPDATE commands SET description = (
+        SELECT group_concat(description, ' / ') FROM (
+            SELECT DISTINCT merged.description FROM commands AS merged
+            WHERE merged.id = commands.id
+            OR merged.id IN (SELECT id FROM temp.duplicates WHERE keep_id = commands.id)
+        )
+    )
+    WHERE id IN (SELECT keep_id FROM temp.duplicates);
+    UPDATE executions SET command_id = (
+        SELECT keep_id FROM temp.duplicates WHERE duplicates.id = executions.command_id
+    )
+    WHERE command_id IN (SELECT id FROM temp.duplicates);
+    INSERT OR IGNORE INTO favorites (command_id, added_at)
+        SELECT duplicates.keep_id, favorites.added_at
+        FROM favorites JOIN temp.duplicates ON duplicates.id = favorites.command_id;
+    INSERT OR IGNORE INTO tags (command_id, tag)
+        SELECT duplicates.keep_id, tags.tag
+        FROM tags JOIN temp.duplicates ON duplicates.id = tags.command_id;
+    DELETE FROM commands WHERE id IN (SELECT id FROM temp.duplicates);
+    DROP TABLE temp.duplicates;
+    CREATE UNIQUE INDEX IF NOT EXISTS commands_unique
+        ON commands(command_name, page, platform, language, source);",
+    // 8: tools documented on the system (man pages) and their options
+    "CREATE TABLE IF NOT EXISTS tools (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        section TEXT NOT NULL DEFAULT '',
+        summary TEXT NOT NULL DEFAULT '',
+        synopsis TEXT NOT NULL DEFAULT '',
+        source TEXT NOT NULL,
+        UNIQUE (name, source)
+    );
+    CREATE TABLE IF NOT EXISTS options (
+        id INTEGER PRIMARY KEY,
+        tool_id INTEGER NOT NULL REFERENCES tools(id) ON DELETE CASCADE,
+        flag TEXT NOT NULL,
+        description TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS options_tool ON options(tool_id);",
+    // 9: subcommands are stored with the options of their tool
+    "ALTER TABLE options ADD COLUMN kind TEXT NOT NULL DEFAULT 'option';",
+    // 10: shell commands listing the values of the placeholders of a command
+    "CREATE TABLE IF NOT EXISTS variables (
+        command_id INTEGER NOT NULL REFERENCES commands(id) ON DELETE CASCADE,
+        name TEXT NOT NULL,
+        generator TEXT NOT NULL,
+        PRIMARY KEY (command_id, name)
+    );",
+    // 11: typed placeholders with defaults, and the state of the synced cheatsheet folders
+    "ALTER TABLE variables ADD COLUMN kind TEXT NOT NULL DEFAULT 'command';
+    ALTER TABLE variables ADD COLUMN choices TEXT NOT NULL DEFAULT '';
+    ALTER TABLE variables ADD COLUMN default_value TEXT NOT NULL DEFAULT '';
+    CREATE TABLE IF NOT EXISTS synced_folders (
+        source TEXT PRIMARY KEY,
+        fingerprint TEXT NOT NULL
+    );",
+    // 12: the tldr page files the commands were imported from, to only import changed ones
+    "CREATE TABLE IF NOT EXISTS page_files (
+        path TEXT PRIMARY KEY,
+        hash TEXT NOT NULL,
+        revision TEXT NOT NULL DEFAULT '',
+        page TEXT NOT NULL,
+        platform TEXT NOT NULL,
+        language TEXT NOT NULL
+    );",
+    // 13: the commands of the shell history files, one row per command, and their full-text index
+    "CREATE TABLE IF NOT EXISTS shell_history (
+        id INTEGER PRIMARY KEY,
+        command TEXT NOT NULL UNIQUE,
+        last_run INTEGER NOT NULL DEFAULT 0,
+        run_count INTEGER NOT NULL DEFAULT 1,
+        position INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE VIRTUAL TABLE IF NOT EXISTS shell_history_fts USING fts5(
+        command,
+        content='shell_history',
+        content_rowid='id'
+    );
+    CREATE TRIGGER IF NOT EXISTS shell_history_fts_insert AFTER INSERT ON shell_history BEGIN
+        INSERT INTO shell_history_fts(rowid, command) VALUES (new.id, new.command);
+    END;
+    CREATE TRIGGER IF NOT EXISTS shell_history_fts_delete AFTER DELETE ON shell_history BEGIN
+        INSERT INTO shell_history_fts(shell_history_fts, rowid, command)
+        VALUES ('delete', old.id, old.command);
+    END;
+    CREATE TRIGGER IF NOT EXISTS shell_history_fts_update AFTER UPDATE ON shell_history BEGIN
+        INSERT INTO shell_history_fts(shell_history_fts, rowid, command)
+        VALUES ('delete', old.id, old.command);
+        INSERT INTO shell_history_fts(rowid, command) VALUES (new.id, new.command);
+    END;",
+];
+
+/// The schema version this binary creates and understands.
+pub const SCHEMA_VERSION: usize = MIGRATIONS.len();
+
+/// Reads the schema version stored in the database header.
+///
+/// # Arguments
+///
+/// * `connection` - The open database connection.
+///
+/// # Returns
+///
+/// The value of `PRAGMA user_version`.
+pub fn schema_version(connection: &Connection) -> Result<usize> {
+    let version: i64 = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    Ok(version as usize)
+}
+
+/// Upgrades the database in place to `SCHEMA_VERSION`.
+/// Every migration runs in its own transaction together with the version bump,
+/// so an interrupted upgrade leaves the database at the last fully applied version.
+///
+/// # Arguments
+///
+/// * `connection` - The open database connection.
+///
+/// # Returns
+///
+/// Result indicating success or failure
+///
+/// # Errors
+///
+/// Returns an error if the database was written by a newer version of cmd-cat,
+/// or if one of the migrations fails.
+pub fn migrate(connection: &mut Connection) -> Result<()> {
+    let version = schema_version(connection)?;
+    if version > SCHEMA_VERSION {
+        return Err(Error::SqliteFailure(
+            ffi::Error::new(ffi::SQLITE_CANTOPEN),
+            Some(format!(
+                "database schema version {version} is newer than the supported version {SCHEMA_VERSION}, please upgrade cmd-cat"
+            )),
+        ));
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(migration)?;
+        transaction.pragma_update(None, "user_version", index + 1)?;
+        transaction.commit()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The schema of a database, every table, index and trigger along with its SQL,
+    /// whitespace aside.
+    fn schema(connection: &Connection) -> Vec<(String, String, String)> {
+        let mut stmt = connection
+            .prepare("SELECT type, name, COALESCE(sql, '') FROM sqlite_master ORDER BY type, name")
+            .unwrap();
+        let rows = stmt
+            .query_map([], |row| {
+                let sql: String = row.get(2)?;
+                let sql = sql.split_whitespace().collect::<Vec<_>>().join(" ");
+                Ok((row.get(0)?, row.get(1)?, sql))
+            })
+            .unwrap();
+        rows.collect::<Result<_>>().unwrap()
+    }
+
+    /// Builds a database at the given version, as the migrations up to it left it.
+    fn database_at_version(version: usize) -> Connection {
+        let connection = Connection::open_in_memory().unwrap();
+        for migration in &MIGRATIONS[..version] {
+            connection.execute_batch(migration).unwrap();
+        }
+        connection
+            .pragma_update(None, "user_version", version)
+            .unwrap();
+        connection
+    }
+
+    /// Builds a database as created before the schema was versioned: the bare commands table,
+    /// at version 0.
+    fn baseline_database() -> Connection {
+        let connection = Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE commands (
+                    id INTEGER PRIMARY KEY,
+                    command_name TEXT NOT NULL,
+                    description TEXT NOT NULL
+                );
+                INSERT INTO commands (command_name, description)
+                VALUES ('tar xf {{source.tar}}', 'Extract an archive:');",
+            )
+            .unwrap();
+        connection
+    }
+
+    /// Checks a migrated database has the schema of a new one and holds the command
+    /// `tar xf {{source.tar}}`, found by the full-text index.
+    fn assert_migrated(connection: &Connection) {
+        let fresh = database_at_version(SCHEMA_VERSION);
+        assert_eq!(schema_version(connection).unwrap(), SCHEMA_VERSION);
+        assert_eq!(schema(connection), schema(&fresh));
+
+        let (description, source, platform): (String, String, String) = connection
+            .query_row(
+                "SELECT commands.description, commands.source, commands.platform
+                FROM commands_fts JOIN commands ON commands.id = commands_fts.rowid
+                WHERE commands_fts MATCH 'tar'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(description, "Extract an archive:");
+        assert_eq!(source, "tldr");
+        assert_eq!(platform, "common");
+    }
+
+    #[test]
+    fn migrates_baseline_database() {
+        let mut connection = baseline_database();
+        migrate(&mut connection).unwrap();
+        assert_migrated(&connection);
+    }
+
+    #[test]
+    fn migrates_every_earlier_version() {
+        for version in 1..SCHEMA_VERSION {
+            let mut connection = database_at_version(version);
+            connection
+                .execute(
+                    "INSERT INTO commands (command_name, description)
+                    VALUES ('tar xf {{source.tar}}', 'Extract an archive:')",
+                    [],
+                )
+                .unwrap();
+            if version >= 6 {
+                connection
+                    .execute(
+                        "INSERT INTO favorites (command_id, added_at) VALUES (1, 0)",
+                        [],
+                    )
+                    .unwrap();
+            }
+
+            migrate(&mut connection).unwrap();
+
+            assert_migrated(&connection);
+            let favorites: i64 = connection
+                .query_row("SELECT COUNT(*) FROM favorites", [], |row| row.get(0))
+                .unwrap();
+            assert_eq!(favorites, i64::from(version >= 6), "version {version}");
+        }
+    }
+
+    #[test]
+    fn merges_duplicate_commands_into_the_oldest() {
+        let mut connection = database_at_version(6);
+        connection
+            .execute_batch(
+                "INSERT INTO commands (id, command_name, description)
+                VALUES (1, 'tar xf {{source.tar}}', 'Extract an archive:'),
+                    (2, 'tar xf {{source.tar}}', 'Unpack a tarball:'),
+                    (3, 'tar xf {{source.tar}}', 'Extract an archive:');
+                INSERT INTO executions (command_id, executed_at, cwd) VALUES (2, 0, '/'), (3, 0, '/');
+                INSERT INTO favorites (command_id, added_at) VALUES (3, 0);
+                INSERT INTO tags (command_id, tag) VALUES (2, 'backup');",
+            )
+            .unwrap();
+
+        migrate(&mut connection).unwrap();
+
+        let (id, description): (i64, String) = connection
+            .query_row("SELECT id, description FROM commands", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(id, 1);
+        assert_eq!(description, "Extract an archive: / Unpack a tarball:");
+        let moved: (i64, i64, i64) = connection
+            .query_row(
+                "SELECT (SELECT COUNT(*) FROM executions WHERE command_id = 1),
+                    (SELECT COUNT(*) FROM favorites WHERE command_id = 1),
+                    (SELECT COUNT(*) FROM tags WHERE command_id = 1 AND tag = 'backup')",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(moved, (2, 1, 1));
+    }
+
+    #[test]
+    fn refuses_newer_database() {
+        let mut connection = database_at_version(SCHEMA_VERSION);
+        connection
+            .pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .unwrap();
+
+        let err = migrate(&mut connection).unwrap_err();
+
+        assert!(err.to_string().contains("newer than the supported version"));
+        assert_eq!(schema_version(&connection).unwrap(), SCHEMA_VERSION + 1);
+    }
+}
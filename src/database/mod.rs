@@ -1,9 +1,65 @@
-use crate::commands::Command;
-use rusqlite::{params, Connection, Result};
+use crate::commands::{
+    Command, Variable, VariableKind, ALIAS_SOURCE, ALIAS_TAG, TLDR_SOURCE, USER_SOURCE,
+};
+use rusqlite::types::Null;
+use rusqlite::{params, Connection, OptionalExtension, Result, Row};
+use std::collections::HashMap;
+use std::path::Path;
 
+pub mod exchange;
 pub mod file_parse;
+pub mod history;
+pub mod migrations;
+pub mod page_files;
+pub mod search;
+pub mod tools;
+
+/// Columns selected for every query returning whole commands, in the order `command_from_row` reads them.
+const COMMAND_COLUMNS: &str = "commands.command_name, commands.description, commands.page, \
+    commands.platform, commands.language, commands.summary, commands.url, commands.source, commands.id, \
+    EXISTS(SELECT 1 FROM favorites WHERE favorites.command_id = commands.id), \
+    (SELECT group_concat(tags.tag, ',') FROM tags WHERE tags.command_id = commands.id)";
+
+/// Optional page, platform, language and tag filters, bound as parameters 2 to 5 (NULL disables a filter),
+/// the favorites only flag bound as parameter 6, and the preferred languages bound as parameter 7
+/// (see `LANGUAGE_PREFERENCE`).
+const SEARCH_FILTERS: &str = "(?2 IS NULL OR commands.page = ?2) \
+    AND (?3 IS NULL OR commands.platform = ?3) \
+    AND (?4 IS NULL OR commands.language = ?4) \
+    AND (?5 IS NULL OR EXISTS(SELECT 1 FROM tags WHERE tags.command_id = commands.id AND tags.tag = ?5)) \
+    AND (NOT ?6 OR EXISTS(SELECT 1 FROM favorites WHERE favorites.command_id = commands.id))";
+
+/// Keeps the commands of the platforms bound as parameter 9 like `,common,linux,`.
+/// NULL keeps every platform.
+const PLATFORM_PREFERENCE: &str = "(?9 IS NULL OR instr(?9, ',' || commands.platform || ',') > 0)";
+
+/// Keeps a single language of each tldr page: the first of the preferred languages, bound as
+/// parameter 7 like `,de_DE,de,en,`, the page was translated to. NULL keeps every language.
+/// Commands of the other sources are kept whatever their language.
+const LANGUAGE_PREFERENCE: &str = "(?7 IS NULL OR commands.source != ?8 \
+    OR commands.language = (SELECT translations.language FROM commands AS translations \
+        WHERE translations.source = ?8 AND translations.page = commands.page \
+        AND translations.platform = commands.platform \
+        AND instr(?7, ',' || translations.language || ',') > 0 \
+        ORDER BY instr(?7, ',' || translations.language || ',') LIMIT 1))";
+
+/// Frecency score of `commands.id`: every execution counts 4 in the first hour, 2 in the first day,
+/// 1 in the first week and 0.5 after that, so frequently and recently run commands rank first.
+const FRECENCY_SCORE: &str = "(SELECT COALESCE(SUM(CASE \
+        WHEN executions.executed_at >= unixepoch() - 3600 THEN 4.0 \
+        WHEN executions.executed_at >= unixepoch() - 86400 THEN 2.0 \
+        WHEN executions.executed_at >= unixepoch() - 604800 THEN 1.0 \
+        ELSE 0.5 END), 0) \
+    FROM executions WHERE executions.command_id = commands.id)";
+
 pub struct SqliteDatabase {
     connection: Connection,
+    /// The languages tldr pages are searched in, most preferred first, empty for all of them
+    languages: Vec<String>,
+    /// The platforms commands are searched in, empty for all of them
+    platforms: Vec<String>,
+    /// Whether searches also return the commands of the shell history
+    include_history: bool,
 }
 
 /// The `SqliteDatabase` stores commands and their descriptions.
@@ -13,15 +69,19 @@ pub struct SqliteDatabase {
 /// ```
 /// use crate::database::SqliteDatabase;
 ///
-/// let db = SqliteDatabase::instance();
+/// let db = SqliteDatabase::open("commands.db").expect("Failed to open database");
 /// let command = Command::new("example", "This is an example command");
 /// db.insert(&command).expect("Failed to insert command");
-/// let description = db.get_command_description("example").expect("Failed to get command description");
-/// assert_eq!(description, Some("This is an example command".to_string()));
+/// let stored = db.get_command("example").expect("Failed to get command").unwrap();
+/// assert_eq!(stored.description, "This is an example command");
 /// ```
 impl SqliteDatabase {
-    /// Creates a new `SqliteDatabase` instance, with database at path "commands.db"
-    /// creates a new table with columns id, command_name and description if table does not already exists
+    /// Opens the database at the given path and brings its schema up to date,
+    /// see `migrations::migrate` for how older and newer databases are handled.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the database file, created if it does not exist.
     ///
     /// # Returns
     ///
@@ -29,21 +89,72 @@ impl SqliteDatabase {
     ///
     /// # Errors
     ///
-    /// Returns an error if the database connection fails.
-    pub fn new() -> Result<Self> {
-        let connection = Connection::open("commands.db")?;
-        connection.execute(
-            "CREATE TABLE IF NOT EXISTS commands (
-                id INTEGER PRIMARY KEY,
-                command_name TEXT NOT NULL,
-                description TEXT NOT NULL
-            )",
-            [],
-        )?;
-        Ok(SqliteDatabase { connection })
+    /// Returns an error if the database connection or a schema migration fails.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let mut connection = Connection::open(path)?;
+        connection.pragma_update(None, "foreign_keys", true)?;
+        migrations::migrate(&mut connection)?;
+        Ok(SqliteDatabase {
+            connection,
+            languages: Vec::new(),
+            platforms: Vec::new(),
+            include_history: true,
+        })
+    }
+
+    /// Sets the languages tldr pages are searched in, each page is shown in the first of them
+    /// it was translated to, and left out if it was translated to none of them.
+    ///
+    /// # Arguments
+    ///
+    /// * `languages` - The language codes, most preferred first, e.g. `["pt_BR", "pt", "en"]`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let db = SqliteDatabase::open("commands.db")?.with_languages(vec!["de".into(), "en".into()]);
+    /// ```
+    pub fn with_languages(mut self, languages: Vec<String>) -> Self {
+        self.languages = languages;
+        self
+    }
+
+    /// Sets the platforms commands are searched in when the search does not ask for one.
+    ///
+    /// # Arguments
+    ///
+    /// * `platforms` - The platform directories, e.g. `["common", "linux"]`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let db = SqliteDatabase::open("commands.db")?.with_platforms(vec!["common".into(), "linux".into()]);
+    /// ```
+    pub fn with_platforms(mut self, platforms: Vec<String>) -> Self {
+        self.platforms = platforms;
+        self
     }
 
-    /// Inserts a command into the database.
+    /// Sets whether searches also return the commands of the shell history, `is:history` still does.
+    ///
+    /// # Arguments
+    ///
+    /// * `include_history` - `false` to keep the shell history out of the results.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let db = SqliteDatabase::open("commands.db")?.with_history(false);
+    /// ```
+    pub fn with_history(mut self, include_history: bool) -> Self {
+        self.include_history = include_history;
+        self
+    }
+
+    /// Inserts a command into the database, the prepared statement is cached and reused.
+    /// A command is unique by its text, page, platform, language and source: inserting it again
+    /// updates the stored description and page metadata, and keeps the id (and so its stars,
+    /// tags and executions).
     ///
     /// # Arguments
     ///
@@ -51,16 +162,98 @@ impl SqliteDatabase {
     ///
     /// # Returns
     ///
+    /// The id of the inserted or updated command.
+    pub fn insert(&self, command: &Command) -> Result<i64> {
+        let mut stmt = self.connection.prepare_cached(
+            "INSERT INTO commands (command_name, description, page, platform, language, summary, url, source)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            ON CONFLICT (command_name, page, platform, language, source) DO UPDATE SET
+                description = excluded.description,
+                summary = excluded.summary,
+                url = excluded.url
+            RETURNING id",
+        )?;
+        stmt.query_row(
+            params![
+                command.name,
+                command.description,
+                command.page,
+                command.platform,
+                command.language,
+                command.summary,
+                command.url,
+                command.source
+            ],
+            |row| row.get(0),
+        )
+    }
+
+    /// Replaces all commands of a source with the given commands in a single transaction.
+    /// Commands that are still present keep their id, commands that are gone are deleted.
+    /// The tags and variables of the new commands are stored along with them.
+    /// Duplicates among the new commands are merged first, see `merge_duplicates`.
+    /// If anything fails, the transaction is rolled back and the old commands are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source to replace, e.g. `TLDR_SOURCE`.
+    /// * `commands` - The new commands of the source.
+    ///
+    /// # Returns
+    ///
     /// Result indicating success or failure
-    pub fn insert(&self, command: &Command) -> Result<()> {
+    pub fn replace_source(&self, source: &str, commands: &[Command]) -> Result<()> {
+        let transaction = self.connection.unchecked_transaction()?;
+        self.clear_kept_commands()?;
+        self.insert_kept_commands(commands)?;
         self.connection.execute(
-            "INSERT INTO commands (command_name, description) VALUES (?1, ?2)",
-            [command.name.as_str(), command.description.as_str()],
+            "DELETE FROM commands WHERE source = ?1 AND id NOT IN (SELECT id FROM temp.kept_commands)",
+            [source],
         )?;
+        transaction.commit()
+    }
+
+    /// Empties `temp.kept_commands`, the ids of the commands kept by a replacement.
+    ///
+    /// # Returns
+    ///
+    /// Result indicating success or failure
+    fn clear_kept_commands(&self) -> Result<()> {
+        self.connection.execute_batch(
+            "CREATE TEMP TABLE IF NOT EXISTS kept_commands (id INTEGER PRIMARY KEY);
+            DELETE FROM temp.kept_commands;",
+        )
+    }
+
+    /// Inserts commands along with their tags and variables, and adds their ids to
+    /// `temp.kept_commands`. Duplicates among the commands are merged first, see `merge_duplicates`.
+    ///
+    /// # Arguments
+    ///
+    /// * `commands` - The commands to insert.
+    ///
+    /// # Returns
+    ///
+    /// Result indicating success or failure
+    fn insert_kept_commands(&self, commands: &[Command]) -> Result<()> {
+        for command in merge_duplicates(commands) {
+            let id = self.insert(&command)?;
+            for tag in &command.tags {
+                self.add_tag(id, tag)?;
+            }
+            self.set_variables(id, &command.variables)?;
+            self.connection
+                .prepare_cached("INSERT OR IGNORE INTO temp.kept_commands (id) VALUES (?1)")?
+                .execute([id])?;
+        }
         Ok(())
     }
 
-    /// Retrieves the description of the given command from the database.
+    /// Retrieves the given command from the database, among the preferred languages and platforms
+    /// (see `with_languages` and `with_platforms`).
+    /// When several pages have the same command, personal commands come first, then the most frecent,
+    /// then the native platform (the last one of `with_platforms`), then the common platform,
+    /// then the oldest.
     ///
     /// # Arguments
     ///
@@ -68,46 +261,120 @@ impl SqliteDatabase {
     ///
     /// # Returns
     ///
-    /// The description of the command, wrapped in `Option`.
+    /// The command with its description, wrapped in `Option`.
     ///
     /// # Errors
     ///
     /// Returns an error if the query fails.
-    pub fn get_command_description(&self, command_name: &str) -> Result<Option<String>> {
-        let mut stmt = self
-            .connection
-            .prepare("SELECT description FROM commands WHERE command_name = ?1")?;
-        let result = stmt.query_row([command_name], |row| row.get(0));
+    pub fn get_command(&self, command_name: &str) -> Result<Option<Command>> {
+        let mut stmt = self.connection.prepare(&format!(
+            "SELECT {COMMAND_COLUMNS} FROM commands
+            WHERE command_name = ?1 AND {LANGUAGE_PREFERENCE} AND {PLATFORM_PREFERENCE}
+            ORDER BY commands.source = ?2 DESC, {FRECENCY_SCORE} DESC,
+                instr(?9, ',' || commands.platform || ',') DESC,
+                commands.platform = 'common' DESC, commands.id
+            LIMIT 1"
+        ))?;
+        // ?3 to ?6 are the `SEARCH_FILTERS` parameters, unused here
+        let result = stmt.query_row(
+            params![
+                command_name,
+                USER_SOURCE,
+                Null,
+                Null,
+                Null,
+                Null,
+                self.preferred_languages(),
+                TLDR_SOURCE,
+                self.preferred_platforms()
+            ],
+            command_from_row,
+        );
         match result {
-            Ok(description) => Ok(Some(description)),
+            Ok(command) => Ok(Some(command)),
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
             Err(err) => Err(err),
         }
     }
 
-    /// Get the matching commands based on the given prefix.
+    /// Gets what an imported shell alias expands to.
     ///
     /// # Arguments
     ///
-    /// * `prefix` - The prefix to search for.
+    /// * `name` - The name of the alias, e.g. `gs`.
     ///
     /// # Returns
     ///
-    /// A vector of matching command names and descriptions
+    /// The expansion of the alias, e.g. `git status`, `None` if there is no such alias.
+    pub fn get_alias(&self, name: &str) -> Result<Option<String>> {
+        self.connection
+            .prepare_cached(
+                "SELECT commands.command_name FROM commands
+                JOIN tags ON tags.command_id = commands.id AND tags.tag = ?3
+                WHERE commands.source = ?1 AND commands.page = ?2
+                ORDER BY commands.id DESC LIMIT 1",
+            )?
+            .query_row(params![ALIAS_SOURCE, name, ALIAS_TAG], |row| row.get(0))
+            .optional()
+    }
 
-    pub fn find_matching_commands(&self, prefix: &str) -> Result<Vec<(String, String)>> {
-        let mut stmt = self
-            .connection
-            .prepare("SELECT command_name, description FROM commands WHERE command_name LIKE ?1")?;
-        let mut rows = stmt.query([format!("{}%", prefix)])?;
-
-        let mut matching_commands = Vec::new();
-        while let Some(row) = rows.next()? {
-            let command_name: String = row.get(0)?;
-            let description: String = row.get(1)?;
-            matching_commands.push((command_name, description));
-        }
-        Ok(matching_commands)
+    /// Records an execution of a stored command, which ranks it higher in later searches.
+    ///
+    /// # Arguments
+    ///
+    /// * `command_id` - The id of the executed command.
+    /// * `cwd` - The working directory the command was run in.
+    /// * `exit_status` - The exit code, `None` if the command could not be run or was killed by a signal.
+    ///
+    /// # Returns
+    ///
+    /// Result indicating success or failure
+    pub fn record_execution(
+        &self,
+        command_id: i64,
+        cwd: &str,
+        exit_status: Option<i32>,
+    ) -> Result<()> {
+        self.connection.execute(
+            "INSERT INTO executions (command_id, executed_at, cwd, exit_status)
+            VALUES (?1, unixepoch(), ?2, ?3)",
+            params![command_id, cwd, exit_status],
+        )?;
+        Ok(())
+    }
+
+    /// Get the matching commands based on the given prefix, most frecent first.
+    /// Only the preferred languages and platforms are returned, see `with_languages` and `with_platforms`.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - The prefix to search for.
+    ///
+    /// # Returns
+    ///
+    /// A vector of matching commands
+    pub fn find_matching_commands(&self, prefix: &str) -> Result<Vec<Command>> {
+        let mut stmt = self.connection.prepare(&format!(
+            "SELECT {COMMAND_COLUMNS} FROM commands
+            WHERE command_name LIKE ?1 AND {LANGUAGE_PREFERENCE} AND {PLATFORM_PREFERENCE}
+            ORDER BY {FRECENCY_SCORE} DESC, commands.id"
+        ))?;
+        // ?2 to ?6 are the `SEARCH_FILTERS` parameters, unused here
+        let rows = stmt.query_map(
+            params![
+                format!("{}%", prefix),
+                Null,
+                Null,
+                Null,
+                Null,
+                Null,
+                self.preferred_languages(),
+                TLDR_SOURCE,
+                self.preferred_platforms()
+            ],
+            command_from_row,
+        )?;
+        rows.collect()
     }
 
     /// Prints all the command stored in database, Commands and Description appear in separte lines
@@ -119,77 +386,688 @@ impl SqliteDatabase {
     pub fn _view_all_commands(&self) -> Result<()> {
         let mut stmt = self
             .connection
-            .prepare("SELECT command_name, description FROM commands")?;
-        let mut rows = stmt.query([])?;
+            .prepare(&format!("SELECT {COMMAND_COLUMNS} FROM commands"))?;
+        let commands = stmt.query_map([], command_from_row)?;
 
         println!("All commands:");
-        while let Some(row) = rows.next()? {
-            let command_name: String = row.get(0)?;
-            let description: String = row.get(1)?;
-            println!("Command: {}", command_name);
-            println!("Description: {}", description);
+        for command in commands {
+            let command = command?;
+            println!("Command: {}", command.name);
+            println!("Description: {}", command.description);
+            println!(
+                "Page: {} ({}, {})",
+                command.page, command.platform, command.language
+            );
             println!("-------------------------");
         }
         Ok(())
     }
 
-    /// Get the matching commands or description matching the input string. (Input string exists in either Description or Command)
+    /// Get the matching commands or description matching the input string, best matches first.
+    /// Words are matched as prefixes of the indexed words, double quoted text is matched as a phrase
+    /// and results are ranked by frecency (see `FRECENCY_SCORE`), then by bm25 over the `commands_fts` index.
+    /// `page:`, `platform:`, `lang:`, `tag:` and `is:favorite` qualifiers restrict the results,
+    /// see `search::SearchQuery`. Unless `lang:` is given, tldr pages are only shown in the preferred
+    /// language they were translated to (see `with_languages`), and unless `platform:` is given,
+    /// only the commands of the system's platforms are returned (see `with_platforms`).
+    /// Matching commands of the shell history follow, most recent first (see `with_history`),
+    /// `is:history` returns them alone.
     ///
     /// # Arguments
     ///
-    /// * `db` - The database instance.
-    /// * `input` - The prefix to search for.
+    /// * `input` - The search query.
     ///
     /// # Returns
     ///
-    /// A vector of matching command names and descriptions
-    pub fn search_commands(&self, input: &str) -> Result<Vec<(String, String)>> {
-        let search_words: Vec<&str> = input.split_whitespace().collect();
+    /// A vector of matching commands
+    pub fn search_commands(&self, input: &str) -> Result<Vec<Command>> {
+        let query = search::SearchQuery::parse(input);
+        if query.history_only {
+            if query.excludes_history() {
+                return Ok(Vec::new());
+            }
+            return self.search_history(&query.text);
+        }
+        let sql = if query.text.is_empty() {
+            format!(
+                "SELECT {COMMAND_COLUMNS} FROM commands
+                WHERE {SEARCH_FILTERS} AND {LANGUAGE_PREFERENCE}
+                AND {PLATFORM_PREFERENCE}
+                ORDER BY {FRECENCY_SCORE} DESC, commands.id"
+            )
+        } else {
+            format!(
+                "SELECT {COMMAND_COLUMNS}
+                FROM commands_fts JOIN commands ON commands.id = commands_fts.rowid
+                WHERE commands_fts MATCH ?1 AND {SEARCH_FILTERS} AND {LANGUAGE_PREFERENCE}
+                AND {PLATFORM_PREFERENCE}
+                ORDER BY {FRECENCY_SCORE} DESC, commands_fts.rank"
+            )
+        };
 
-        let mut matching_commands = Vec::new();
-        let mut command_ids = Vec::new();
+        let preferred_languages = match query.language {
+            None => self.preferred_languages(),
+            Some(_) => None,
+        };
+        let preferred_platforms = match query.platform {
+            None if !query.all_platforms => self.preferred_platforms(),
+            _ => None,
+        };
 
-        // Step 1: Search using the first word and store the results
-        let first_word = search_words.get(0).cloned().unwrap_or("");
-        let mut stmt = self.connection.prepare("SELECT id, command_name, description FROM commands WHERE command_name LIKE ?1 OR description LIKE ?1")?;
-        let mut rows = stmt.query(params![format!("%{}%", first_word)])?;
-        while let Some(row) = rows.next()? {
-            let command_id: i32 = row.get(0)?;
-            let command_name: String = row.get(1)?;
-            let description: String = row.get(2)?;
-            matching_commands.push((command_name.clone(), description.clone()));
-            command_ids.push(command_id);
+        let mut stmt = self.connection.prepare(&sql)?;
+        let rows = stmt.query_map(
+            params![
+                query.text,
+                query.page,
+                query.platform,
+                query.language,
+                query.tag,
+                query.favorites_only,
+                preferred_languages,
+                TLDR_SOURCE,
+                preferred_platforms
+            ],
+            command_from_row,
+        )?;
+        let mut commands = rows.collect::<Result<Vec<_>>>()?;
+        if self.include_history && !query.text.is_empty() && !query.excludes_history() {
+            commands.extend(self.search_history(&query.text)?);
         }
+        Ok(commands)
+    }
 
-        // Step 2: Filter the results for each subsequent word
-        for word in search_words.iter().skip(1) {
-            let mut new_matching_commands = Vec::new();
-            let mut new_command_ids = Vec::new();
-
-            for (command_id, (command_name, description)) in
-                command_ids.iter().zip(matching_commands.iter())
-            {
-                // Check if the word is present in the command name or description
-                if command_name.contains(word) || description.contains(word) {
-                    new_matching_commands.push((command_name.clone(), description.clone()));
-                    new_command_ids.push(*command_id);
-                }
-            }
+    /// Gets the preferred languages as bound to `LANGUAGE_PREFERENCE`.
+    ///
+    /// # Returns
+    ///
+    /// The languages like `,de,en,`, `None` to keep every language.
+    fn preferred_languages(&self) -> Option<String> {
+        (!self.languages.is_empty()).then(|| format!(",{},", self.languages.join(",")))
+    }
+
+    /// Gets the preferred platforms as bound to `PLATFORM_PREFERENCE`.
+    ///
+    /// # Returns
+    ///
+    /// The platforms like `,common,linux,`, `None` to keep every platform.
+    fn preferred_platforms(&self) -> Option<String> {
+        (!self.platforms.is_empty()).then(|| format!(",{},", self.platforms.join(",")))
+    }
+
+    /// Stars or unstars a command.
+    ///
+    /// # Arguments
+    ///
+    /// * `command_id` - The id of the command.
+    /// * `favorite` - `true` to star the command, `false` to unstar it.
+    ///
+    /// # Returns
+    ///
+    /// Result indicating success or failure
+    pub fn set_favorite(&self, command_id: i64, favorite: bool) -> Result<()> {
+        if favorite {
+            self.connection.execute(
+                "INSERT OR IGNORE INTO favorites (command_id, added_at) VALUES (?1, unixepoch())",
+                [command_id],
+            )?;
+        } else {
+            self.connection
+                .execute("DELETE FROM favorites WHERE command_id = ?1", [command_id])?;
+        }
+        Ok(())
+    }
+
+    /// Attaches a tag to a command, attaching the same tag twice has no effect.
+    ///
+    /// # Arguments
+    ///
+    /// * `command_id` - The id of the command.
+    /// * `tag` - The tag, see `search::normalize_tag`.
+    ///
+    /// # Returns
+    ///
+    /// Result indicating success or failure
+    pub fn add_tag(&self, command_id: i64, tag: &str) -> Result<()> {
+        self.connection.execute(
+            "INSERT OR IGNORE INTO tags (command_id, tag) VALUES (?1, ?2)",
+            params![command_id, search::normalize_tag(tag)],
+        )?;
+        Ok(())
+    }
+
+    /// Removes a tag from a command.
+    ///
+    /// # Arguments
+    ///
+    /// * `command_id` - The id of the command.
+    /// * `tag` - The tag to remove.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the command had the tag.
+    pub fn remove_tag(&self, command_id: i64, tag: &str) -> Result<bool> {
+        let removed = self.connection.execute(
+            "DELETE FROM tags WHERE command_id = ?1 AND tag = ?2",
+            params![command_id, search::normalize_tag(tag)],
+        )?;
+        Ok(removed > 0)
+    }
 
-            matching_commands = new_matching_commands;
-            command_ids = new_command_ids;
+    /// Replaces the variables of a command, the prepared statements are cached and reused.
+    ///
+    /// # Arguments
+    ///
+    /// * `command_id` - The id of the command.
+    /// * `variables` - The new variables of the command.
+    ///
+    /// # Returns
+    ///
+    /// Result indicating success or failure
+    fn set_variables(&self, command_id: i64, variables: &[Variable]) -> Result<()> {
+        self.connection
+            .prepare_cached("DELETE FROM variables WHERE command_id = ?1")?
+            .execute([command_id])?;
+        let mut stmt = self.connection.prepare_cached(
+            "INSERT OR REPLACE INTO variables (command_id, name, kind, generator, choices, default_value)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for variable in variables {
+            stmt.execute(params![
+                command_id,
+                variable.name,
+                variable.kind.name(),
+                variable.generator,
+                variable.choices.join("\n"),
+                variable.default
+            ])?;
         }
+        Ok(())
+    }
+
+    /// Get the variables of a command.
+    ///
+    /// # Arguments
+    ///
+    /// * `command_id` - The id of the command.
+    ///
+    /// # Returns
+    ///
+    /// The variables of the command, by placeholder name.
+    pub fn get_variables(&self, command_id: i64) -> Result<Vec<Variable>> {
+        let mut stmt = self.connection.prepare(
+            "SELECT name, kind, generator, choices, default_value FROM variables
+            WHERE command_id = ?1 ORDER BY name",
+        )?;
+        let rows = stmt.query_map([command_id], |row| {
+            Ok(Variable {
+                name: row.get(0)?,
+                kind: VariableKind::from_name(row.get_ref(1)?.as_str()?).unwrap_or_default(),
+                generator: row.get(2)?,
+                choices: row
+                    .get_ref(3)?
+                    .as_str()?
+                    .lines()
+                    .map(String::from)
+                    .collect(),
+                default: row.get(4)?,
+            })
+        })?;
+        rows.collect()
+    }
 
-        Ok(matching_commands)
+    /// Gets the fingerprint of a synced folder, as it was when its commands were last loaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source of the commands loaded from the folder.
+    ///
+    /// # Returns
+    ///
+    /// The fingerprint, `None` if the folder was never loaded.
+    pub fn get_folder_fingerprint(&self, source: &str) -> Result<Option<String>> {
+        self.connection
+            .query_row(
+                "SELECT fingerprint FROM synced_folders WHERE source = ?1",
+                [source],
+                |row| row.get(0),
+            )
+            .optional()
     }
 
-    /// Clears all commands from the database.
+    /// Records the fingerprint of a synced folder once its commands are loaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source of the commands loaded from the folder.
+    /// * `fingerprint` - The fingerprint of the folder.
     ///
     /// # Returns
     ///
     /// Result indicating success or failure
-    pub fn clear(&self) -> Result<()> {
-        self.connection.execute("DELETE FROM commands", [])?;
+    pub fn set_folder_fingerprint(&self, source: &str, fingerprint: &str) -> Result<()> {
+        self.connection.execute(
+            "INSERT INTO synced_folders (source, fingerprint) VALUES (?1, ?2)
+            ON CONFLICT (source) DO UPDATE SET fingerprint = excluded.fingerprint",
+            params![source, fingerprint],
+        )?;
         Ok(())
     }
+
+    /// Get all the commands of the given source, in insertion order.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source of the commands, e.g. `USER_SOURCE`.
+    ///
+    /// # Returns
+    ///
+    /// A vector of the commands of that source
+    pub fn commands_by_source(&self, source: &str) -> Result<Vec<Command>> {
+        let mut stmt = self.connection.prepare(&format!(
+            "SELECT {COMMAND_COLUMNS} FROM commands WHERE source = ?1 ORDER BY id"
+        ))?;
+        let rows = stmt.query_map([source], command_from_row)?;
+        rows.collect()
+    }
+
+    /// Replaces the command and description of a command added by the user.
+    /// Commands of any other source are read-only.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command with its `id` and the new values.
+    ///
+    /// # Returns
+    ///
+    /// `true` if a user command was updated, `false` if there is no user command with that id.
+    pub fn update_user_command(&self, command: &Command) -> Result<bool> {
+        let updated = self.connection.execute(
+            "UPDATE commands SET command_name = ?1, description = ?2 WHERE id = ?3 AND source = ?4",
+            params![command.name, command.description, command.id, USER_SOURCE],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Removes a command added by the user. Commands of any other source are read-only.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the command.
+    ///
+    /// # Returns
+    ///
+    /// `true` if a user command was removed, `false` if there is no user command with that id.
+    pub fn remove_user_command(&self, id: i64) -> Result<bool> {
+        let removed = self.connection.execute(
+            "DELETE FROM commands WHERE id = ?1 AND source = ?2",
+            params![id, USER_SOURCE],
+        )?;
+        Ok(removed > 0)
+    }
+}
+
+/// Maps a row selected with `COMMAND_COLUMNS` into a `Command`.
+///
+/// # Arguments
+///
+/// * `row` - The result row.
+///
+/// # Returns
+///
+/// The command stored in the row.
+fn command_from_row(row: &Row) -> Result<Command> {
+    Ok(Command {
+        name: row.get(0)?,
+        description: row.get(1)?,
+        page: row.get(2)?,
+        platform: row.get(3)?,
+        language: row.get(4)?,
+        summary: row.get(5)?,
+        url: row.get(6)?,
+        source: row.get(7)?,
+        id: row.get(8)?,
+        favorite: row.get(9)?,
+        tags: row
+            .get::<_, Option<String>>(10)?
+            .map(|tags| tags.split(',').map(String::from).collect())
+            .unwrap_or_default(),
+        variables: Vec::new(),
+    })
+}
+
+/// Merges commands sharing text, page, platform, language and source into one,
+/// joining their distinct descriptions instead of keeping only one of them.
+///
+/// # Arguments
+///
+/// * `commands` - The commands to merge.
+///
+/// # Returns
+///
+/// The merged commands, in order of first appearance.
+fn merge_duplicates(commands: &[Command]) -> Vec<Command> {
+    let mut merged: Vec<Command> = Vec::with_capacity(commands.len());
+    let mut positions = HashMap::new();
+
+    for command in commands {
+        let key = (
+            &command.name,
+            &command.page,
+            &command.platform,
+            &command.language,
+            &command.source,
+        );
+        match positions.get(&key) {
+            Some(&position) => {
+                let existing: &mut Command = &mut merged[position];
+                if !existing
+                    .description
+                    .split(" / ")
+                    .any(|description| description == command.description)
+                {
+                    existing.description =
+                        format!("{} / {}", existing.description, command.description);
+                }
+            }
+            None => {
+                positions.insert(key, merged.len());
+                merged.push(command.clone());
+            }
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::Page;
+
+    /// Creates a tldr command of the given page.
+    fn tldr_command(name: &str, page: &str, platform: &str, language: &str) -> Command {
+        Command::new(name, "Do something:").with_page(&Page::new(page, platform, language))
+    }
+
+    #[test]
+    fn lookups_show_pages_in_the_preferred_language() {
+        let db = SqliteDatabase::open(":memory:")
+            .unwrap()
+            .with_languages(vec!["de".to_owned(), "en".to_owned()]);
+        let commands = [
+            tldr_command("foo --bar", "foo", "common", "en"),
+            tldr_command("foo --bar", "foo", "common", "de"),
+            tldr_command("foobaz", "foobaz", "common", "en"),
+        ];
+        db.replace_source(TLDR_SOURCE, &commands).unwrap();
+
+        let matches = db.find_matching_commands("foo").unwrap();
+        let languages: Vec<(&str, &str)> = matches
+            .iter()
+            .map(|command| (command.page.as_str(), command.language.as_str()))
+            .collect();
+        assert_eq!(languages, [("foo", "de"), ("foobaz", "en")]);
+
+        let command = db.get_command("foo --bar").unwrap().unwrap();
+        assert_eq!(command.language, "de");
+    }
+
+    #[test]
+    fn lookups_prefer_the_native_platform() {
+        let db = SqliteDatabase::open(":memory:")
+            .unwrap()
+            .with_platforms(vec!["common".to_owned(), "linux".to_owned()]);
+        let commands = [
+            tldr_command("top", "top", "common", "en"),
+            tldr_command("top", "top", "linux", "en"),
+            tldr_command("top -o cpu", "top", "osx", "en"),
+        ];
+        db.replace_source(TLDR_SOURCE, &commands).unwrap();
+
+        let platforms: Vec<String> = db
+            .find_matching_commands("top")
+            .unwrap()
+            .into_iter()
+            .map(|command| command.platform)
+            .collect();
+        assert_eq!(platforms, ["common", "linux"]);
+
+        let command = db.get_command("top").unwrap().unwrap();
+        assert_eq!(command.platform, "linux");
+        assert!(db.get_command("top -o cpu").unwrap().is_none());
+    }
+
+    fn names(commands: &[Command]) -> Vec<&str> {
+        commands
+            .iter()
+            .map(|command| command.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn recent_and_frequent_commands_rank_first() {
+        let db = SqliteDatabase::open(":memory:").unwrap();
+        let mut ids = Vec::new();
+        for name in ["git status", "git stash", "git stage"] {
+            ids.push(db.insert(&Command::new(name, "Git:")).unwrap());
+        }
+        let (stash, stage) = (ids[1], ids[2]);
+        for _ in 0..3 {
+            db.record_execution(stash, "/repo", Some(0)).unwrap();
+        }
+        // runs older than a week count 0.5 each, a run within the hour counts 4
+        db.connection
+            .execute(
+                "UPDATE executions SET executed_at = unixepoch() - 30 * 86400",
+                [],
+            )
+            .unwrap();
+        db.record_execution(stage, "/repo", Some(0)).unwrap();
+
+        let order = ["git stage", "git stash", "git status"];
+        assert_eq!(names(&db.find_matching_commands("git st").unwrap()), order);
+        assert_eq!(names(&db.search_commands("git").unwrap()), order);
+
+        db.record_execution(stash, "/repo", None).unwrap();
+        let order = ["git stash", "git stage", "git status"];
+        assert_eq!(names(&db.find_matching_commands("git st").unwrap()), order);
+        assert_eq!(names(&db.search_commands("git").unwrap()), order);
+    }
+
+    #[test]
+    fn favorites_can_be_toggled() {
+        let db = SqliteDatabase::open(":memory:").unwrap();
+        let id = db
+            .insert(&Command::new("ls -la", "List all files:"))
+            .unwrap();
+        db.insert(&Command::new("ls -lh", "List with sizes:"))
+            .unwrap();
+
+        db.set_favorite(id, true).unwrap();
+        db.set_favorite(id, true).unwrap();
+        let favorites = db.search_commands("is:favorite").unwrap();
+        assert_eq!(names(&favorites), ["ls -la"]);
+        assert!(favorites[0].favorite);
+        assert!(!db.search_commands("ls -lh").unwrap()[0].favorite);
+
+        db.set_favorite(id, false).unwrap();
+        assert!(db.search_commands("is:favorite").unwrap().is_empty());
+        assert!(!db.find_matching_commands("ls -la").unwrap()[0].favorite);
+    }
+
+    #[test]
+    fn tags_filter_the_search() {
+        let db = SqliteDatabase::open(":memory:").unwrap();
+        let apply = db
+            .insert(&Command::new("kubectl apply -f app.yaml", "Apply:"))
+            .unwrap();
+        let get = db
+            .insert(&Command::new("kubectl get pods", "List pods:"))
+            .unwrap();
+        db.add_tag(apply, "Deploy").unwrap();
+        db.add_tag(apply, "#deploy").unwrap();
+        db.add_tag(get, "k8s").unwrap();
+
+        let tagged = db.search_commands("tag:deploy").unwrap();
+        assert_eq!(names(&tagged), ["kubectl apply -f app.yaml"]);
+        assert_eq!(tagged[0].tags, ["deploy"]);
+        assert_eq!(
+            names(&db.search_commands("kubectl tag:k8s").unwrap()),
+            ["kubectl get pods"]
+        );
+        assert!(db.search_commands("pods tag:deploy").unwrap().is_empty());
+
+        assert!(db.remove_tag(apply, "DEPLOY").unwrap());
+        assert!(!db.remove_tag(apply, "deploy").unwrap());
+        assert!(db.search_commands("tag:deploy").unwrap().is_empty());
+    }
+
+    #[test]
+    fn search_ranks_by_bm25() {
+        let db = SqliteDatabase::open(":memory:").unwrap();
+        for (name, description) in [
+            ("gzip file", "Compress a file, to be packed by tar later:"),
+            ("ls -la", "List all files:"),
+            ("tar cf target.tar dir", "Create a tar archive:"),
+        ] {
+            db.insert(&Command::new(name, description)).unwrap();
+        }
+
+        let commands = db.search_commands("tar").unwrap();
+        assert_eq!(names(&commands), ["tar cf target.tar dir", "gzip file"]);
+        let commands = db.search_commands("lis fil").unwrap();
+        assert_eq!(names(&commands), ["ls -la"]);
+        let commands = db.search_commands(r#""tar archive""#).unwrap();
+        assert_eq!(names(&commands), ["tar cf target.tar dir"]);
+    }
+
+    #[test]
+    fn search_accepts_fts_syntax_and_empty_queries() {
+        let db = SqliteDatabase::open(":memory:").unwrap();
+        let id = db.insert(&Command::new("make deploy", "Deploy:")).unwrap();
+        db.insert(&Command::new("make test", "Test:")).unwrap();
+        db.add_tag(id, "deploy").unwrap();
+
+        for input in [
+            "AND",
+            "NEAR(make",
+            "-make",
+            "make*",
+            r#"make" OR "test"#,
+            "^",
+        ] {
+            assert!(db.search_commands(input).is_ok(), "{input}");
+        }
+        let commands = db.search_commands("tag:deploy").unwrap();
+        assert_eq!(names(&commands), ["make deploy"]);
+        let commands = db.search_commands("").unwrap();
+        assert_eq!(names(&commands), ["make deploy", "make test"]);
+    }
+
+    #[test]
+    fn failed_replacement_keeps_the_old_commands() {
+        let db = SqliteDatabase::open(":memory:").unwrap();
+        let old = [
+            Command::new("tar xf {{source.tar}}", "Extract an archive:"),
+            Command::new("ls -a", "List all files:"),
+        ];
+        db.replace_source(TLDR_SOURCE, &old).unwrap();
+        db.connection
+            .execute_batch(
+                "CREATE TEMP TRIGGER fail_insert BEFORE INSERT ON commands
+                WHEN new.command_name = 'boom'
+                BEGIN SELECT RAISE(ABORT, 'insert failed'); END;",
+            )
+            .unwrap();
+
+        let new = [
+            Command::new("cp {{source}} {{target}}", "Copy a file:"),
+            Command::new("boom", "Fail halfway:"),
+            Command::new("mv {{source}} {{target}}", "Move a file:"),
+        ];
+        assert!(db.replace_source(TLDR_SOURCE, &new).is_err());
+
+        let stored = db.commands_by_source(TLDR_SOURCE).unwrap();
+        assert_eq!(names(&stored), ["tar xf {{source.tar}}", "ls -a"]);
+        assert!(db.search_commands("cp").unwrap().is_empty());
+
+        // the failed transaction was rolled back, the next replacement goes through
+        db.replace_source(TLDR_SOURCE, &new[..1]).unwrap();
+        let stored = db.commands_by_source(TLDR_SOURCE).unwrap();
+        assert_eq!(names(&stored), ["cp {{source}} {{target}}"]);
+    }
+
+    #[test]
+    fn inserting_again_updates_the_command_and_keeps_its_id() {
+        let db = SqliteDatabase::open(":memory:").unwrap();
+        let mut command = tldr_command("tar xf {{source.tar}}", "tar", "common", "en");
+        let id = db.insert(&command).unwrap();
+        db.set_favorite(id, true).unwrap();
+
+        command.description = "Extract an archive in place:".to_owned();
+        command.summary = "Archiving utility.".to_owned();
+        command.url = "https://www.gnu.org/software/tar".to_owned();
+        assert_eq!(db.insert(&command).unwrap(), id);
+
+        let stored = db.commands_by_source(TLDR_SOURCE).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].description, "Extract an archive in place:");
+        assert_eq!(stored[0].summary, "Archiving utility.");
+        assert_eq!(stored[0].url, "https://www.gnu.org/software/tar");
+        assert!(stored[0].favorite);
+
+        let other_page = tldr_command("tar xf {{source.tar}}", "tar", "linux", "en");
+        assert_ne!(db.insert(&other_page).unwrap(), id);
+        let user_command = command.clone().with_source(USER_SOURCE);
+        assert_ne!(db.insert(&user_command).unwrap(), id);
+    }
+
+    #[test]
+    fn merge_duplicates_joins_descriptions() {
+        let with_description = |mut command: Command, description: &str| {
+            command.description = description.to_owned();
+            command
+        };
+        let tar = tldr_command("tar xf {{source.tar}}", "tar", "common", "en");
+        let commands = [
+            with_description(tar.clone(), "Extract an archive:"),
+            tldr_command("ls", "ls", "common", "en"),
+            with_description(tar.clone(), "Unpack a tarball:"),
+            with_description(tar.clone(), "Extract an archive:"),
+            with_description(tar.clone(), "Unpack a tarball:").with_source(USER_SOURCE),
+        ];
+
+        let merged = merge_duplicates(&commands);
+
+        let descriptions: Vec<(&str, &str, &str)> = merged
+            .iter()
+            .map(|command| {
+                (
+                    command.name.as_str(),
+                    command.source.as_str(),
+                    command.description.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            descriptions,
+            [
+                (
+                    "tar xf {{source.tar}}",
+                    TLDR_SOURCE,
+                    "Extract an archive: / Unpack a tarball:"
+                ),
+                ("ls", TLDR_SOURCE, "Do something:"),
+                ("tar xf {{source.tar}}", USER_SOURCE, "Unpack a tarball:"),
+            ]
+        );
+
+        let db = SqliteDatabase::open(":memory:").unwrap();
+        db.replace_source(TLDR_SOURCE, &commands[..4]).unwrap();
+        let stored = db.commands_by_source(TLDR_SOURCE).unwrap();
+        assert_eq!(names(&stored), ["tar xf {{source.tar}}", "ls"]);
+        assert_eq!(
+            stored[0].description,
+            "Extract an archive: / Unpack a tarball:"
+        );
+    }
 }
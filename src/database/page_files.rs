@@ -0,0 +1,194 @@
+use crate::commands::{Command, TLDR_SOURCE};
+use crate::database::SqliteDatabase;
+use rusqlite::{params, OptionalExtension, Result, Row};
+
+/// A tldr page file as it was when its commands were last imported.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PageFile {
+    /// The path of the file in the checkout, e.g. `pages.de/linux/tar.md`
+    pub path: String,
+    /// The hash of the content of the file
+    pub hash: String,
+    /// The revision of the checkout the file was read from, empty if unknown
+    pub revision: String,
+    /// The page, platform and language of the commands of the file
+    pub page: String,
+    pub platform: String,
+    pub language: String,
+}
+
+/// A page file to import, along with its commands.
+#[derive(Debug)]
+pub struct PageImport {
+    pub file: PageFile,
+    pub commands: Vec<Command>,
+}
+
+impl SqliteDatabase {
+    /// Get the page files the tldr commands were imported from.
+    ///
+    /// # Returns
+    ///
+    /// The page files, sorted by path.
+    pub fn get_page_files(&self) -> Result<Vec<PageFile>> {
+        let mut stmt = self.connection.prepare(
+            "SELECT path, hash, revision, page, platform, language FROM page_files ORDER BY path",
+        )?;
+        let rows = stmt.query_map([], page_file_from_row)?;
+        rows.collect()
+    }
+
+    /// Imports the commands of added and changed page files, and deletes the commands
+    /// of removed ones, in a single transaction. The commands of the other pages are left as they are.
+    /// Commands still present in a changed page, or in a renamed file, keep their id.
+    ///
+    /// # Arguments
+    ///
+    /// * `imports` - The added and changed page files, along with their commands.
+    /// * `removed` - The page files that are gone.
+    ///
+    /// # Returns
+    ///
+    /// Result indicating success or failure
+    pub fn update_page_files(&self, imports: &[PageImport], removed: &[PageFile]) -> Result<()> {
+        let transaction = self.connection.unchecked_transaction()?;
+        // every imported command is kept, a renamed file can still hold the page of a removed one
+        self.clear_kept_commands()?;
+        for import in imports {
+            self.insert_kept_commands(&import.commands)?;
+        }
+        for import in imports {
+            // the title of a changed file may have changed, the commands of the old page go as well
+            for file in self
+                .get_page_file(&import.file.path)?
+                .iter()
+                .chain([&import.file])
+            {
+                self.delete_page_commands(file)?;
+            }
+            self.set_page_file(&import.file)?;
+        }
+        for file in removed {
+            self.delete_page_commands(file)?;
+            self.connection
+                .execute("DELETE FROM page_files WHERE path = ?1", [&file.path])?;
+        }
+        transaction.commit()
+    }
+
+    /// Replaces all tldr commands, and the page files they were imported from,
+    /// in a single transaction. Used when no page file was recorded yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `imports` - All of the page files, along with their commands.
+    ///
+    /// # Returns
+    ///
+    /// Result indicating success or failure
+    pub fn replace_page_files(&self, imports: &[PageImport]) -> Result<()> {
+        let transaction = self.connection.unchecked_transaction()?;
+        self.clear_kept_commands()?;
+        for import in imports {
+            self.insert_kept_commands(&import.commands)?;
+        }
+        self.connection.execute(
+            "DELETE FROM commands WHERE source = ?1 AND id NOT IN (SELECT id FROM temp.kept_commands)",
+            [TLDR_SOURCE],
+        )?;
+        self.connection.execute("DELETE FROM page_files", [])?;
+        for import in imports {
+            self.set_page_file(&import.file)?;
+        }
+        transaction.commit()
+    }
+
+    /// Get a page file by path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file in the checkout.
+    ///
+    /// # Returns
+    ///
+    /// The page file, `None` if it was never imported.
+    fn get_page_file(&self, path: &str) -> Result<Option<PageFile>> {
+        self.connection
+            .prepare_cached(
+                "SELECT path, hash, revision, page, platform, language FROM page_files WHERE path = ?1",
+            )?
+            .query_row([path], page_file_from_row)
+            .optional()
+    }
+
+    /// Records a page file, replacing the record of the same path.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The page file.
+    ///
+    /// # Returns
+    ///
+    /// Result indicating success or failure
+    fn set_page_file(&self, file: &PageFile) -> Result<()> {
+        self.connection
+            .prepare_cached(
+                "INSERT OR REPLACE INTO page_files (path, hash, revision, page, platform, language)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?
+            .execute(params![
+                file.path,
+                file.hash,
+                file.revision,
+                file.page,
+                file.platform,
+                file.language
+            ])?;
+        Ok(())
+    }
+
+    /// Deletes the tldr commands of the page of a page file, except those in `temp.kept_commands`.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The page file.
+    ///
+    /// # Returns
+    ///
+    /// Result indicating success or failure
+    fn delete_page_commands(&self, file: &PageFile) -> Result<()> {
+        self.connection
+            .prepare_cached(
+                "DELETE FROM commands
+                WHERE source = ?1 AND page = ?2 AND platform = ?3 AND language = ?4
+                AND id NOT IN (SELECT id FROM temp.kept_commands)",
+            )?
+            .execute(params![
+                TLDR_SOURCE,
+                file.page,
+                file.platform,
+                file.language
+            ])?;
+        Ok(())
+    }
+}
+
+/// Reads a page file from a result row of `path, hash, revision, page, platform, language`.
+///
+/// # Arguments
+///
+/// * `row` - The result row.
+///
+/// # Returns
+///
+/// The page file stored in the row.
+fn page_file_from_row(row: &Row) -> Result<PageFile> {
+    Ok(PageFile {
+        path: row.get(0)?,
+        hash: row.get(1)?,
+        revision: row.get(2)?,
+        page: row.get(3)?,
+        platform: row.get(4)?,
+        language: row.get(5)?,
+    })
+}
@@ -0,0 +1,244 @@
+/// A parsed search input: the FTS5 match expression along with the metadata filters.
+#[derive(Debug, Default, PartialEq)]
+pub struct SearchQuery {
+    /// The FTS5 query string, empty if the input does not contain anything searchable
+    pub text: String,
+    /// Only return examples of this page (`page:tar`)
+    pub page: Option<String>,
+    /// Only return examples of this platform (`platform:linux`)
+    pub platform: Option<String>,
+    /// Return examples of every platform (`platform:all`), not only those of the system
+    pub all_platforms: bool,
+    /// Only return examples in this language (`lang:de`)
+    pub language: Option<String>,
+    /// Only return commands with this tag (`tag:deploy`)
+    pub tag: Option<String>,
+    /// Only return starred commands (`is:favorite`)
+    pub favorites_only: bool,
+    /// Only return commands of the shell history (`is:history`)
+    pub history_only: bool,
+}
+
+impl SearchQuery {
+    /// Checks if the query filters on metadata the shell history does not have,
+    /// such as a page or a tag.
+    ///
+    /// # Returns
+    ///
+    /// `true` if no command of the shell history can match.
+    pub fn excludes_history(&self) -> bool {
+        self.page.is_some()
+            || self.platform.is_some()
+            || self.language.is_some()
+            || self.tag.is_some()
+            || self.favorites_only
+    }
+
+    /// Parses the user's search input.
+    /// Plain words are turned into prefix queries (`compress` matches `compressed`),
+    /// text wrapped in double quotes is kept together as a phrase query, and a trailing `*` on a
+    /// phrase makes the phrase itself a prefix query. All the terms have to match.
+    /// Every term is quoted, so FTS5 operators or punctuation in the input never cause a syntax error.
+    /// Unquoted `page:`, `platform:`, `lang:`, `tag:`, `is:favorite` and `is:history` words are taken
+    /// as filters instead of search terms, `platform:all` lifts the platform filter.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The search input as typed by the user.
+    ///
+    /// # Returns
+    ///
+    /// The parsed `SearchQuery`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let query = SearchQuery::parse(r#"compress "whole directory" platform:linux"#);
+    /// assert_eq!(query.text, r#""compress"* "whole directory""#);
+    /// assert_eq!(query.platform, Some("linux".to_string()));
+    /// ```
+    pub fn parse(input: &str) -> Self {
+        let mut query = SearchQuery::default();
+        let mut terms = Vec::new();
+        let mut chars = input.chars().peekable();
+
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+            } else if c == '"' {
+                chars.next();
+                let phrase: String = chars.by_ref().take_while(|&c| c != '"').collect();
+                let is_prefix = chars.next_if_eq(&'*').is_some();
+                push_term(&mut terms, &phrase, is_prefix);
+            } else {
+                let mut word = String::new();
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != '"') {
+                    word.push(c);
+                }
+                if !query.apply_qualifier(&word) {
+                    push_term(&mut terms, word.trim_end_matches('*'), true);
+                }
+            }
+        }
+
+        query.text = terms.join(" ");
+        query
+    }
+
+    /// Applies a `key:value` qualifier to the query.
+    ///
+    /// # Arguments
+    ///
+    /// * `word` - A single unquoted word of the input.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the word was a known qualifier, `false` if it should be searched for.
+    fn apply_qualifier(&mut self, word: &str) -> bool {
+        let Some((key, value)) = word.split_once(':') else {
+            return false;
+        };
+        if value.is_empty() {
+            return false;
+        }
+        let filter = match (key, value) {
+            ("is", "favorite" | "fav" | "starred") => {
+                self.favorites_only = true;
+                return true;
+            }
+            ("is", "history") => {
+                self.history_only = true;
+                return true;
+            }
+            ("platform", "all") => {
+                self.all_platforms = true;
+                return true;
+            }
+            ("tag", tag) => {
+                self.tag = Some(normalize_tag(tag));
+                return true;
+            }
+            ("page", _) => &mut self.page,
+            ("platform", _) => &mut self.platform,
+            ("lang", _) => &mut self.language,
+            _ => return false,
+        };
+        *filter = Some(value.to_string());
+        true
+    }
+}
+
+/// Quotes a single term for FTS5, dropping terms without any indexable character.
+///
+/// # Arguments
+///
+/// * `terms` - The terms collected so far.
+/// * `text` - The word or phrase to add.
+/// * `is_prefix` - Whether the term should match as a prefix.
+fn push_term(terms: &mut Vec<String>, text: &str, is_prefix: bool) {
+    if !text.chars().any(char::is_alphanumeric) {
+        return;
+    }
+    let quoted = format!("\"{}\"", text.replace('"', "\"\""));
+    terms.push(if is_prefix { quoted + "*" } else { quoted });
+}
+
+/// Normalizes a tag so `Deploy`, `deploy ` and `#deploy` are the same tag.
+///
+/// # Arguments
+///
+/// * `tag` - The tag as typed by the user.
+///
+/// # Returns
+///
+/// The lowercase tag without leading `#`, with whitespace and commas replaced by `-`.
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim()
+        .trim_start_matches('#')
+        .to_lowercase()
+        .replace(|c: char| c.is_whitespace() || c == ',', "-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_are_prefix_terms() {
+        assert_eq!(
+            SearchQuery::parse("  compress dir*  ").text,
+            r#""compress"* "dir"*"#
+        );
+    }
+
+    #[test]
+    fn quoted_text_is_a_phrase() {
+        assert_eq!(
+            SearchQuery::parse(r#"tar "whole directory" "new arch"*"#).text,
+            r#""tar"* "whole directory" "new arch"*"#
+        );
+        // an unclosed quote runs to the end of the input
+        assert_eq!(
+            SearchQuery::parse(r#"tar "whole directory"#).text,
+            r#""tar"* "whole directory""#
+        );
+    }
+
+    #[test]
+    fn fts_operators_are_searched_as_words() {
+        assert_eq!(
+            SearchQuery::parse("cats AND dogs NEAR(a b) -v OR NOT").text,
+            r#""cats"* "AND"* "dogs"* "NEAR(a"* "b)"* "-v"* "OR"* "NOT"*"#
+        );
+        assert_eq!(SearchQuery::parse(r#"* -- "" "*" ^"#).text, "");
+        assert_eq!(SearchQuery::parse(r#"a"b"#).text, r#""a"* "b""#);
+    }
+
+    #[test]
+    fn quotes_inside_terms_are_escaped() {
+        let mut terms = Vec::new();
+        push_term(&mut terms, r#"say "hi""#, false);
+        assert_eq!(terms, [r#""say ""hi""""#]);
+    }
+
+    #[test]
+    fn qualifiers_are_filters() {
+        let query =
+            SearchQuery::parse("archive page:tar platform:linux lang:de tag:#Deploy is:favorite");
+        assert_eq!(
+            query,
+            SearchQuery {
+                text: r#""archive"*"#.to_owned(),
+                page: Some("tar".to_owned()),
+                platform: Some("linux".to_owned()),
+                language: Some("de".to_owned()),
+                tag: Some("deploy".to_owned()),
+                favorites_only: true,
+                ..Default::default()
+            }
+        );
+        assert!(query.excludes_history());
+
+        let query = SearchQuery::parse("git platform:all is:history");
+        assert!(query.all_platforms);
+        assert!(query.history_only);
+        assert_eq!(query.platform, None);
+        assert!(!query.excludes_history());
+    }
+
+    #[test]
+    fn unknown_and_quoted_qualifiers_are_searched() {
+        assert_eq!(
+            SearchQuery::parse(r#"user:me page: "page:tar""#),
+            SearchQuery {
+                text: r#""user:me"* "page:"* "page:tar""#.to_owned(),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn tags_are_normalized() {
+        assert_eq!(normalize_tag(" #Deploy Prod,EU "), "deploy-prod-eu");
+    }
+}
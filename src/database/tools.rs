@@ -0,0 +1,191 @@
+use crate::commands::{Tool, ToolOption};
+use crate::database::SqliteDatabase;
+use rusqlite::{params, OptionalExtension, Result};
+
+/// `options.kind` of the flags of a tool.
+const OPTION_KIND: &str = "option";
+
+/// `options.kind` of the subcommands of a tool.
+const SUBCOMMAND_KIND: &str = "subcommand";
+
+impl SqliteDatabase {
+    /// Replaces all tools of a source with the given tools in a single transaction,
+    /// the options of the old tools are deleted with them.
+    /// When several tools have the same name, the first one is kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source to replace, e.g. `MAN_SOURCE`.
+    /// * `tools` - The new tools of the source, along with their options.
+    ///
+    /// # Returns
+    ///
+    /// Result indicating success or failure
+    pub fn replace_tools(&self, source: &str, tools: &[Tool]) -> Result<()> {
+        let transaction = self.connection.unchecked_transaction()?;
+        self.connection
+            .execute("DELETE FROM tools WHERE source = ?1", [source])?;
+        for tool in tools {
+            self.insert_tool(tool)?;
+        }
+        transaction.commit()
+    }
+
+    /// Replaces a tool of the source of `tool` along with its options, in a single transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `tool` - The new version of the tool.
+    ///
+    /// # Returns
+    ///
+    /// Result indicating success or failure
+    pub fn replace_tool(&self, tool: &Tool) -> Result<()> {
+        let transaction = self.connection.unchecked_transaction()?;
+        self.connection.execute(
+            "DELETE FROM tools WHERE name = ?1 AND source = ?2",
+            params![tool.name, tool.source],
+        )?;
+        self.insert_tool(tool)?;
+        transaction.commit()
+    }
+
+    /// Inserts a tool along with its options and subcommands,
+    /// unless the source already has a tool with this name.
+    ///
+    /// # Arguments
+    ///
+    /// * `tool` - The tool to insert.
+    ///
+    /// # Returns
+    ///
+    /// Result indicating success or failure
+    fn insert_tool(&self, tool: &Tool) -> Result<()> {
+        let tool_id: Option<i64> = self
+            .connection
+            .prepare_cached(
+                "INSERT INTO tools (name, section, summary, synopsis, source)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                ON CONFLICT (name, source) DO NOTHING
+                RETURNING id",
+            )?
+            .query_row(
+                params![
+                    tool.name,
+                    tool.section,
+                    tool.summary,
+                    tool.synopsis,
+                    tool.source
+                ],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let Some(tool_id) = tool_id else {
+            return Ok(());
+        };
+
+        let mut stmt = self.connection.prepare_cached(
+            "INSERT INTO options (tool_id, flag, description, kind) VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for option in &tool.options {
+            stmt.execute(params![
+                tool_id,
+                option.flag,
+                option.description,
+                OPTION_KIND
+            ])?;
+        }
+        for subcommand in &tool.subcommands {
+            stmt.execute(params![
+                tool_id,
+                subcommand.flag,
+                subcommand.description,
+                SUBCOMMAND_KIND
+            ])?;
+        }
+        Ok(())
+    }
+
+    /// Retrieves a tool along with its options and subcommands.
+    /// When several sources document the tool, the one with the most options is used.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the tool, e.g. `tar`.
+    ///
+    /// # Returns
+    ///
+    /// The tool, wrapped in `Option`.
+    pub fn get_tool(&self, name: &str) -> Result<Option<Tool>> {
+        let tool = self
+            .connection
+            .query_row(
+                "SELECT id, name, section, summary, synopsis, source FROM tools WHERE name = ?1
+                ORDER BY (SELECT COUNT(*) FROM options WHERE options.tool_id = tools.id) DESC, id
+                LIMIT 1",
+                [name],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        Tool {
+                            name: row.get(1)?,
+                            section: row.get(2)?,
+                            summary: row.get(3)?,
+                            synopsis: row.get(4)?,
+                            source: row.get(5)?,
+                            ..Default::default()
+                        },
+                    ))
+                },
+            )
+            .optional()?;
+        let Some((tool_id, mut tool)) = tool else {
+            return Ok(None);
+        };
+
+        let mut stmt = self.connection.prepare(
+            "SELECT flag, description, kind FROM options WHERE tool_id = ?1 ORDER BY id",
+        )?;
+        let mut rows = stmt.query([tool_id])?;
+        while let Some(row) = rows.next()? {
+            let option = ToolOption {
+                flag: row.get(0)?,
+                description: row.get(1)?,
+            };
+            match row.get_ref(2)?.as_str()? {
+                SUBCOMMAND_KIND => tool.subcommands.push(option),
+                _ => tool.options.push(option),
+            }
+        }
+        Ok(Some(tool))
+    }
+
+    /// Searches the options and subcommands of all tools by flag, name or description.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to look for, e.g. `--recursive` or `follow symlinks`.
+    ///
+    /// # Returns
+    ///
+    /// The name of the tool and the option, for every matching option.
+    pub fn search_options(&self, text: &str) -> Result<Vec<(String, ToolOption)>> {
+        let mut stmt = self.connection.prepare(
+            "SELECT tools.name, options.flag, options.description
+            FROM options JOIN tools ON tools.id = options.tool_id
+            WHERE options.flag LIKE '%' || ?1 || '%' OR options.description LIKE '%' || ?1 || '%'
+            ORDER BY options.flag LIKE ?1 || '%' DESC, tools.name, options.id
+            LIMIT 50",
+        )?;
+        let rows = stmt.query_map([text], |row| {
+            Ok((
+                row.get(0)?,
+                ToolOption {
+                    flag: row.get(1)?,
+                    description: row.get(2)?,
+                },
+            ))
+        })?;
+        rows.collect()
+    }
+}
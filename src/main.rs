@@ -1,18 +1,31 @@
-use commands::command_executor::execute_command;
 use commands::command_helper::*;
+use commands::exchange_helper::{
+    export_database, import_aliases, import_cheat, import_database, import_history, import_navi,
+    sync_cheatsheets,
+};
+use commands::favorites::{star_command, tag_command, untag_command};
+use commands::personal_commands::{add_user_command, edit_user_command, remove_user_command};
+use commands::tool_helper::{explain_command, import_help, import_man};
 
 use custom_styling::color_style;
 
-use database::file_parse::_clean_update_database;
+use database::file_parse::archive::ArchiveFormat;
+use database::file_parse::{
+    _clean_update_database, find_pages_root, lint_page_file, page_filepaths, print_parse_errors,
+    update_from_archive, UpdateError, UpdateSummary,
+};
 use database::SqliteDatabase;
 
+use utils::config::Config;
+
 use custom_styling::color_style::style_prompt_text;
 
 use rustyline::config::Configurer;
 use rustyline::error::ReadlineError;
 use rustyline::DefaultEditor;
 
-use std::{env, fs, path::Path};
+use std::path::Path;
+use std::{env, fs, process};
 
 mod commands;
 mod custom_styling;
@@ -20,23 +33,29 @@ mod database;
 mod utils;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    let db = setup_database();
-    if args.len() > 1 {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let db_path = utils::take_leading_option_value(&mut args, "--db");
+    if args.first().map(String::as_str) == Some("--lint") {
+        // no database needed, the exit status tells pre-commit hooks whether the pages are valid
+        let valid = lint_pages(&args[1..].join(" "));
+        process::exit(if valid { 0 } else { 1 });
+    }
+    let config = Config::load();
+    let db = setup_database(db_path.as_deref(), &config);
+    if args.first().map(String::as_str) != Some("--reload") {
+        sync_cheatsheets(&db, &config, false);
+    }
+    if !args.is_empty() {
         // Command line arguments provided
-        let input = args[1..].join(" ");
-        if input == "--update" {
-            update_database(&db);
-        } else {
-            handle_input(&input, &db);
-        }
+        let input = args.join(" ");
+        handle_input(&input, &db, &config);
     } else {
         // No command line arguments, start REPL
-        start_repl(db);
+        start_repl(db, &config);
     }
 }
 
-fn start_repl(db: SqliteDatabase) {
+fn start_repl(db: SqliteDatabase, config: &Config) {
     let mut rl = DefaultEditor::new().unwrap();
     rl.set_max_history_size(100).unwrap();
     loop {
@@ -44,7 +63,7 @@ fn start_repl(db: SqliteDatabase) {
         match input {
             Ok(input) => {
                 rl.add_history_entry(input.as_str()).unwrap();
-                handle_input(&input, &db);
+                handle_input(&input, &db, config);
             }
             Err(ReadlineError::Interrupted | ReadlineError::Eof) => {
                 println!(
@@ -61,25 +80,46 @@ fn start_repl(db: SqliteDatabase) {
     }
 }
 
-fn handle_input(input: &str, db: &SqliteDatabase) {
-    let mut is_meow = false;
-    input.split_once(" ").map(|(first_word, rest_string)| {
-        if first_word == "meow" {
-            is_meow = true;
-            search_in_database(rest_string, db);
-            return;
+fn handle_input(input: &str, db: &SqliteDatabase, config: &Config) {
+    let input = input.trim();
+    let (first_word, rest_string) = input.split_once(' ').unwrap_or((input, ""));
+    match first_word {
+        "meow" if input.contains(' ') => search_commands_and_options(rest_string, db),
+        "--update" => update_database(rest_string, db),
+        "--reload" => sync_cheatsheets(db, config, true),
+        "--lint" => {
+            lint_pages(rest_string);
         }
-    });
-    if !is_meow {
-        handle_command(input, db)
+        "--add" => add_user_command(db),
+        "--edit" => edit_user_command(db),
+        "--remove" => remove_user_command(db),
+        "--star" => star_command(rest_string, true, db),
+        "--unstar" => star_command(rest_string, false, db),
+        "--tag" => tag_command(rest_string, db),
+        "--untag" => untag_command(rest_string, db),
+        "--export" => export_database(rest_string, db),
+        "--import" => import_database(rest_string, db),
+        "--import-navi" => import_navi(rest_string, db),
+        "--import-cheat" => import_cheat(rest_string, db),
+        "--import-aliases" => import_aliases(rest_string, db),
+        "--import-history" => import_history(rest_string, db),
+        "--import-man" => import_man(rest_string, db),
+        "--import-help" => import_help(rest_string, db),
+        "--explain" => explain_command(rest_string, db),
+        _ => handle_command(input, db),
     }
 }
 
-fn update_database(db: &SqliteDatabase) {
-    let folder_path = "./tldr-page";
-    if Path::new(folder_path).is_dir() {
-        fs::remove_dir_all("./tldr-page").unwrap();
+fn update_database(input: &str, db: &SqliteDatabase) {
+    let mut args: Vec<String> = input.split_whitespace().map(String::from).collect();
+    if let Some(path) = utils::take_option_value(&mut args, "--from") {
+        update_database_from(Path::new(&path), db);
+        return;
     }
+
+    let cache_dir = utils::paths::cache_dir();
+    let folder = cache_dir.join("tldr-page");
+    let folder_path = folder.to_str().expect("Cache directory is not valid UTF-8");
     println!(
         "{}",
         color_style::color_light_red("Make sure you have an internet connection and git installed in your System. This operation will download the necessary files in your system")
@@ -88,8 +128,176 @@ fn update_database(db: &SqliteDatabase) {
     if !response {
         return;
     }
-    execute_command("git clone https://github.com/snh1999/tldr-page.git");
-    fs::remove_dir_all("./tldr-page/.git").unwrap();
-    _clean_update_database(db, folder_path);
-    fs::remove_dir_all("./tldr-page").unwrap();
+    if folder.is_dir() {
+        fs::remove_dir_all(&folder).unwrap();
+    }
+    fs::create_dir_all(&cache_dir).expect("Failed to create cache directory");
+    let cloned = process::Command::new("git")
+        .args(["clone", "https://github.com/snh1999/tldr-page.git"])
+        .arg(&folder)
+        .status()
+        .is_ok_and(|status| status.success());
+    if !cloned {
+        println!(
+            "{}",
+            color_style::color_light_red(
+                "Failed to download the pages, the database was left unchanged."
+            )
+        );
+        return;
+    }
+    let revision = git_revision(&folder);
+    let _ = fs::remove_dir_all(folder.join(".git"));
+    print_update_result(_clean_update_database(db, folder_path, &revision));
+    fs::remove_dir_all(&folder).unwrap();
+}
+
+fn update_database_from(path: &Path, db: &SqliteDatabase) {
+    let result = if path.is_dir() {
+        _clean_update_database(
+            db,
+            &find_pages_root(path).to_string_lossy(),
+            &git_revision(path),
+        )
+    } else if let (true, Some(format)) = (path.is_file(), ArchiveFormat::from_path(path)) {
+        let extract_folder = utils::paths::cache_dir().join("tldr-archive");
+        let _ = fs::remove_dir_all(&extract_folder);
+        let result = update_from_archive(db, path, format, &extract_folder);
+        let _ = fs::remove_dir_all(&extract_folder);
+        result
+    } else {
+        println!(
+            "{} is neither a folder nor a .tar.gz, .tgz, .tar or .zip archive.",
+            path.display()
+        );
+        return;
+    };
+    print_update_result(result);
+}
+
+/// Gets the commit a git checkout is at, without failing when git is missing.
+fn git_revision(folder: &Path) -> String {
+    process::Command::new("git")
+        .arg("-C")
+        .arg(folder)
+        .args(["rev-parse", "HEAD"])
+        .stderr(process::Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+        .unwrap_or_default()
+}
+
+/// Checks tldr pages against the format and the placeholder rules,
+/// printing a `file:line: message` line for each problem.
+///
+/// # Arguments
+///
+/// * `input` - A page, or a folder searched recursively for pages.
+///
+/// # Returns
+///
+/// `true` if no problem was found.
+fn lint_pages(input: &str) -> bool {
+    let path = input.trim();
+    if path.is_empty() {
+        println!("Usage: --lint <page.md or folder>");
+        return false;
+    }
+    let filepaths = if Path::new(path).is_dir() {
+        page_filepaths(path)
+    } else if Path::new(path).is_file() {
+        vec![path.to_owned()]
+    } else {
+        println!(
+            "{}",
+            color_style::color_light_red(&format!("{path} does not exist."))
+        );
+        return false;
+    };
+
+    let mut problems = 0;
+    let mut invalid_pages = 0;
+    for filepath in &filepaths {
+        match lint_page_file(filepath) {
+            Ok(errors) if errors.is_empty() => {}
+            Ok(errors) => {
+                print_parse_errors(filepath, &errors);
+                problems += errors.len();
+                invalid_pages += 1;
+            }
+            Err(err) => {
+                println!("{}", color_style::color_light_red(&err.to_string()));
+                problems += 1;
+                invalid_pages += 1;
+            }
+        }
+    }
+
+    if problems == 0 {
+        println!("{} pages checked, no problems found.", filepaths.len());
+        return true;
+    }
+    println!(
+        "{}",
+        color_style::color_light_red(&format!(
+            "{problems} problems in {invalid_pages} of {} pages.",
+            filepaths.len()
+        ))
+    );
+    false
+}
+
+fn print_update_result(result: Result<UpdateSummary, UpdateError>) {
+    match result {
+        Ok(UpdateSummary {
+            updated: 0,
+            removed: 0,
+            ..
+        }) => println!("The database is up to date."),
+        Ok(summary) => println!(
+            "{} pages updated, {} removed ({} examples, {} errors).",
+            summary.updated, summary.removed, summary.examples, summary.errors
+        ),
+        Err(err) => println!(
+            "{}",
+            color_style::color_light_red(&format!(
+                "Update failed, the database was left unchanged: {err}"
+            ))
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lint_status_tells_if_pages_are_valid() {
+        let folder = env::temp_dir().join(format!("cmd-cat-lint-{}", process::id()));
+        let pages = folder.join("pages/common");
+        fs::create_dir_all(&pages).unwrap();
+        let valid_page = pages.join("ls.md");
+        fs::write(
+            &valid_page,
+            "# ls\n\n> List files.\n\n- List all files:\n\n`ls -a`\n",
+        )
+        .unwrap();
+
+        assert!(lint_pages(&folder.to_string_lossy()));
+        assert!(lint_pages(&valid_page.to_string_lossy()));
+
+        fs::write(
+            pages.join("cp.md"),
+            "# copy\n\n> Copy files.\n\n- Copy\n\n`cp`\n",
+        )
+        .unwrap();
+        assert!(!lint_pages(&folder.to_string_lossy()));
+        assert!(lint_pages(&valid_page.to_string_lossy()));
+
+        assert!(!lint_pages(&folder.join("missing").to_string_lossy()));
+        assert!(!lint_pages(""));
+        fs::remove_dir_all(folder).unwrap();
+    }
 }
@@ -0,0 +1,195 @@
+use crate::utils::paths::{config_dir, expand_home};
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::{env, fs, io};
+
+/// File name of the config file, in the config directory.
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// The settings of `config.toml`, every setting is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    /// The folder of TOML and YAML cheatsheets loaded on start-up
+    pub cheatsheet_folder: Option<String>,
+    /// The languages of the tldr pages, most preferred first, e.g. `pt_BR:pt`
+    pub language: Option<String>,
+    /// Whether searches also return the commands of the shell history, `true` by default
+    pub search_history: Option<bool>,
+}
+
+impl Config {
+    /// Loads the config file, the defaults are used if there is none.
+    ///
+    /// # Returns
+    ///
+    /// The config, the defaults along with a warning if the file could not be read.
+    pub fn load() -> Self {
+        let path = config_dir().join(CONFIG_FILE_NAME);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Config::default(),
+            Err(err) => {
+                eprintln!("Failed to read {}: {err}", path.display());
+                return Config::default();
+            }
+        };
+        toml::from_str(&content).unwrap_or_else(|err| {
+            eprintln!("Ignoring {}: {err}", path.display());
+            Config::default()
+        })
+    }
+
+    /// Gets the folder of TOML and YAML cheatsheets, `cheatsheets` in the config directory by default.
+    pub fn cheatsheet_folder(&self) -> PathBuf {
+        match &self.cheatsheet_folder {
+            Some(folder) => expand_home(folder),
+            None => config_dir().join("cheatsheets"),
+        }
+    }
+
+    /// Gets whether searches also return the commands of the shell history, they do by default.
+    pub fn search_history(&self) -> bool {
+        self.search_history.unwrap_or(true)
+    }
+
+    /// Gets the languages tldr pages are searched in, most preferred first.
+    /// They are read from the `language` setting, or else from the environment,
+    /// see `languages_from`.
+    ///
+    /// # Returns
+    ///
+    /// The language codes, as used in the names of the `pages.<language>` folders.
+    pub fn languages(&self) -> Vec<String> {
+        let env_var = |name: &str| env::var(name).ok();
+        languages_from(
+            self.language.as_deref(),
+            env_var("LC_ALL").as_deref(),
+            env_var("LANG").as_deref(),
+            env_var("LANGUAGE").as_deref(),
+        )
+    }
+}
+
+/// Gets the languages tldr pages are searched in, most preferred first.
+/// The `language` setting wins, else the `LANGUAGE` list is used along with `LC_ALL` or `LANG`
+/// (`de_DE.UTF-8` gives `de_DE` and `de`). English always comes last.
+///
+/// # Arguments
+///
+/// * `setting` - The `language` setting, e.g. `pt_BR:pt`.
+/// * `lc_all` - The value of `LC_ALL`.
+/// * `lang` - The value of `LANG`, used when `LC_ALL` is unset or empty.
+/// * `language_list` - The value of `LANGUAGE`, ignored when the locale is unset, `C` or `POSIX`.
+///
+/// # Returns
+///
+/// The language codes, as used in the names of the `pages.<language>` folders.
+fn languages_from(
+    setting: Option<&str>,
+    lc_all: Option<&str>,
+    lang: Option<&str>,
+    language_list: Option<&str>,
+) -> Vec<String> {
+    let locales: Vec<&str> = match setting {
+        Some(language) => vec![language],
+        None => {
+            let locale = lc_all
+                .filter(|locale| !locale.is_empty())
+                .or(lang.filter(|locale| !locale.is_empty()));
+            // like gettext, LANGUAGE is ignored when the locale is unset or `C`
+            match locale.filter(|locale| !matches!(*locale, "C" | "POSIX")) {
+                Some(locale) => language_list
+                    .filter(|list| !list.is_empty())
+                    .into_iter()
+                    .chain([locale])
+                    .collect(),
+                None => Vec::new(),
+            }
+        }
+    };
+
+    let mut languages: Vec<String> = Vec::new();
+    let codes = locales
+        .iter()
+        .flat_map(|locale| locale.split(':'))
+        .map(|locale| locale.split(['.', '@']).next().unwrap_or("").trim())
+        .filter(|code| !code.is_empty() && !matches!(*code, "C" | "POSIX"));
+    for code in codes {
+        let base = code.split_once('_').map(|(base, _)| base);
+        for code in [Some(code), base].into_iter().flatten() {
+            if !languages.iter().any(|language| language == code) {
+                languages.push(code.to_owned());
+            }
+        }
+    }
+    if !languages.iter().any(|language| language == "en") {
+        languages.push("en".to_owned());
+    }
+    languages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn languages_default_to_english() {
+        assert_eq!(languages_from(None, None, None, None), ["en"]);
+        assert_eq!(languages_from(None, Some(""), Some(""), Some("de")), ["en"]);
+    }
+
+    #[test]
+    fn languages_come_from_lc_all_then_lang() {
+        assert_eq!(
+            languages_from(None, Some("de_DE.UTF-8"), Some("fr_FR.UTF-8"), None),
+            ["de_DE", "de", "en"]
+        );
+        assert_eq!(
+            languages_from(None, Some(""), Some("pt_BR.UTF-8@euro"), None),
+            ["pt_BR", "pt", "en"]
+        );
+        assert_eq!(
+            languages_from(None, None, Some("en_US.UTF-8"), None),
+            ["en_US", "en"]
+        );
+    }
+
+    #[test]
+    fn language_list_comes_first() {
+        assert_eq!(
+            languages_from(None, None, Some("de_DE.UTF-8"), Some("fr:es_ES")),
+            ["fr", "es_ES", "es", "de_DE", "de", "en"]
+        );
+        assert_eq!(
+            languages_from(None, None, Some("de_DE"), Some("de:en")),
+            ["de", "en", "de_DE"]
+        );
+    }
+
+    #[test]
+    fn c_and_posix_locales_have_no_language() {
+        assert_eq!(
+            languages_from(None, Some("C"), Some("de_DE"), Some("fr")),
+            ["en"]
+        );
+        assert_eq!(
+            languages_from(None, None, Some("POSIX"), Some("fr")),
+            ["en"]
+        );
+        assert_eq!(languages_from(None, None, Some("C.UTF-8"), None), ["en"]);
+        assert_eq!(
+            languages_from(None, None, Some("de_DE"), Some("C:fr")),
+            ["fr", "de_DE", "de", "en"]
+        );
+    }
+
+    #[test]
+    fn setting_overrides_the_environment() {
+        assert_eq!(
+            languages_from(Some("pt_BR:pt"), Some("de_DE.UTF-8"), None, Some("fr")),
+            ["pt_BR", "pt", "en"]
+        );
+        assert_eq!(languages_from(Some("C"), Some("de_DE"), None, None), ["en"]);
+    }
+}
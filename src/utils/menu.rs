@@ -1,8 +1,9 @@
+use crate::commands::{native_platform, Command, HISTORY_SOURCE, USER_SOURCE};
 use crate::custom_styling::color_style;
 use crate::custom_styling::menu_style::{confirm_render_config, my_render_config};
 use crate::utils::{clear_previous_line, highlight_command, highlight_description};
 
-use inquire::{Confirm, Select};
+use inquire::{Confirm, Select, Text};
 
 /// Gets the confirmation from the user to execute the command.
 ///
@@ -29,12 +30,68 @@ pub fn get_custom_confirmation(prompt_text: &str) -> bool {
         .prompt();
     clear_previous_line();
 
-    let response = match response {
-        Ok(data) => data,
-        Err(_) => false,
-    };
+    response.unwrap_or_default()
+}
+
+/// Gets a line of text from the user with the specified prompt text.
+///
+/// # Arguments
+///
+/// * `prompt_text` - The text to display as the prompt.
+/// * `initial_value` - The editable text the input starts with.
+///
+/// # Returns
+///
+/// The entered text, empty if the prompt was cancelled.
+pub fn get_text_input(prompt_text: &str, initial_value: &str) -> String {
+    let response = Text::new(prompt_text)
+        .with_initial_value(initial_value)
+        .with_render_config(confirm_render_config())
+        .prompt();
+    clear_previous_line();
+
+    response.unwrap_or_default()
+}
 
-    response
+/// Lets the user pick one of the given values, typing filters them.
+///
+/// # Arguments
+///
+/// * `prompt_text` - The text to display as the prompt.
+/// * `values` - The values to choose from.
+/// * `default` - The value selected at first, the first one if it is not among the values.
+///
+/// # Returns
+///
+/// The chosen value, `None` if the prompt was cancelled.
+pub fn get_choice(prompt_text: &str, values: Vec<String>, default: &str) -> Option<String> {
+    let starting_cursor = values
+        .iter()
+        .position(|value| value == default)
+        .unwrap_or(0);
+    let response = Select::new(prompt_text, values)
+        .with_starting_cursor(starting_cursor)
+        .with_render_config(confirm_render_config())
+        .prompt();
+    clear_previous_line();
+
+    response.ok()
+}
+
+/// Gets the order the commands are shown in the menu: starred commands are pinned at the top,
+/// otherwise the order of `matching_commands` is kept.
+///
+/// # Arguments
+///
+/// * `matching_commands` - The vector of matching commands.
+///
+/// # Returns
+///
+/// The indexes of `matching_commands` in menu order.
+fn get_menu_order(matching_commands: &[Command]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..matching_commands.len()).collect();
+    order.sort_by_key(|&index| !matching_commands[index].favorite);
+    order
 }
 
 /// Gets the array of formatted command menu items.
@@ -42,23 +99,66 @@ pub fn get_custom_confirmation(prompt_text: &str) -> bool {
 /// # Arguments
 ///
 /// * `matching_commands` - The vector of matching commands.
+/// * `menu_order` - The indexes of the commands in menu order.
 /// * `input` - The input string to match against.
 ///
 /// # Returns
 ///
 /// A vector of formatted command menu items.
-fn get_command_array(matching_commands: &Vec<(String, String)>, input: &str) -> Vec<String> {
+fn get_command_array(
+    matching_commands: &[Command],
+    menu_order: &[usize],
+    input: &str,
+) -> Vec<String> {
     let mut menu_items: Vec<String> = Vec::new();
-    for (command, description) in matching_commands {
-        let colored_command = highlight_command(&command, input);
-        let colored_description = highlight_description(&description);
+    for &index in menu_order {
+        let command = &matching_commands[index];
+        let colored_command = highlight_command(&command.name, input);
+        let colored_description = highlight_description(&command.description);
+        let star = if command.favorite { "★ " } else { "" };
+        let page_label = page_label(command);
 
-        let menu_item = format!("{}\n  {}", colored_description, colored_command);
+        let menu_item = format!(
+            "{}{} {}\n  {}",
+            star, colored_description, page_label, colored_command
+        );
         menu_items.push(menu_item);
     }
     menu_items
 }
 
+/// Gets the label naming the page (and platform if not common) a command comes from,
+/// commands of another platform than the system's are marked in red.
+///
+/// # Arguments
+///
+/// * `command` - The command to label.
+///
+/// # Returns
+///
+/// The greyed out label followed by the tags, empty for commands without page
+/// (except the user's own and those of the shell history).
+fn page_label(command: &Command) -> String {
+    let platform = command.platform.as_str();
+    let is_foreign = platform != "common" && platform != native_platform();
+    let mut label = match command.page.as_str() {
+        "" if command.source == USER_SOURCE => "[personal]".to_string(),
+        "" if command.source == HISTORY_SOURCE => "[history]".to_string(),
+        "" => String::new(),
+        page if platform == "common" => format!("[{page}]"),
+        page if is_foreign => format!("[{page}, {platform} only]"),
+        page => format!("[{page}, {platform}]"),
+    };
+    for tag in &command.tags {
+        label.push_str(&format!(" #{tag}"));
+    }
+    if is_foreign {
+        color_style::color_light_red(label.trim_start())
+    } else {
+        color_style::color_grey(label.trim_start())
+    }
+}
+
 /// Displays the commands menu and returns the selected index.
 ///
 /// # Arguments
@@ -88,13 +188,13 @@ fn commands_menu(menu_items: Vec<String>) -> Result<usize, i32> {
                 color_style::color_green(description),
                 command
             );
-            return Ok(data.index);
+            Ok(data.index)
         }
         Err(_) => Err(-1),
     }
 }
 
-/// Handles multiple returned commands by displaying a menu and returning the selected index.
+/// Handles multiple returned commands by displaying a menu (starred commands first) and returning the selected index.
 ///
 /// # Arguments
 ///
@@ -103,12 +203,31 @@ fn commands_menu(menu_items: Vec<String>) -> Result<usize, i32> {
 ///
 /// # Returns
 ///
-/// The selected index of the command menu.
+/// The index of the selected command in `matching_commands`.
 pub fn handle_multiple_returned_command(
-    matching_commands: &Vec<(String, String)>,
+    matching_commands: &[Command],
     input: &str,
 ) -> Result<usize, i32> {
-    let menu_items = get_command_array(matching_commands, input);
-    let response = commands_menu(menu_items);
-    response
+    let menu_order = get_menu_order(matching_commands);
+    let menu_items = get_command_array(matching_commands, &menu_order, input);
+    commands_menu(menu_items).map(|choice| menu_order[choice])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starred_commands_are_pinned_at_the_top() {
+        let mut commands: Vec<Command> = ["ls", "cd", "pwd", "cat"]
+            .iter()
+            .map(|name| Command::new(name, ""))
+            .collect();
+        commands[1].favorite = true;
+        commands[3].favorite = true;
+
+        assert_eq!(get_menu_order(&commands), [1, 3, 0, 2]);
+        assert_eq!(get_menu_order(&commands[..1]), [0]);
+        assert!(get_menu_order(&[]).is_empty());
+    }
 }
@@ -1,9 +1,18 @@
-use crate::commands::command_executor::execute_command;
+use crate::commands::command_executor::{command_output, execute_command};
+use crate::commands::tool_helper::print_flag_explanations;
+use crate::commands::{Command, Variable, VariableKind};
 use crate::custom_styling::color_style;
 use crate::custom_styling::menu_style::confirm_render_config;
+use crate::database::file_parse::tldr::{self, Segment};
+use crate::database::SqliteDatabase;
+use inquire::validator::Validation;
 use inquire::Text;
+use std::collections::HashMap;
+use std::env;
 
+pub mod config;
 pub mod menu;
+pub mod paths;
 
 /// Highlights the command by applying color formatting to the input matching parts.
 ///
@@ -36,24 +45,35 @@ pub fn highlight_description(description: &str) -> String {
 }
 
 /// Executes the current command after getting confirmation from the user.
+/// Executions of stored commands are recorded for frecency ranking.
 ///
 /// # Arguments
 ///
-/// * `command` - The command to execute.
-/// * `description` - The description of the command.
-pub fn execute_current_command(command: &str, description: &str) {
+/// * `command_line` - The command to execute, with the placeholders filled in.
+/// * `command` - The stored command it was built from.
+/// * `db` - The database instance.
+pub fn execute_current_command(command_line: &str, command: &Command, db: &SqliteDatabase) {
     println!(
         " {} {}",
-        color_style::color_green(description),
-        color_style::color_light_red(command)
+        color_style::color_green(&command.description),
+        color_style::color_light_red(command_line)
     );
+    print_flag_explanations(command_line, db);
 
     let confirmation = menu::get_confirmation();
     // clear_previous_line();
 
     if confirmation {
         println!("\x1B[1A");
-        execute_command(command);
+        let cwd = env::current_dir()
+            .map(|dir| dir.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let exit_status = execute_command(command_line);
+        if command.id != 0 {
+            if let Err(err) = db.record_execution(command.id, &cwd, exit_status) {
+                eprintln!("Failed to record the execution: {err}");
+            }
+        }
     } else {
         println!()
     }
@@ -64,14 +84,18 @@ pub fn execute_current_command(command: &str, description: &str) {
 /// # Arguments
 ///
 /// * `command` - The chosen command.
-/// * `description` - The description of the command.
-pub fn check_chosen_command(command: &str, description: &str) {
-    let command = _replace_input_string(command);
+/// * `db` - The database instance.
+pub fn check_chosen_command(command: &Command, db: &SqliteDatabase) {
+    let variables = match command.id {
+        0 => command.variables.clone(),
+        id => db.get_variables(id).unwrap_or_default(),
+    };
+    let command_line = _replace_input_string(&command.name, &variables);
 
-    if command.is_empty() {
+    if command_line.is_empty() {
         return;
     }
-    execute_current_command(&command, description)
+    execute_current_command(&command_line, command, db)
 }
 
 /// Clears the lines of previous selections- aka description and command
@@ -81,58 +105,120 @@ fn clear_selection_lines() {
     clear_previous_line(); // actual command
 }
 
-/// Replaces the input string with user-provided values or prompts for input.
+/// Replaces the placeholders of the input string with user-provided values or prompts for input.
+/// Option placeholders (`{{[-o|--output]}}`) are filled in with their long form, and placeholders
+/// followed by a value (`{{placeholder}}[value]`, from the progressive search) use that value.
+/// Placeholders with a variable (from navi cheatsheets) are chosen from the lines its command prints,
+/// where `<name>` is replaced by the value given to the `name` placeholder before.
 ///
 /// # Arguments
 ///
 /// * `input_string` - The input string to replace.
+/// * `variables` - The variables of the command.
 ///
 /// # Returns
 ///
-/// The replaced input string.
-fn _replace_input_string(input_string: &str) -> String {
+/// The replaced input string, empty if the user did not fill in a placeholder.
+fn _replace_input_string(input_string: &str, variables: &[Variable]) -> String {
     let mut replaced_string = String::new();
-    let mut start = 0;
+    let mut values: HashMap<String, String> = HashMap::new();
+    let mut segments = tldr::parse_command(input_string).into_iter().peekable();
 
-    while let Some(start_delim) = input_string[start..].find("{{") {
-        let start_index = start + start_delim;
-        let mut input: String = String::new();
+    while let Some(segment) = segments.next() {
+        let placeholder = match segment {
+            Segment::Text(text) => {
+                replaced_string += &text;
+                continue;
+            }
+            Segment::Placeholder(placeholder) => placeholder,
+        };
 
-        if let Some(end_delim) = input_string[start_index..].find("}}") {
-            let mut end_index = start_index + end_delim + 2;
-            if end_index + 1 < input_string.len() && &input_string[end_index..end_index + 1] == "["
+        if let Some(Segment::Text(next_text)) = segments.peek_mut() {
+            if let Some((value, rest)) = next_text
+                .strip_prefix('[')
+                .and_then(|text| text.split_once(']'))
             {
-                if let Some(end_val) = input_string[end_index + 1..].find("]") {
-                    let end_val_index = end_index + 1 + end_val;
-                    input = String::from(&input_string[end_index + 1..end_val_index]);
-                    end_index = end_val_index + 1;
-                }
-            } else {
-                let word = &input_string[start_index + 2..end_index - 2];
-                let replaced_word = _replace_word(word);
-
-                input = _get_user_input(format!("Enter '{}': ", replaced_word));
-                clear_previous_line();
-                if input.is_empty() {
-                    return input;
-                }
+                replaced_string += value;
+                *next_text = rest.to_owned();
+                continue;
             }
+        }
 
-            replaced_string += &input_string[start..start_index];
-            replaced_string += &input;
+        if let Some(option) = placeholder.preferred_option() {
+            replaced_string += option;
+            continue;
+        }
+
+        let name = placeholder.to_string();
+        if let Some(value) = values.get(&name) {
+            replaced_string += value;
+            continue;
+        }
 
-            start = end_index;
-        } else {
-            replaced_string += &input_string[start..];
-            break;
+        let prompt = format!("Enter '{}': ", _replace_word(&name));
+        let input = match variables.iter().find(|variable| variable.name == name) {
+            Some(variable) => _fill_variable(variable, &prompt, &values),
+            None => _get_user_input(&prompt, &Variable::default()),
+        };
+        if input.is_empty() {
+            return input;
         }
+        replaced_string += &input;
+        values.insert(name, input);
     }
     clear_selection_lines();
 
-    replaced_string += &input_string[start..]; // Append the remaining portion of the input string
     replaced_string
 }
 
+/// Asks the user for the value of a variable, picked from its choices or the lines printed by
+/// its command when it has some, typed otherwise.
+///
+/// # Arguments
+///
+/// * `variable` - The variable.
+/// * `prompt` - The prompt text.
+/// * `values` - The values of the placeholders filled in so far.
+///
+/// # Returns
+///
+/// The value, empty if the user cancelled.
+fn _fill_variable(variable: &Variable, prompt: &str, values: &HashMap<String, String>) -> String {
+    let choices = match variable.kind {
+        VariableKind::Choice => variable.choices.clone(),
+        VariableKind::Command => _generate_values(&variable.generator, values),
+        VariableKind::Text | VariableKind::Number => Vec::new(),
+    };
+    if choices.is_empty() {
+        _get_user_input(prompt, variable)
+    } else {
+        menu::get_choice(prompt, choices, &variable.default).unwrap_or_default()
+    }
+}
+
+/// Runs the command of a variable to get the values it can take.
+///
+/// # Arguments
+///
+/// * `generator` - The command, e.g. `git branch | cut -c 3-`.
+/// * `values` - The values of the placeholders filled in so far, replacing their `<name>`.
+///
+/// # Returns
+///
+/// The non-empty lines printed by the command.
+fn _generate_values(generator: &str, values: &HashMap<String, String>) -> Vec<String> {
+    let mut generator = generator.to_owned();
+    for (name, value) in values {
+        generator = generator.replace(&format!("<{name}>"), value);
+    }
+    command_output(&generator)
+        .unwrap_or_default()
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(String::from)
+        .collect()
+}
+
 /// Replaces the word by formatting and applying color styling.
 ///
 /// # Arguments
@@ -147,24 +233,74 @@ fn _replace_word(word: &str) -> String {
     color_style::color_light_cyan(&text)
 }
 
-/// Prompts the user for input and returns the entered text.
+/// Prompts the user for the value of a placeholder, numbers are checked as they are typed.
 ///
 /// # Arguments
 ///
 /// * `message` - The message to display as the input prompt.
+/// * `variable` - The variable of the placeholder, giving the default value and the kind.
+///
+/// # Returns
+///
+/// The user-entered text, the default if nothing was entered, empty if the user cancelled.
+fn _get_user_input(message: &str, variable: &Variable) -> String {
+    let mut prompt = Text::new(message).with_render_config(confirm_render_config());
+    if !variable.default.is_empty() {
+        prompt = prompt.with_default(&variable.default);
+    }
+    if variable.kind == VariableKind::Number {
+        prompt = prompt.with_validator(|input: &str| {
+            Ok(match input.parse::<f64>() {
+                Ok(_) => Validation::Valid,
+                Err(_) => Validation::Invalid("Enter a number".into()),
+            })
+        });
+    }
+    let input = prompt.prompt().unwrap_or_default();
+    clear_previous_line();
+    input
+}
+
+/// Removes an option and its value (`--name value` or `--name=value`) from the arguments.
+///
+/// # Arguments
+///
+/// * `args` - The command line arguments, without the program name.
+/// * `name` - The option name, e.g. `--db`.
 ///
 /// # Returns
 ///
-/// The user-entered text.
-fn _get_user_input(message: String) -> String {
-    let name = Text::new(&message)
-        .with_render_config(confirm_render_config())
-        .prompt();
+/// The value of the option, if it was given.
+pub fn take_option_value(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let prefix = format!("{name}=");
+    let index = args
+        .iter()
+        .position(|arg| arg == name || arg.starts_with(&prefix))?;
+    let arg = args.remove(index);
+    match arg.strip_prefix(&prefix) {
+        Some(value) => Some(value.to_owned()),
+        None if index < args.len() => Some(args.remove(index)),
+        None => None,
+    }
+}
 
-    match name {
-        Ok(name) => name,
-        Err(_) => "".to_owned(),
+/// Removes an option and its value if they start the arguments, like `take_option_value`.
+/// Options given later, e.g. within the words of a search, are left alone.
+///
+/// # Arguments
+///
+/// * `args` - The command line arguments, without the program name.
+/// * `name` - The option name, e.g. `--db`.
+///
+/// # Returns
+///
+/// The value of the option, if the arguments start with it.
+pub fn take_leading_option_value(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let first = args.first()?;
+    if first != name && !first.starts_with(&format!("{name}=")) {
+        return None;
     }
+    take_option_value(args, name)
 }
 
 /// Clears the previous line in the console output.
@@ -172,3 +308,49 @@ pub fn clear_previous_line() {
     println!("\x1B[1A\x1B[2K"); // Move up one line and clear the line
     print!("\x1B[1A");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn takes_options_anywhere() {
+        let mut export = args("team.json --tag k8s --format=toml");
+        assert_eq!(
+            take_option_value(&mut export, "--format").as_deref(),
+            Some("toml")
+        );
+        assert_eq!(
+            take_option_value(&mut export, "--tag").as_deref(),
+            Some("k8s")
+        );
+        assert_eq!(take_option_value(&mut export, "--page"), None);
+        assert_eq!(export, ["team.json"]);
+    }
+
+    #[test]
+    fn takes_the_database_only_before_the_command() {
+        let mut search = args("--db team.db meow tar");
+        assert_eq!(
+            take_leading_option_value(&mut search, "--db").as_deref(),
+            Some("team.db")
+        );
+        assert_eq!(search, ["meow", "tar"]);
+
+        let mut search = args("--db=team.db --add");
+        assert_eq!(
+            take_leading_option_value(&mut search, "--db").as_deref(),
+            Some("team.db")
+        );
+        assert_eq!(search, ["--add"]);
+
+        let mut search = args("meow sqlite3 --db file.db");
+        assert_eq!(take_leading_option_value(&mut search, "--db"), None);
+        assert_eq!(search, ["meow", "sqlite3", "--db", "file.db"]);
+        assert_eq!(take_leading_option_value(&mut Vec::new(), "--db"), None);
+    }
+}
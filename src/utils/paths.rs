@@ -0,0 +1,237 @@
+use std::env;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+/// Environment variable overriding the database location.
+pub const DATABASE_ENV_VAR: &str = "CMD_CAT_DB";
+
+/// File name of the database, in the data directory and next to released binaries.
+pub const DATABASE_FILE_NAME: &str = "commands.db";
+
+/// Resolves an XDG base directory.
+///
+/// # Arguments
+///
+/// * `xdg_var` - The XDG environment variable, e.g. `XDG_DATA_HOME`.
+/// * `home_fallback` - The directory relative to `$HOME` used when the variable is unset.
+///
+/// # Returns
+///
+/// The base directory, `None` if `$HOME` is unset as well.
+fn xdg_base_dir(xdg_var: &str, home_fallback: &str) -> Option<PathBuf> {
+    xdg_base_dir_from(env::var_os(xdg_var), env::var_os("HOME"), home_fallback)
+}
+
+/// Resolves an XDG base directory from the values of the environment variables,
+/// a relative XDG directory is ignored as the specification asks.
+///
+/// # Arguments
+///
+/// * `xdg_dir` - The value of the XDG environment variable.
+/// * `home` - The value of `$HOME`.
+/// * `home_fallback` - The directory relative to `$HOME` used when the variable is unset.
+///
+/// # Returns
+///
+/// The base directory, `None` if `$HOME` is unset as well.
+fn xdg_base_dir_from(
+    xdg_dir: Option<OsString>,
+    home: Option<OsString>,
+    home_fallback: &str,
+) -> Option<PathBuf> {
+    xdg_dir
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| home.map(|home| PathBuf::from(home).join(home_fallback)))
+}
+
+/// Resolves an XDG base directory for cmd-cat.
+///
+/// # Arguments
+///
+/// * `xdg_var` - The XDG environment variable, e.g. `XDG_DATA_HOME`.
+/// * `home_fallback` - The directory relative to `$HOME` used when the variable is unset.
+///
+/// # Returns
+///
+/// The `cmd-cat` directory inside the base directory, or the current directory if `$HOME` is unset as well.
+fn xdg_dir(xdg_var: &str, home_fallback: &str) -> PathBuf {
+    match xdg_base_dir(xdg_var, home_fallback) {
+        Some(dir) => dir.join("cmd-cat"),
+        None => PathBuf::from("."),
+    }
+}
+
+/// Gets the data directory, `$XDG_DATA_HOME/cmd-cat` (`~/.local/share/cmd-cat` by default).
+pub fn data_dir() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME", ".local/share")
+}
+
+/// Gets the cache directory, `$XDG_CACHE_HOME/cmd-cat` (`~/.cache/cmd-cat` by default).
+pub fn cache_dir() -> PathBuf {
+    xdg_dir("XDG_CACHE_HOME", ".cache")
+}
+
+/// Gets the config directory, `$XDG_CONFIG_HOME/cmd-cat` (`~/.config/cmd-cat` by default).
+pub fn config_dir() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
+
+/// Expands a leading `~` of a path given by the user to the home directory.
+///
+/// # Arguments
+///
+/// * `path` - The path, e.g. `~/team-commands`.
+///
+/// # Returns
+///
+/// The expanded path, unchanged if it does not start with `~` or `$HOME` is unset.
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), env::var_os("HOME")) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            PathBuf::from(home).join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Gets the cheatsheet directory of `cheat`, `$XDG_CONFIG_HOME/cheat/cheatsheets`
+/// (`~/.config/cheat/cheatsheets` by default).
+pub fn cheatsheets_dir() -> Option<PathBuf> {
+    xdg_base_dir("XDG_CONFIG_HOME", ".config").map(|dir| dir.join("cheat").join("cheatsheets"))
+}
+
+/// Gets the shell startup files aliases are imported from: `~/.bashrc`, `~/.bash_aliases`,
+/// `~/.zshrc` (in `$ZDOTDIR` if set) and the fish configuration folder, `$XDG_CONFIG_HOME/fish`.
+///
+/// # Returns
+///
+/// The files and folders that exist.
+pub fn shell_startup_paths() -> Vec<PathBuf> {
+    let home = env::var_os("HOME").map(PathBuf::from);
+    let zdotdir = env::var_os("ZDOTDIR").map(PathBuf::from).or(home.clone());
+    let mut paths: Vec<PathBuf> = home
+        .iter()
+        .flat_map(|home| [home.join(".bashrc"), home.join(".bash_aliases")])
+        .collect();
+    paths.extend(zdotdir.map(|dir| dir.join(".zshrc")));
+    paths.extend(xdg_base_dir("XDG_CONFIG_HOME", ".config").map(|dir| dir.join("fish")));
+    paths.retain(|path| path.exists());
+    paths
+}
+
+/// Gets the shell history files: `~/.bash_history`, `~/.zsh_history` (in `$ZDOTDIR` if set)
+/// and fish's `$XDG_DATA_HOME/fish/fish_history`.
+///
+/// # Returns
+///
+/// The files that exist.
+pub fn shell_history_paths() -> Vec<PathBuf> {
+    let home = env::var_os("HOME").map(PathBuf::from);
+    let zdotdir = env::var_os("ZDOTDIR").map(PathBuf::from).or(home.clone());
+    let mut paths: Vec<PathBuf> = home.iter().map(|home| home.join(".bash_history")).collect();
+    paths.extend(zdotdir.map(|dir| dir.join(".zsh_history")));
+    paths.extend(
+        xdg_base_dir("XDG_DATA_HOME", ".local/share")
+            .map(|dir| dir.join("fish").join("fish_history")),
+    );
+    paths.retain(|path| path.is_file());
+    paths
+}
+
+/// Gets the database path chosen by the user, the `--db` argument takes precedence over `CMD_CAT_DB`.
+///
+/// # Arguments
+///
+/// * `cli_path` - The value of the `--db` argument, if given.
+///
+/// # Returns
+///
+/// The chosen path, `None` if the default location should be used.
+pub fn database_path_override(cli_path: Option<&str>) -> Option<PathBuf> {
+    database_path_override_from(cli_path, env::var_os(DATABASE_ENV_VAR))
+}
+
+/// Gets the database path chosen by the user from the `--db` argument and the value of `CMD_CAT_DB`.
+///
+/// # Arguments
+///
+/// * `cli_path` - The value of the `--db` argument, if given.
+/// * `env_path` - The value of `CMD_CAT_DB`, if set.
+///
+/// # Returns
+///
+/// The chosen path, `None` if neither is given or the one given is empty.
+fn database_path_override_from(
+    cli_path: Option<&str>,
+    env_path: Option<OsString>,
+) -> Option<PathBuf> {
+    cli_path
+        .map(PathBuf::from)
+        .or_else(|| env_path.map(PathBuf::from))
+        .filter(|path| !path.as_os_str().is_empty())
+}
+
+/// Gets the default database path inside the data directory.
+pub fn default_database_path() -> PathBuf {
+    data_dir().join(DATABASE_FILE_NAME)
+}
+
+/// Gets the places a pre-built database might have been put before the data directory existed:
+/// the current directory (older versions kept it there) and the directory of the executable
+/// (where the release archives and the AppImage ship it).
+///
+/// # Returns
+///
+/// The candidate paths, in order of preference. They may not exist.
+pub fn seed_database_candidates() -> Vec<PathBuf> {
+    let mut candidates = vec![PathBuf::from(DATABASE_FILE_NAME)];
+    if let Some(exe_dir) = env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.to_path_buf()))
+    {
+        candidates.push(exe_dir.join(DATABASE_FILE_NAME));
+    }
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn os(value: &str) -> Option<OsString> {
+        Some(OsString::from(value))
+    }
+
+    #[test]
+    fn xdg_dirs_fall_back_to_home() {
+        assert_eq!(
+            xdg_base_dir_from(os("/data"), os("/home/me"), ".local/share"),
+            Some(PathBuf::from("/data"))
+        );
+        assert_eq!(
+            xdg_base_dir_from(None, os("/home/me"), ".local/share"),
+            Some(PathBuf::from("/home/me/.local/share"))
+        );
+        assert_eq!(
+            xdg_base_dir_from(os("data"), os("/home/me"), ".cache"),
+            Some(PathBuf::from("/home/me/.cache"))
+        );
+        assert_eq!(xdg_base_dir_from(os(""), None, ".cache"), None);
+    }
+
+    #[test]
+    fn database_argument_wins_over_the_environment() {
+        assert_eq!(
+            database_path_override_from(Some("cli.db"), os("env.db")),
+            Some(PathBuf::from("cli.db"))
+        );
+        assert_eq!(
+            database_path_override_from(None, os("env.db")),
+            Some(PathBuf::from("env.db"))
+        );
+        assert_eq!(database_path_override_from(None, None), None);
+        assert_eq!(database_path_override_from(None, os("")), None);
+        assert_eq!(database_path_override_from(Some(""), os("env.db")), None);
+    }
+}